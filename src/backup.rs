@@ -0,0 +1,207 @@
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::sql_connection_factory::SqlConnectionFactory;
+use crate::task_repo::{TaskRepo, TaskRepoError};
+
+// Backups are disabled unless a directory is configured
+const TASKER_BACKUP_DIR_ENV_VAR: &str = "TASKER_BACKUP_DIR";
+const TASKER_BACKUP_INTERVAL_ENV_VAR: &str = "TASKER_BACKUP_INTERVAL_SECS";
+const TASKER_BACKUP_RETENTION_ENV_VAR: &str = "TASKER_BACKUP_RETENTION";
+const TASKER_DEFAULT_BACKUP_INTERVAL_SECS: u64 = 3600;
+const TASKER_DEFAULT_BACKUP_RETENTION: usize = 7;
+
+const BACKUP_FILE_PREFIX: &str = "tasks-";
+const BACKUP_FILE_SUFFIX: &str = ".db";
+
+#[derive(Clone)]
+pub struct BackupConfig {
+    pub directory: PathBuf,
+    pub interval: Duration,
+    pub retention: usize, // Number of backups to keep
+}
+
+impl BackupConfig {
+    pub fn from_env() -> Option<BackupConfig> {
+        let directory = env::var(TASKER_BACKUP_DIR_ENV_VAR).ok()?;
+        let interval = positive_or_default(
+            TASKER_BACKUP_INTERVAL_ENV_VAR,
+            env::var(TASKER_BACKUP_INTERVAL_ENV_VAR).ok(),
+            TASKER_DEFAULT_BACKUP_INTERVAL_SECS,
+        );
+        let retention = positive_or_default(
+            TASKER_BACKUP_RETENTION_ENV_VAR,
+            env::var(TASKER_BACKUP_RETENTION_ENV_VAR).ok(),
+            TASKER_DEFAULT_BACKUP_RETENTION,
+        );
+
+        Some(BackupConfig {
+            directory: directory.into(),
+            interval: Duration::from_secs(interval),
+            retention,
+        })
+    }
+}
+
+// Backups cannot be taken continuously, nor all be deleted right away: zero, like anything but a
+// number, falls back to the default
+fn positive_or_default<T>(var_name: &str, value: Option<String>, default: T) -> T
+where
+    T: FromStr + PartialOrd + From<u8> + Display,
+{
+    let Some(value) = value else {
+        return default;
+    };
+    match value.parse::<T>() {
+        Ok(parsed) if parsed >= T::from(1) => parsed,
+        _ => {
+            tracing::warn!(
+                "{} should be a positive number, got {:?}: using {} instead",
+                var_name,
+                value,
+                default
+            );
+            default
+        }
+    }
+}
+
+// Copies the whole database into a new timestamped file of the backup directory, then deletes
+// the oldest backups so that only `retention` of them remain. Returns the path of the new backup.
+pub fn backup_database(
+    connection_factory: Arc<dyn SqlConnectionFactory>,
+    directory: &Path,
+    retention: usize,
+) -> Result<PathBuf, TaskRepoError> {
+    fs::create_dir_all(directory)?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time should be after epoch")
+        .as_millis();
+    let backup_path = directory.join(format!(
+        "{BACKUP_FILE_PREFIX}{timestamp}{BACKUP_FILE_SUFFIX}"
+    ));
+    TaskRepo::new(connection_factory).backup_to(&backup_path)?;
+
+    // Timestamps have the same number of digits, so backups sort chronologically
+    let mut backups: Vec<PathBuf> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(BACKUP_FILE_PREFIX) && name.ends_with(BACKUP_FILE_SUFFIX)
+                })
+        })
+        .collect();
+    backups.sort();
+    let backups_to_delete = backups.len().saturating_sub(retention);
+    for old_backup in &backups[..backups_to_delete] {
+        fs::remove_file(old_backup)?;
+    }
+
+    Ok(backup_path)
+}
+
+pub async fn run_periodic_backups(
+    connection_factory: Arc<dyn SqlConnectionFactory>,
+    config: BackupConfig,
+) {
+    let mut interval = tokio::time::interval(config.interval);
+    loop {
+        interval.tick().await;
+
+        let connection_factory = connection_factory.clone();
+        let directory = config.directory.clone();
+        let retention = config.retention;
+        let result = tokio::task::spawn_blocking(move || {
+            backup_database(connection_factory, &directory, retention)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(backup_path)) => {
+                tracing::info!("Database backed up to {}", backup_path.display())
+            }
+            Ok(Err(error)) => tracing::error!("Database backup failed: {}", error),
+            Err(error) => tracing::error!("Database backup task failed: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rusqlite::Connection;
+    use tempfile::tempdir;
+
+    use crate::sql_connection_factory::tests::TempDirSqliteConnectionFactory;
+    use crate::task::{LETTERS, Task};
+
+    #[test]
+    fn backup_is_a_valid_copy() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory.clone());
+        task_repo.init_db()?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Important task", None)?)?;
+
+        let backup_dir = tempdir()?;
+        let backup_path = backup_database(connection_factory.clone(), backup_dir.path(), 2)?;
+
+        // The backup can be opened and contains the tasks
+        let backup_conn = Connection::open(&backup_path)?;
+        let description: String =
+            backup_conn.query_row("SELECT description FROM tasks", [], |row| row.get(0))?;
+        assert_eq!(description, "Important task");
+
+        Ok(())
+    }
+
+    #[test]
+    fn non_positive_settings_fall_back_to_default() {
+        for (value, expected) in [
+            (None, 7),
+            (Some("3"), 3),
+            (Some("0"), 7),
+            (Some("-1"), 7),
+            (Some("often"), 7),
+        ] {
+            assert_eq!(
+                positive_or_default("SETTING", value.map(String::from), 7_usize),
+                expected,
+                "{value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn old_backups_are_deleted() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+        TaskRepo::new(connection_factory.clone()).init_db()?;
+
+        let backup_dir = tempdir()?;
+        let mut backup_paths = vec![];
+        for _ in 0..3 {
+            backup_paths.push(backup_database(
+                connection_factory.clone(),
+                backup_dir.path(),
+                2,
+            )?);
+            std::thread::sleep(Duration::from_millis(2)); // Ensures distinct timestamps
+        }
+
+        // Only the two most recent backups are kept
+        assert!(!backup_paths[0].exists());
+        assert!(backup_paths[1].exists());
+        assert!(backup_paths[2].exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::env;
+use std::fmt;
+
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+// Logs are human-readable unless set to "json"
+const TASKER_LOG_FORMAT_ENV_VAR: &str = "TASKER_LOG_FORMAT";
+// Only plain levels are supported (e.g. "debug"), not per-target directives
+const RUST_LOG_ENV_VAR: &str = "RUST_LOG";
+const TASKER_DEFAULT_LOG_LEVEL: Level = Level::INFO;
+
+pub fn init_tracing() {
+    let max_level = env::var(RUST_LOG_ENV_VAR)
+        .ok()
+        .and_then(|val| val.trim().parse::<Level>().ok())
+        .unwrap_or(TASKER_DEFAULT_LOG_LEVEL);
+    let subscriber = tracing_subscriber::fmt().with_max_level(max_level);
+
+    match env::var(TASKER_LOG_FORMAT_ENV_VAR).as_deref() {
+        Ok("json") => subscriber.event_format(JsonLines).init(),
+        _ => subscriber.init(),
+    }
+}
+
+// One JSON object per line, e.g.
+// {"timestamp":"...","level":"INFO","target":"tasker","spans":["request"],"fields":{"message":"..."}}
+pub struct JsonLines;
+
+impl<S, N> FormatEvent<S, N> for JsonLines
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut fields = JsonFields(Map::new());
+        event.record(&mut fields);
+
+        // From the outermost span to the innermost one
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .map(|scope| scope.from_root().map(|span| span.name().into()).collect())
+            .unwrap_or_default();
+
+        let metadata = event.metadata();
+        let mut line = Map::new();
+        line.insert("timestamp".into(), chrono::Utc::now().to_rfc3339().into());
+        line.insert("level".into(), metadata.level().to_string().into());
+        line.insert("target".into(), metadata.target().into());
+        line.insert("spans".into(), spans.into());
+        line.insert("fields".into(), fields.0.into());
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+// Keeps the type of fields where JSON has one, uses their debug representation otherwise
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().into(), format!("{value:?}").into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    // Collects all logs, to be checked once done
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines() {
+        let buffer = SharedBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .event_format(JsonLines)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("request").entered();
+            tracing::info!(task_id = 42, completed = true, "Task \"updated\"");
+        });
+
+        let logs = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = logs.lines().collect();
+        assert_eq!(lines.len(), 1);
+
+        let line: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["spans"], serde_json::json!(["request"]));
+        assert_eq!(line["fields"]["message"], "Task \"updated\"");
+        assert_eq!(line["fields"]["task_id"], 42);
+        assert_eq!(line["fields"]["completed"], true);
+        assert!(line["timestamp"].is_string());
+    }
+}
@@ -1,28 +1,81 @@
 use std::env;
+use std::future::pending;
+use std::net::SocketAddr;
+use std::num::NonZeroU32;
+use std::str::FromStr;
 use std::sync::Arc;
 
+mod backup;
+mod logging;
+mod note;
 mod presets;
+mod rate_limit;
+mod saved_view;
 mod sql_connection_factory;
+mod stats;
 mod task;
+mod task_changes;
 mod task_repo;
+mod theme;
 mod webapp;
 
+use axum::Router;
+use tokio::net::TcpListener;
 use tokio::signal;
 
-use crate::sql_connection_factory::SqliteConnectionFactory;
+use crate::backup::{BackupConfig, run_periodic_backups};
+use crate::rate_limit::RateLimiter;
+use crate::sql_connection_factory::{
+    PooledSqliteConnectionFactory, SQLITE_URL, SqlConnectionFactory, SqliteConnectionFactory,
+};
+use crate::task::{PriorityCapMode, TaskError};
 use crate::task_repo::{TaskRepo, TaskRepoError};
+use crate::theme::Theme;
 use crate::webapp::{AppState, build_app};
 
+const TASKER_BIND_ENV_VAR: &str = "TASKER_BIND";
 const TASKER_PORT_ENV_VAR: &str = "TASKER_PORT";
-const TASKER_DEFAULT_PORT: i32 = 3000;
+const TASKER_DEFAULT_PORT: u16 = 3000;
+const TASKER_ADMIN_TOKEN_ENV_VAR: &str = "TASKER_ADMIN_TOKEN";
+const TASKER_CLEANUP_KEEP_MIN_ENV_VAR: &str = "TASKER_CLEANUP_KEEP_MIN";
+const TASKER_DEFAULT_DUE_OFFSET_DAYS_ENV_VAR: &str = "TASKER_DEFAULT_DUE_OFFSET_DAYS";
+const TASKER_PRESETS_ENABLED_ENV_VAR: &str = "TASKER_PRESETS_ENABLED";
+const TASKER_PRIORITIES_ENV_VAR: &str = "TASKER_PRIORITIES";
+const TASKER_MAX_ASSIGNABLE_PRIORITY_ENV_VAR: &str = "TASKER_MAX_ASSIGNABLE_PRIORITY";
+const TASKER_PRIORITY_CAP_MODE_ENV_VAR: &str = "TASKER_PRIORITY_CAP_MODE";
+const TASKER_THEME_ENV_VAR: &str = "TASKER_THEME";
+const TASKER_RATE_LIMIT_ENV_VAR: &str = "TASKER_RATE_LIMIT_PER_MINUTE";
+const TASKER_CONNECTION_POOL_SIZE: u32 = 8;
 
-#[allow(dead_code)] // Rust has no way to know where this is used
-#[derive(Debug)]
 enum ApplicativeError {
     TaskRepoError(TaskRepoError),
     IoError(std::io::Error),
+    ConnectionPool(r2d2::Error),
+    InvalidConfiguration(TaskError),
+    InvalidSetting(String),
 }
 
+impl std::fmt::Display for ApplicativeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::TaskRepoError(error) => write!(f, "Storage error: {}", error),
+            Self::IoError(error) => write!(f, "I/O error: {}", error),
+            Self::ConnectionPool(error) => write!(f, "Connection pool error: {}", error),
+            Self::InvalidConfiguration(error) => write!(f, "Invalid configuration: {}", error),
+            Self::InvalidSetting(message) => write!(f, "Invalid configuration: {}", message),
+        }
+    }
+}
+
+// Errors returned by `main` are printed with `Debug`, which should be as readable as `Display`
+impl std::fmt::Debug for ApplicativeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ApplicativeError {}
+
 impl From<TaskRepoError> for ApplicativeError {
     fn from(value: TaskRepoError) -> Self {
         ApplicativeError::TaskRepoError(value)
@@ -35,59 +88,263 @@ impl From<std::io::Error> for ApplicativeError {
     }
 }
 
+impl From<TaskError> for ApplicativeError {
+    fn from(value: TaskError) -> Self {
+        ApplicativeError::InvalidConfiguration(value)
+    }
+}
+
+impl From<r2d2::Error> for ApplicativeError {
+    fn from(value: r2d2::Error) -> Self {
+        ApplicativeError::ConnectionPool(value)
+    }
+}
+
 #[tokio::main]
-async fn main() -> Result<(), ApplicativeError> {
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    Ok(run().await?)
+}
+
+async fn run() -> Result<(), ApplicativeError> {
     // initialize tracing
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::DEBUG)
-        .init();
+    logging::init_tracing();
 
     // Database setup
     TaskRepo::new(Arc::new(SqliteConnectionFactory {})).init_db()?;
 
+    // Connections are shared by all requests and background jobs
+    let connection_factory: Arc<dyn SqlConnectionFactory> = Arc::new(
+        PooledSqliteConnectionFactory::new(SQLITE_URL, TASKER_CONNECTION_POOL_SIZE)?,
+    );
+
+    // Periodic backups, if configured
+    let backup_config = BackupConfig::from_env();
+    if let Some(backup_config) = backup_config.clone() {
+        tokio::spawn(run_periodic_backups(
+            connection_factory.clone(),
+            backup_config,
+        ));
+    }
+
     // Routing setup
-    let app_state = AppState {
-        connection_factory: Arc::new(SqliteConnectionFactory {}),
-    };
+    let mut app_state = AppState::new(connection_factory);
+    app_state.backup_config = backup_config;
+    app_state.admin_token = env::var(TASKER_ADMIN_TOKEN_ENV_VAR).ok();
+    app_state.cleanup_keep_min = parse_setting(
+        TASKER_CLEANUP_KEEP_MIN_ENV_VAR,
+        env::var(TASKER_CLEANUP_KEEP_MIN_ENV_VAR).ok(),
+        "a number of tasks",
+    )?;
+    app_state.default_due_offset_days = parse_setting(
+        TASKER_DEFAULT_DUE_OFFSET_DAYS_ENV_VAR,
+        env::var(TASKER_DEFAULT_DUE_OFFSET_DAYS_ENV_VAR).ok(),
+        "a number of days",
+    )?;
+    app_state.presets_enabled = parse_setting(
+        TASKER_PRESETS_ENABLED_ENV_VAR,
+        env::var(TASKER_PRESETS_ENABLED_ENV_VAR).ok(),
+        "true or false",
+    )?
+    .unwrap_or(true);
+    if let Ok(val) = env::var(TASKER_PRIORITIES_ENV_VAR) {
+        app_state.priority_scheme = val.parse()?;
+    }
+    if let Some(max_assignable) = parse_setting(
+        TASKER_MAX_ASSIGNABLE_PRIORITY_ENV_VAR,
+        env::var(TASKER_MAX_ASSIGNABLE_PRIORITY_ENV_VAR).ok(),
+        "a single priority",
+    )? {
+        let cap_mode = parse_setting(
+            TASKER_PRIORITY_CAP_MODE_ENV_VAR,
+            env::var(TASKER_PRIORITY_CAP_MODE_ENV_VAR).ok(),
+            "reject or clamp",
+        )?
+        .unwrap_or(PriorityCapMode::Reject);
+        app_state.priority_scheme = app_state
+            .priority_scheme
+            .with_max_assignable(max_assignable, cap_mode)
+            .map_err(|error| {
+                ApplicativeError::InvalidSetting(format!(
+                    "{TASKER_MAX_ASSIGNABLE_PRIORITY_ENV_VAR} should be within the priority scheme: {error}"
+                ))
+            })?;
+    }
+    if let Ok(val) = env::var(TASKER_THEME_ENV_VAR) {
+        match val.parse::<Theme>() {
+            Ok(theme) => app_state.theme = theme,
+            Err(error) => tracing::warn!("{}, using the default one", error),
+        }
+    }
+    // A limit of zero would refuse every mutating request
+    app_state.rate_limiter = parse_setting::<NonZeroU32>(
+        TASKER_RATE_LIMIT_ENV_VAR,
+        env::var(TASKER_RATE_LIMIT_ENV_VAR).ok(),
+        "a positive number of requests",
+    )?
+    .map(|max_requests_per_minute| Arc::new(RateLimiter::new(max_requests_per_minute.get())));
     let app = build_app(app_state);
 
-    // Finding port configuration
-    let bind_port: i32 = match env::var(TASKER_PORT_ENV_VAR) {
-        Ok(val) => match val.to_string().parse::<i32>() {
-            Ok(val) => val,
-            Err(_) => TASKER_DEFAULT_PORT,
-        },
-        Err(_) => TASKER_DEFAULT_PORT,
+    // Finding address configuration, the port alone being used if no address is given
+    let bind_address: SocketAddr = match env::var(TASKER_BIND_ENV_VAR) {
+        Ok(val) => val.trim().parse().map_err(|_| {
+            ApplicativeError::InvalidSetting(format!(
+                "{TASKER_BIND_ENV_VAR} should be an address and a port such as 127.0.0.1:8080, got {val:?}"
+            ))
+        })?,
+        Err(_) => {
+            let bind_port = parse_setting(
+                TASKER_PORT_ENV_VAR,
+                env::var(TASKER_PORT_ENV_VAR).ok(),
+                "a port number",
+            )?
+            .unwrap_or(TASKER_DEFAULT_PORT);
+            SocketAddr::from(([0, 0, 0, 0], bind_port))
+        }
     };
-    let bind_ip_port: String = format!("0.0.0.0:{}", bind_port);
 
-    let listener = tokio::net::TcpListener::bind(bind_ip_port).await?;
-    let _ = axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await;
+    let listener = tokio::net::TcpListener::bind(bind_address).await?;
+    tracing::info!("Listening on {}", bind_address);
+    serve(listener, app, shutdown_signal()).await?;
     Ok(())
 }
 
+// Unset settings are `None`, while malformed ones are refused rather than silently ignored
+fn parse_setting<T: FromStr>(
+    var_name: &str,
+    value: Option<String>,
+    expected: &str,
+) -> Result<Option<T>, ApplicativeError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.trim().parse() {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(_) => Err(ApplicativeError::InvalidSetting(format!(
+            "{var_name} should be {expected}, got {value:?}"
+        ))),
+    }
+}
+
+// Serves requests until `shutdown` resolves, then waits for in-flight requests to complete
+async fn serve(
+    listener: TcpListener,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), std::io::Error> {
+    // Peer addresses are needed to rate limit each client separately
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await?;
+    tracing::info!("All in-flight requests completed, exiting");
+    Ok(())
+}
+
+// Resolves on Ctrl+C or SIGTERM. Failing to listen for one of them is logged, the other one still
+// being listened for.
 async fn shutdown_signal() {
     let ctrl_c = async {
-        signal::ctrl_c()
-            .await
-            .expect("failed to install Ctrl+C handler");
+        if let Err(error) = signal::ctrl_c().await {
+            tracing::error!("Cannot listen for Ctrl+C: {}", error);
+            pending::<()>().await;
+        }
     };
 
     let terminate = async {
-        signal::unix::signal(signal::unix::SignalKind::terminate())
-            .expect("failed to install signal handler")
-            .recv()
-            .await;
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(error) => {
+                tracing::error!("Cannot listen for SIGTERM: {}", error);
+                pending::<()>().await;
+            }
+        }
     };
 
     tokio::select! {
         _ = ctrl_c => {
-            println!("Received ctrl-c signal, stopping...")
+            tracing::info!("Received ctrl-c signal, stopping...")
         },
         _ = terminate => {
-            println!("Received terminate signal, stopping...")
+            tracing::info!("Received terminate signal, stopping...")
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::oneshot;
+
+    use crate::sql_connection_factory::tests::InMemorySqliteConnectionFactory;
+
+    #[test]
+    fn settings() {
+        assert_eq!(
+            parse_setting::<u32>("SETTING", None, "a number").unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_setting::<u32>("SETTING", Some(" 7 ".into()), "a number").unwrap(),
+            Some(7)
+        );
+        for value in ["", "seven", "-7"] {
+            assert!(parse_setting::<u32>("SETTING", Some(value.into()), "a number").is_err());
+        }
+
+        // Rate limits cannot be zero
+        assert!(parse_setting::<NonZeroU32>("SETTING", Some("0".into()), "a number").is_err());
+
+        // Priority settings are reported as such rather than as an invalid priority scheme
+        assert_eq!(
+            parse_setting::<char>("SETTING", Some(" B ".into()), "a single priority").unwrap(),
+            Some('B')
+        );
+        assert!(matches!(
+            parse_setting::<char>("SETTING", Some("AB".into()), "a single priority"),
+            Err(ApplicativeError::InvalidSetting(_))
+        ));
+        assert!(matches!(
+            parse_setting::<PriorityCapMode>("SETTING", Some("cap".into()), "reject or clamp"),
+            Err(ApplicativeError::InvalidSetting(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn serving_stops_on_shutdown() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        let app = build_app(AppState::new(connection_factory));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let (stop, stopped) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, app, async {
+            let _ = stopped.await;
+        }));
+
+        // Requests are served until then
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(!server.is_finished());
+
+        stop.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("Serving should stop")
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+use crate::task::TaskId;
+
+pub type NoteId = i64;
+
+// Longer text attached to a task, kept apart from its one-line description
+#[derive(Serialize, Debug)]
+pub struct Note {
+    pub id: NoteId,
+    pub task_id: TaskId,
+    pub body: String,
+    pub created_at: i64, // Unix epoch, in seconds
+}
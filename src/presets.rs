@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+use crate::task::{MAX_DESCRIPTION_LENGTH, PriorityScheme, TaskError, validate_description};
+
 pub type PresetTaskId = i64;
 pub type PresetId = i64;
 
@@ -13,14 +15,23 @@ pub struct PresetTask {
 
 #[derive(Debug)]
 pub enum PresetTaskError {
-    // Tried to set priority to a value outside of A..Z
+    // Tried to set priority to a value outside of the priority scheme
     PriorityNotInRangeError(char),
+    // Same as for regular tasks
+    EmptyDescription,
+    DescriptionTooLong(usize),
 }
 
 impl std::fmt::Display for PresetTaskError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Self::PriorityNotInRangeError(c) => write!(f, "Priority {} is invalid", c),
+            Self::EmptyDescription => write!(f, "Description cannot be empty"),
+            Self::DescriptionTooLong(length) => write!(
+                f,
+                "Description is {} characters long, more than the maximum {}",
+                length, MAX_DESCRIPTION_LENGTH
+            ),
         }
     }
 }
@@ -28,13 +39,21 @@ impl std::fmt::Display for PresetTaskError {
 impl PresetTask {
     // Creates a brand new, never-persisted-before PresetTask
     pub fn new(
+        priority_scheme: &PriorityScheme,
         priority: char,
         description: &str,
         preset_id: PresetId,
     ) -> Result<PresetTask, PresetTaskError> {
-        if !priority.is_ascii_uppercase() {
+        if !priority_scheme.contains(priority) {
             return Err(PresetTaskError::PriorityNotInRangeError(priority));
         }
+        let description = match validate_description(description) {
+            Ok(description) => description,
+            Err(TaskError::DescriptionTooLong(length)) => {
+                return Err(PresetTaskError::DescriptionTooLong(length));
+            }
+            Err(_) => return Err(PresetTaskError::EmptyDescription),
+        };
         Ok(PresetTask {
             id: -1,
             preset_id,
@@ -54,14 +73,55 @@ pub struct Preset {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::task::LETTERS;
 
     #[test]
     fn simple_usage() {
-        let task =
-            PresetTask::new('A', "Some nice task", 42).expect("Task creation should not fail");
+        let task = PresetTask::new(&LETTERS, 'A', "Some nice task", 42)
+            .expect("Task creation should not fail");
 
         assert_eq!(task.id, -1); // Unpersisted tasks should have a special ID
         assert_eq!(task.priority, 'A');
         assert_eq!(task.description, "Some nice task");
     }
+
+    #[test]
+    fn new_preset_task_out_of_range() {
+        // Same rules as for regular tasks
+        for priority in ['a', '4', 'é', 'É', 'Ω'] {
+            let new_task_result =
+                PresetTask::new(&LETTERS, priority, "Some task with an invalid priority", 42);
+
+            assert!(
+                matches!(new_task_result, Err(PresetTaskError::PriorityNotInRangeError(c)) if c == priority),
+                "Preset task creation should fail for priority {priority}"
+            )
+        }
+
+        // Whatever the scheme
+        let digits = PriorityScheme::new('1', '5').unwrap();
+        assert!(PresetTask::new(&digits, '4', "Some task", 42).is_ok());
+        assert!(matches!(
+            PresetTask::new(&digits, 'A', "Some task", 42),
+            Err(PresetTaskError::PriorityNotInRangeError('A'))
+        ));
+    }
+
+    #[test]
+    fn new_preset_task_description() {
+        let task = PresetTask::new(&LETTERS, 'A', " Some task ", 42)
+            .expect("Task creation should not fail");
+        assert_eq!(task.description, "Some task");
+
+        for empty in ["", "   "] {
+            assert!(matches!(
+                PresetTask::new(&LETTERS, 'A', empty, 42),
+                Err(PresetTaskError::EmptyDescription)
+            ));
+        }
+        assert!(matches!(
+            PresetTask::new(&LETTERS, 'A', &"a".repeat(MAX_DESCRIPTION_LENGTH + 1), 42),
+            Err(PresetTaskError::DescriptionTooLong(_))
+        ));
+    }
 }
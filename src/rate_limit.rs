@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+// Allows each client a fixed number of requests per minute, counted from its first request of the
+// minute. Clients of unknown address (e.g. in tests) share the same allowance.
+pub struct RateLimiter {
+    max_requests_per_minute: u32,
+    windows: Mutex<HashMap<Option<IpAddr>, (Instant, u32)>>, // Start of the window, requests in it
+}
+
+impl RateLimiter {
+    pub fn new(max_requests_per_minute: u32) -> RateLimiter {
+        RateLimiter {
+            max_requests_per_minute,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Counts the request, unless the client already used up its allowance
+    pub fn allow(&self, client: Option<IpAddr>) -> bool {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        // Clients are forgotten once their window is over, so that the map does not grow forever
+        windows.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+
+        let (_, count) = windows.entry(client).or_insert((now, 0));
+        if *count >= self.max_requests_per_minute {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clients_are_limited_separately() {
+        let rate_limiter = RateLimiter::new(2);
+        let client: Option<IpAddr> = Some([192, 168, 0, 1].into());
+        let other_client: Option<IpAddr> = Some([192, 168, 0, 2].into());
+
+        assert!(rate_limiter.allow(client));
+        assert!(rate_limiter.allow(client));
+        assert!(!rate_limiter.allow(client));
+
+        // Other clients have their own allowance
+        assert!(rate_limiter.allow(other_client));
+        assert!(rate_limiter.allow(None));
+    }
+}
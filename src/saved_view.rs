@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+// Parameters of the task list, as accepted by the main page. Views are saved sets of them.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct TaskListFilters {
+    pub project: Option<String>,
+    pub show: Option<String>, // "all", "completed" or "pending" (default)
+    pub tag: Option<String>,
+    // Bounds of the priority range, both included. Unprioritized tasks are out of any range.
+    pub highest_priority: Option<String>,
+    pub lowest_priority: Option<String>,
+    pub sort: Option<String>, // "description", "created", "due_date" or "priority" (default)
+    pub view: Option<String>, // "hidden" to only show hidden tasks, left out otherwise
+}
@@ -1,16 +1,78 @@
-use rusqlite::{Connection, Error};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
 
-const SQLITE_URL: &str = "./tasks.db";
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, Error, ffi};
+
+pub const SQLITE_URL: &str = "./tasks.db";
+
+// Either a connection of its own, or one borrowed from a pool and given back to it once dropped.
+// Both are used as a plain connection.
+pub enum SqlConnection {
+    Owned(Connection),
+    Pooled(PooledConnection<SqliteConnectionManager>),
+}
+
+impl Deref for SqlConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            SqlConnection::Owned(conn) => conn,
+            SqlConnection::Pooled(conn) => conn,
+        }
+    }
+}
+
+impl DerefMut for SqlConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        match self {
+            SqlConnection::Owned(conn) => conn,
+            SqlConnection::Pooled(conn) => conn,
+        }
+    }
+}
+
+impl From<Connection> for SqlConnection {
+    fn from(conn: Connection) -> Self {
+        SqlConnection::Owned(conn)
+    }
+}
 
 pub trait SqlConnectionFactory: Send + Sync {
-    fn open(&self) -> Result<Connection, Error>;
+    fn open(&self) -> Result<SqlConnection, Error>;
 }
 
 pub struct SqliteConnectionFactory;
 
 impl SqlConnectionFactory for SqliteConnectionFactory {
-    fn open(&self) -> Result<Connection, Error> {
-        Connection::open(SQLITE_URL)
+    fn open(&self) -> Result<SqlConnection, Error> {
+        Ok(Connection::open(SQLITE_URL)?.into())
+    }
+}
+
+// Keeps up to `max_size` connections open, so that requests do not have to open their own.
+pub struct PooledSqliteConnectionFactory {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl PooledSqliteConnectionFactory {
+    pub fn new(path: impl AsRef<Path>, max_size: u32) -> Result<Self, r2d2::Error> {
+        let pool = Pool::builder()
+            .max_size(max_size)
+            .build(SqliteConnectionManager::file(path))?;
+        Ok(PooledSqliteConnectionFactory { pool })
+    }
+}
+
+impl SqlConnectionFactory for PooledSqliteConnectionFactory {
+    fn open(&self) -> Result<SqlConnection, Error> {
+        // Failing to get a connection in time means all of them are in use by others
+        let conn = self.pool.get().map_err(|error| {
+            Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_BUSY), Some(error.to_string()))
+        })?;
+        Ok(SqlConnection::Pooled(conn))
     }
 }
 
@@ -18,6 +80,10 @@ impl SqlConnectionFactory for SqliteConnectionFactory {
 pub mod tests {
     use super::*;
     use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
 
     use tempfile::{TempDir, tempdir};
 
@@ -34,7 +100,7 @@ pub mod tests {
     }
 
     impl SqlConnectionFactory for TempDirSqliteConnectionFactory {
-        fn open(&self) -> Result<Connection, Error> {
+        fn open(&self) -> Result<SqlConnection, Error> {
             let full_path = format!(
                 "{}/tasks.db",
                 self.tempdir
@@ -42,7 +108,100 @@ pub mod tests {
                     .to_str()
                     .expect("Path should be OK as generated internally")
             );
-            Connection::open(full_path)
+            Ok(Connection::open(full_path)?.into())
+        }
+    }
+
+    // In-memory databases only live as long as a connection is open to them, and are private to
+    // this connection unless shared by name. Each factory thus has its own uniquely named
+    // database, kept alive by a connection held for the lifetime of the factory.
+    pub struct InMemorySqliteConnectionFactory {
+        uri: String,
+        _keep_alive: Mutex<Connection>,
+    }
+
+    static IN_MEMORY_DATABASE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    impl InMemorySqliteConnectionFactory {
+        pub fn new() -> Result<Self, Error> {
+            let id = IN_MEMORY_DATABASE_COUNT.fetch_add(1, Ordering::SeqCst);
+            let uri = format!("file:tasker-memdb-{id}?mode=memory&cache=shared");
+            Ok(InMemorySqliteConnectionFactory {
+                _keep_alive: Mutex::new(Connection::open(&uri)?),
+                uri,
+            })
+        }
+    }
+
+    impl SqlConnectionFactory for InMemorySqliteConnectionFactory {
+        fn open(&self) -> Result<SqlConnection, Error> {
+            Ok(Connection::open(&self.uri)?.into())
+        }
+    }
+
+    #[test]
+    fn in_memory_databases_are_shared_per_factory() -> Result<(), Error> {
+        let connection_factory = InMemorySqliteConnectionFactory::new()?;
+        let other_connection_factory = InMemorySqliteConnectionFactory::new()?;
+
+        connection_factory
+            .open()?
+            .execute("CREATE TABLE some_table (id INTEGER PRIMARY KEY)", ())?;
+
+        // Visible from another connection of the same factory...
+        let count: i64 =
+            connection_factory
+                .open()?
+                .query_row("SELECT COUNT(*) FROM some_table", [], |row| row.get(0))?;
+        assert_eq!(count, 0);
+
+        // ... but not from other factories
+        assert!(
+            other_connection_factory
+                .open()?
+                .query_row("SELECT COUNT(*) FROM some_table", [], |row| row
+                    .get::<_, i64>(0))
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn pool_bounds_are_respected() -> Result<(), Box<dyn std::error::Error>> {
+        let tempdir = tempdir()?;
+        let connection_factory = Arc::new(PooledSqliteConnectionFactory::new(
+            tempdir.path().join("tasks.db"),
+            2,
+        )?);
+        let in_use = Arc::new(AtomicUsize::new(0));
+        let max_in_use = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let connection_factory = connection_factory.clone();
+                let in_use = in_use.clone();
+                let max_in_use = max_in_use.clone();
+                thread::spawn(move || -> Result<(), Error> {
+                    let conn = connection_factory.open()?;
+                    let now_in_use = in_use.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_use.fetch_max(now_in_use, Ordering::SeqCst);
+
+                    conn.query_row("SELECT 1", [], |_| Ok(()))?;
+                    thread::sleep(Duration::from_millis(20));
+
+                    in_use.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                })
+            })
+            .collect();
+
+        // Everyone eventually gets a connection, but never more than two at once
+        for handle in handles {
+            handle.join().expect("Thread should not panic")?;
         }
+        assert_eq!(max_in_use.load(Ordering::SeqCst), 2);
+
+        Ok(())
     }
 }
@@ -0,0 +1,31 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PriorityCount {
+    pub priority: Option<char>, // None for unprioritized tasks
+    pub count: i64,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ProjectCount {
+    pub project: Option<String>, // None for tasks outside of any project
+    pub count: i64,
+}
+
+// Summary of the task list, counting both pending and completed tasks
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Stats {
+    pub total: i64,
+    pub completed: i64,
+    pub pending: i64,
+    pub by_priority: Vec<PriorityCount>,
+    pub by_project: Vec<ProjectCount>,
+}
+
+// Completed tasks of a month, counted by project once their details were deleted
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MonthlySummary {
+    pub year: i32,
+    pub month: u32,
+    pub by_project: Vec<ProjectCount>,
+}
@@ -1,67 +1,438 @@
-use serde::Serialize;
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+use crate::presets::PresetId;
 
 pub type TaskId = i64;
+pub type SubtaskId = i64;
 
 #[derive(Serialize, Debug)]
 pub struct Task {
-    pub id: TaskId, // -1 if never persisted, ID in DB otherwise
-    pub priority: char,
+    pub id: TaskId,             // -1 if never persisted, ID in DB otherwise
+    pub priority: Option<char>, // None for unprioritized tasks
     pub description: String,
     pub completed: bool,
+    pub hidden: bool, // Left out of the task list, whatever its completion state
     pub project: Option<String>,
+    pub source_preset_id: Option<PresetId>, // Preset the task was injected from, if any
+    pub due_date: Option<NaiveDate>,
+    pub subtasks: Vec<Subtask>, // Checklist breaking the task down, if any
+    pub recurrence: Option<Recurrence>, // Completing a recurring task spawns a fresh pending copy
+    pub recurrence_paused: bool, // If set, completing the task does not spawn a copy
+    pub tags: Vec<String>,      // Labels orthogonal to the project, sorted by name
+    pub blocked: bool,          // Some task it depends on is still pending
+    #[serde(flatten, serialize_with = "serialize_created_at")]
+    pub created_at: i64, // Unix timestamp, 0 if unknown (e.g. never persisted)
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Subtask {
+    pub id: SubtaskId,
+    pub task_id: TaskId,
+    pub description: String,
+    pub completed: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Recurrence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    // Due date of the next occurrence. Monthly occurrences fall on the last day of shorter months.
+    // Fails if there is no such date, the due date being too close to the end of time.
+    pub fn next_due_date(&self, due_date: NaiveDate) -> Result<NaiveDate, TaskError> {
+        let next_due_date = match self {
+            Self::Daily => due_date.checked_add_days(Days::new(1)),
+            Self::Weekly => due_date.checked_add_days(Days::new(7)),
+            Self::Monthly => due_date.checked_add_months(Months::new(1)),
+        };
+        next_due_date.ok_or_else(|| {
+            TaskError::InvalidRecurrence(format!("{} after {}", self.as_str(), due_date))
+        })
+    }
+
+    // First date falling on the weekday, on or after the given one. Fails if there is no such
+    // date, as for `next_due_date`.
+    pub fn on_weekday(date: NaiveDate, weekday: Weekday) -> Result<NaiveDate, TaskError> {
+        let days_ahead =
+            (weekday.num_days_from_monday() + 7 - date.weekday().num_days_from_monday()) % 7;
+        date.checked_add_days(Days::new(days_ahead.into()))
+            .ok_or_else(|| TaskError::InvalidRecurrence(format!("weekly on {weekday} from {date}")))
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = TaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            "monthly" => Ok(Self::Monthly),
+            _ => Err(TaskError::InvalidRecurrence(s.into())),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum TaskError {
-    // Tried to set priority to a value outside of A..Z
+    // Tried to set priority to a value outside of the priority scheme
     PriorityNotInRangeError(char),
+    // Tried to parse a line that does not follow the todo.txt format
+    TodoTxtParseError(String),
+    // Tried to define a priority scheme that is not a range of ASCII letters or digits
+    InvalidPriorityScheme(String),
+    // Tried to parse a recurrence other than "daily", "weekly" or "monthly"
+    InvalidRecurrence(String),
+    // Tried to assign a priority more urgent than the maximum assignable one
+    PriorityNotAssignable(char),
+    // Tried to give a task a description that is empty, once trimmed
+    EmptyDescription,
+    // Tried to give a task a description longer than MAX_DESCRIPTION_LENGTH (holds its length)
+    DescriptionTooLong(usize),
 }
 
 impl std::fmt::Display for TaskError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Self::PriorityNotInRangeError(c) => write!(f, "Priority {} is invalid", c),
+            Self::TodoTxtParseError(line) => write!(f, "Line \"{}\" is not valid todo.txt", line),
+            Self::InvalidPriorityScheme(scheme) => {
+                write!(f, "Priority scheme \"{}\" is invalid", scheme)
+            }
+            Self::InvalidRecurrence(recurrence) => {
+                write!(f, "Recurrence \"{}\" is invalid", recurrence)
+            }
+            Self::PriorityNotAssignable(c) => write!(f, "Priority {} cannot be assigned", c),
+            Self::EmptyDescription => write!(f, "Description cannot be empty"),
+            Self::DescriptionTooLong(length) => write!(
+                f,
+                "Description is {} characters long, more than the maximum {}",
+                length, MAX_DESCRIPTION_LENGTH
+            ),
+        }
+    }
+}
+
+// What happens when trying to assign a priority more urgent than the maximum assignable one
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityCapMode {
+    Reject, // The operation fails
+    Clamp,  // The maximum assignable priority is used instead
+}
+
+impl FromStr for PriorityCapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "clamp" => Ok(Self::Clamp),
+            _ => Err(format!("Unknown priority cap mode {s}")),
+        }
+    }
+}
+
+// Range of consecutive characters allowed as priorities, from the highest to the lowest one,
+// e.g. A..C or 1..5. Being consecutive, they sort in the same order as the characters themselves.
+// Priorities above `max_assignable` may exist, but cannot be given to tasks (e.g. to reserve the
+// highest priority to some users).
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct PriorityScheme {
+    highest: char,
+    lowest: char,
+    max_assignable: char,
+    cap_mode: PriorityCapMode,
+}
+
+impl PriorityScheme {
+    pub fn new(highest: char, lowest: char) -> Result<PriorityScheme, TaskError> {
+        // Both ends have to be of the same kind, so that e.g. "9-A" does not take in punctuation
+        let same_class = [
+            char::is_ascii_uppercase,
+            char::is_ascii_lowercase,
+            char::is_ascii_digit,
+        ]
+        .iter()
+        .any(|is_in_class| is_in_class(&highest) && is_in_class(&lowest));
+        if !same_class || highest > lowest {
+            return Err(TaskError::InvalidPriorityScheme(format!(
+                "{highest}-{lowest}"
+            )));
+        }
+        Ok(PriorityScheme {
+            highest,
+            lowest,
+            max_assignable: highest,
+            cap_mode: PriorityCapMode::Reject,
+        })
+    }
+
+    pub fn with_max_assignable(
+        self,
+        max_assignable: char,
+        cap_mode: PriorityCapMode,
+    ) -> Result<PriorityScheme, TaskError> {
+        if !self.contains(max_assignable) {
+            return Err(TaskError::PriorityNotInRangeError(max_assignable));
         }
+        Ok(PriorityScheme {
+            max_assignable,
+            cap_mode,
+            ..self
+        })
+    }
+
+    pub fn lowest(&self) -> char {
+        self.lowest
+    }
+
+    pub fn max_assignable(&self) -> char {
+        self.max_assignable
+    }
+
+    pub fn contains(&self, priority: char) -> bool {
+        (self.highest..=self.lowest).contains(&priority)
+    }
+
+    // Priority to actually assign when `priority` is requested, according to the cap mode
+    pub fn assignable(&self, priority: char) -> Result<char, TaskError> {
+        if !self.contains(priority) {
+            return Err(TaskError::PriorityNotInRangeError(priority));
+        }
+        if priority >= self.max_assignable {
+            return Ok(priority);
+        }
+        match self.cap_mode {
+            PriorityCapMode::Reject => Err(TaskError::PriorityNotAssignable(priority)),
+            PriorityCapMode::Clamp => Ok(self.max_assignable),
+        }
+    }
+
+    // Bucket of the priority, None if outside of the scheme. The highest tenth of the scheme (at
+    // least one priority) is high, the rest of its upper half medium, and its lower half low,
+    // e.g. A to C, D to M and N to Z with the default scheme.
+    pub fn bucket(&self, priority: char) -> Option<PriorityBucket> {
+        if !self.contains(priority) {
+            return None;
+        }
+        let size = self.lowest as u32 - self.highest as u32 + 1;
+        let rank = priority as u32 - self.highest as u32;
+        Some(if rank < size.div_ceil(10) {
+            PriorityBucket::High
+        } else if rank < size.div_ceil(2) {
+            PriorityBucket::Medium
+        } else {
+            PriorityBucket::Low
+        })
+    }
+
+    // Moves the priority by one step (upwards if negative), without leaving the scheme nor going
+    // above the maximum assignable priority. Priorities already above it are left as is.
+    #[cfg(test)]
+    fn shift(&self, priority: char, step: i32) -> char {
+        let highest = self.max_assignable.min(priority);
+        let shifted = (priority as i32 + step).clamp(highest as i32, self.lowest as i32);
+        std::char::from_u32(shifted as u32).expect("Priority should be convertible safely")
+    }
+}
+
+// Rough importance of a priority, for pages to style tasks consistently whatever the scheme
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PriorityBucket {
+    High,
+    Medium,
+    Low,
+}
+
+impl PriorityBucket {
+    // CSS class of the bucket, styled by templates
+    pub fn class(&self) -> &'static str {
+        match self {
+            Self::High => "priority-high",
+            Self::Medium => "priority-medium",
+            Self::Low => "priority-low",
+        }
+    }
+}
+
+// Default scheme, from A (highest) to Z (lowest)
+pub const LETTERS: PriorityScheme = PriorityScheme {
+    highest: 'A',
+    lowest: 'Z',
+    max_assignable: 'A',
+    cap_mode: PriorityCapMode::Reject,
+};
+
+impl Default for PriorityScheme {
+    fn default() -> Self {
+        LETTERS
+    }
+}
+
+// Parses schemes written as "A-C"
+impl FromStr for PriorityScheme {
+    type Err = TaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid_scheme = || TaskError::InvalidPriorityScheme(s.into());
+
+        let (highest, lowest) = s.trim().split_once('-').ok_or_else(invalid_scheme)?;
+        let single_char = |part: &str| {
+            let mut chars = part.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(invalid_scheme()),
+            }
+        };
+        PriorityScheme::new(single_char(highest)?, single_char(lowest)?)
+    }
+}
+
+// Serializes the creation timestamp along with the age of the task in whole days, as
+// "created_at" and "age_days". The age is null if the creation time is unknown.
+fn serialize_created_at<S: Serializer>(created_at: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    let age_days =
+        (*created_at > 0).then(|| (chrono::Utc::now().timestamp() - created_at).max(0) / 86400);
+
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry("created_at", created_at)?;
+    map.serialize_entry("age_days", &age_days)?;
+    map.end()
+}
+
+// Longest description accepted for tasks and preset tasks, in characters
+pub const MAX_DESCRIPTION_LENGTH: usize = 1000;
+
+// Drops surrounding whitespace, rejecting descriptions left empty or too long
+pub fn validate_description(description: &str) -> Result<&str, TaskError> {
+    let description = description.trim();
+    let length = description.chars().count();
+    if length == 0 {
+        return Err(TaskError::EmptyDescription);
+    }
+    if length > MAX_DESCRIPTION_LENGTH {
+        return Err(TaskError::DescriptionTooLong(length));
     }
+    Ok(description)
+}
+
+// Drops surrounding whitespace, a blank project name meaning no project at all
+pub fn normalize_project(project: Option<&str>) -> Option<&str> {
+    project.map(str::trim).filter(|project| !project.is_empty())
 }
 
 impl Task {
     // Creates a brand new, never-persisted-before Task
     pub fn new(
-        priority: char,
+        priority_scheme: &PriorityScheme,
+        priority: Option<char>,
         description: &str,
         project: Option<&str>,
     ) -> Result<Task, TaskError> {
-        if !priority.is_ascii_uppercase() {
-            return Err(TaskError::PriorityNotInRangeError(priority));
-        }
+        let priority = priority
+            .map(|priority| priority_scheme.assignable(priority))
+            .transpose()?;
         Ok(Task {
             id: -1,
             priority,
-            project: project.map(str::to_string),
-            description: description.into(),
+            project: normalize_project(project).map(str::to_string),
+            description: validate_description(description)?.into(),
             completed: false,
+            hidden: false,
+            source_preset_id: None,
+            due_date: None,
+            subtasks: vec![],
+            recurrence: None,
+            recurrence_paused: false,
+            tags: vec![],
+            blocked: false,
+            created_at: 0,
         })
     }
 
-    pub fn increase_priority(&mut self) {
-        match self.priority {
-            'A' => (), // Do nothing if the priority is already maxed out
-            _ => {
-                self.priority = std::char::from_u32(self.priority as u32 - 1)
-                    .expect("Priority should be convertible safely")
-            }
+    // Only used by tests, as a reference: stored tasks are rather shifted by
+    // `TaskRepo::shift_priority`, which mirrors these two
+    #[cfg(test)]
+    pub fn increase_priority(&mut self, priority_scheme: &PriorityScheme) {
+        self.priority = Some(match self.priority {
+            None => priority_scheme.lowest, // Unprioritized tasks start from the bottom
+            Some(priority) => priority_scheme.shift(priority, -1), // No-op if already maxed out
+        });
+    }
+
+    #[cfg(test)]
+    pub fn lower_priority(&mut self, priority_scheme: &PriorityScheme) {
+        self.priority = Some(match self.priority {
+            None => priority_scheme.max_assignable, // Unprioritized tasks start from the top
+            Some(priority) => priority_scheme.shift(priority, 1), // No-op if already at the minimum
+        });
+    }
+
+    // Renders the task as a todo.txt line, e.g. "x (A) description +project".
+    // Unlike the canonical representation, the priority of completed tasks is kept, so that
+    // todo.txt exports can be imported back without loss.
+    pub fn to_todotxt(&self) -> String {
+        let mut line = String::new();
+        if self.completed {
+            line.push_str("x ");
+        }
+        if let Some(priority) = self.priority {
+            line.push_str(&format!("({}) ", priority));
+        }
+        line.push_str(&self.description);
+        if let Some(project) = &self.project {
+            line.push_str(&format!(" +{}", project));
         }
+        line
     }
 
-    pub fn lower_priority(&mut self) {
-        match self.priority {
-            'Z' => (), // Do nothing if the priority is already at the minimum value
-            _ => {
-                self.priority = std::char::from_u32(self.priority as u32 + 1)
-                    .expect("Priority should be convertible safely")
+    // Parses a todo.txt line, as produced by `to_todotxt`, into a brand new Task
+    pub fn from_todotxt(priority_scheme: &PriorityScheme, line: &str) -> Result<Task, TaskError> {
+        let parse_error = || TaskError::TodoTxtParseError(line.into());
+
+        let (completed, rest) = match line.trim().strip_prefix("x ") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, line.trim()),
+        };
+
+        // Priority, if any, is expected to be "(X) " at the beginning of the line
+        let mut chars = rest.chars();
+        let (priority, rest) = match (chars.next(), chars.next(), chars.next()) {
+            (Some('('), Some(priority), Some(')')) => (Some(priority), chars.as_str().trim()),
+            _ => (None, rest),
+        };
+
+        // Project, if any, is expected to be the last "+project" tag
+        let (description, project) = match rest.rsplit_once(" +") {
+            Some((description, project)) if !project.is_empty() => {
+                (description.trim(), Some(project.trim()))
             }
+            _ => (rest, None),
+        };
+        if description.is_empty() {
+            return Err(parse_error());
         }
+
+        let mut task = Task::new(priority_scheme, priority, description, project)?;
+        task.completed = completed;
+        Ok(task)
     }
 }
 
@@ -71,39 +442,316 @@ mod tests {
 
     #[test]
     fn simple_usage() {
-        let mut task =
-            Task::new('A', "Some nice task", None).expect("Task creation should not fail");
+        let mut task = Task::new(&LETTERS, Some('A'), "Some nice task", None)
+            .expect("Task creation should not fail");
 
         assert_eq!(task.id, -1); // Unpersisted tasks should have a special ID
         assert!(!task.completed); // Newly created tasks are not completed
-        assert_eq!(task.priority, 'A');
+        assert_eq!(task.priority, Some('A'));
         assert_eq!(task.description, "Some nice task");
 
-        task.lower_priority();
-        assert_eq!(task.priority, 'B');
+        task.lower_priority(&LETTERS);
+        assert_eq!(task.priority, Some('B'));
+
+        task.increase_priority(&LETTERS);
+        assert_eq!(task.priority, Some('A'));
+    }
+
+    #[test]
+    fn description_validation() {
+        // Surrounding whitespace is dropped
+        let task = Task::new(&LETTERS, None, "  Some task\n", None)
+            .expect("Task creation should not fail");
+        assert_eq!(task.description, "Some task");
+
+        // Empty descriptions are rejected, even once trimmed
+        for empty in ["", "   ", "\t\n"] {
+            assert!(matches!(
+                Task::new(&LETTERS, None, empty, None),
+                Err(TaskError::EmptyDescription)
+            ));
+        }
 
-        task.increase_priority();
-        assert_eq!(task.priority, 'A');
+        // Lengths are counted in characters, not bytes
+        let longest = "é".repeat(MAX_DESCRIPTION_LENGTH);
+        assert!(Task::new(&LETTERS, None, &longest, None).is_ok());
+        let too_long = format!("{longest}e");
+        assert!(matches!(
+            Task::new(&LETTERS, None, &too_long, None),
+            Err(TaskError::DescriptionTooLong(length)) if length == MAX_DESCRIPTION_LENGTH + 1
+        ));
+    }
+
+    #[test]
+    fn project_normalization() {
+        // Surrounding whitespace is dropped
+        let task = Task::new(&LETTERS, None, "Some task", Some("  house \t"))
+            .expect("Task creation should not fail");
+        assert_eq!(task.project, Some("house".into()));
+
+        // Blank projects mean no project at all
+        for blank in ["", " ", "\t \n"] {
+            let task = Task::new(&LETTERS, None, "Some task", Some(blank))
+                .expect("Task creation should not fail");
+            assert_eq!(task.project, None);
+        }
+
+        assert_eq!(normalize_project(None), None);
+        assert_eq!(normalize_project(Some("my project ")), Some("my project"));
     }
 
     #[test]
     fn increase_max_priority_lower_min_priority() {
-        let mut urgent_task =
-            Task::new('A', "Some urgent task", None).expect("Task creation should not fail");
-        let mut unimportant_task =
-            Task::new('Z', "Some unimportant task", None).expect("Task creation should not fail");
+        let mut urgent_task = Task::new(&LETTERS, Some('A'), "Some urgent task", None)
+            .expect("Task creation should not fail");
+        let mut unimportant_task = Task::new(&LETTERS, Some('Z'), "Some unimportant task", None)
+            .expect("Task creation should not fail");
 
-        urgent_task.increase_priority();
-        assert_eq!(urgent_task.priority, 'A'); // No failure, but no change either
+        urgent_task.increase_priority(&LETTERS);
+        assert_eq!(urgent_task.priority, Some('A')); // No failure, but no change either
 
-        unimportant_task.lower_priority();
-        assert_eq!(unimportant_task.priority, 'Z'); // No failure, but no change either
+        unimportant_task.lower_priority(&LETTERS);
+        assert_eq!(unimportant_task.priority, Some('Z')); // No failure, but no change either
+    }
+
+    #[test]
+    fn unprioritized_task() {
+        let mut task =
+            Task::new(&LETTERS, None, "Some task", None).expect("Task creation should not fail");
+        assert_eq!(task.priority, None);
+
+        // Unprioritized tasks get a sensible priority when changed
+        task.increase_priority(&LETTERS);
+        assert_eq!(task.priority, Some('Z'));
+
+        task.priority = None;
+        task.lower_priority(&LETTERS);
+        assert_eq!(task.priority, Some('A'));
+    }
+
+    #[test]
+    fn todotxt_round_trip() {
+        let mut task = Task::new(
+            &LETTERS,
+            Some('C'),
+            "Buy a new vacuum cleaner",
+            Some("house"),
+        )
+        .expect("Task creation should not fail");
+        assert_eq!(task.to_todotxt(), "(C) Buy a new vacuum cleaner +house");
+
+        task.completed = true;
+        assert_eq!(task.to_todotxt(), "x (C) Buy a new vacuum cleaner +house");
+
+        let parsed_task =
+            Task::from_todotxt(&LETTERS, &task.to_todotxt()).expect("Parsing should not fail");
+        assert_eq!(parsed_task.priority, Some('C'));
+        assert_eq!(parsed_task.description, "Buy a new vacuum cleaner");
+        assert_eq!(parsed_task.project, Some("house".into()));
+        assert!(parsed_task.completed);
+
+        // Tasks without project have no "+project" tag
+        let parsed_task =
+            Task::from_todotxt(&LETTERS, "(A) Clean up laundry").expect("Parsing should not fail");
+        assert_eq!(parsed_task.description, "Clean up laundry");
+        assert_eq!(parsed_task.project, None);
+        assert!(!parsed_task.completed);
+
+        // Unprioritized tasks have no "(X)" prefix
+        let parsed_task = Task::from_todotxt(&LETTERS, "x Wish mom a happy birthday")
+            .expect("Parsing should not fail");
+        assert_eq!(parsed_task.priority, None);
+        assert_eq!(parsed_task.description, "Wish mom a happy birthday");
+        assert!(parsed_task.completed);
+        assert_eq!(parsed_task.to_todotxt(), "x Wish mom a happy birthday");
+    }
+
+    #[test]
+    fn todotxt_invalid_lines() {
+        assert!(Task::from_todotxt(&LETTERS, "(a) Clean up laundry").is_err()); // Invalid priority
+        assert!(Task::from_todotxt(&LETTERS, "(A) ").is_err()); // No description
     }
 
     #[test]
     fn new_task_out_of_range() {
-        let new_task_result = Task::new('4', "Some task with an invalid priority", None);
+        // Lowercase, digits and non-ASCII letters (even uppercase ones) are all rejected
+        for priority in ['a', '4', 'é', 'É', 'Ω'] {
+            let new_task_result = Task::new(
+                &LETTERS,
+                Some(priority),
+                "Some task with an invalid priority",
+                None,
+            );
+
+            assert!(
+                matches!(new_task_result, Err(TaskError::PriorityNotInRangeError(c)) if c == priority),
+                "Task creation should fail for priority {priority}"
+            )
+        }
+    }
+
+    #[test]
+    fn restricted_priority_scheme() {
+        let priority_scheme: PriorityScheme = "A-C".parse().expect("Scheme should be valid");
+        assert_eq!(priority_scheme, PriorityScheme::new('A', 'C').unwrap());
+
+        // Priorities outside of the scheme are rejected
+        assert!(Task::new(&priority_scheme, Some('D'), "Some task", None).is_err());
+
+        // Priorities are clamped at the endpoints of the scheme
+        let mut task = Task::new(&priority_scheme, Some('B'), "Some task", None)
+            .expect("Task creation should not fail");
+        task.lower_priority(&priority_scheme);
+        assert_eq!(task.priority, Some('C'));
+        task.lower_priority(&priority_scheme);
+        assert_eq!(task.priority, Some('C')); // No failure, but no change either
+
+        task.priority = None;
+        task.increase_priority(&priority_scheme);
+        assert_eq!(task.priority, Some('C'));
+
+        // Digits work as well
+        let priority_scheme: PriorityScheme = "1-3".parse().expect("Scheme should be valid");
+        assert!(Task::new(&priority_scheme, Some('2'), "Some task", None).is_ok());
+    }
+
+    #[test]
+    fn invalid_priority_schemes() {
+        for scheme in ["C-A", "A", "AB-C", "a-é", "", "9-A", "Z-a"] {
+            assert!(
+                scheme.parse::<PriorityScheme>().is_err(),
+                "Scheme {scheme} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn recurrences() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!("weekly".parse::<Recurrence>().unwrap(), Recurrence::Weekly);
+        assert!("yearly".parse::<Recurrence>().is_err());
+
+        assert_eq!(
+            Recurrence::Daily.next_due_date(date(2024, 12, 31)).unwrap(),
+            date(2025, 1, 1)
+        );
+        assert_eq!(
+            Recurrence::Weekly.next_due_date(date(2024, 2, 26)).unwrap(),
+            date(2024, 3, 4)
+        );
+        assert_eq!(
+            Recurrence::Monthly
+                .next_due_date(date(2024, 1, 31))
+                .unwrap(),
+            date(2024, 2, 29)
+        );
+
+        // 2024-02-26 is a Monday
+        assert_eq!(
+            Recurrence::on_weekday(date(2024, 2, 26), Weekday::Mon).unwrap(),
+            date(2024, 2, 26)
+        );
+        assert_eq!(
+            Recurrence::on_weekday(date(2024, 2, 26), Weekday::Sun).unwrap(),
+            date(2024, 3, 3)
+        );
+        assert_eq!(
+            Recurrence::on_weekday(date(2024, 2, 27), Weekday::Mon).unwrap(),
+            date(2024, 3, 4)
+        );
+
+        // There is no next occurrence at the end of time
+        for recurrence in [Recurrence::Daily, Recurrence::Weekly, Recurrence::Monthly] {
+            assert!(matches!(
+                recurrence.next_due_date(NaiveDate::MAX),
+                Err(TaskError::InvalidRecurrence(_))
+            ));
+        }
+        assert!(Recurrence::on_weekday(NaiveDate::MAX, NaiveDate::MAX.weekday().succ()).is_err());
+    }
+
+    #[test]
+    fn max_assignable_priority() {
+        let rejecting_scheme = LETTERS
+            .with_max_assignable('B', PriorityCapMode::Reject)
+            .expect("Scheme should be valid");
+        let clamping_scheme = LETTERS
+            .with_max_assignable('B', PriorityCapMode::Clamp)
+            .expect("Scheme should be valid");
+        assert!(
+            LETTERS
+                .with_max_assignable('a', PriorityCapMode::Clamp)
+                .is_err()
+        );
+
+        // Depending on the mode, priorities above the cap are either rejected or clamped
+        assert!(matches!(
+            Task::new(&rejecting_scheme, Some('A'), "Some task", None),
+            Err(TaskError::PriorityNotAssignable('A'))
+        ));
+        let mut task = Task::new(&clamping_scheme, Some('A'), "Some task", None)
+            .expect("Task creation should not fail");
+        assert_eq!(task.priority, Some('B'));
+        task.priority = Some('C');
+
+        // Priorities cannot be increased past the cap...
+        task.increase_priority(&rejecting_scheme);
+        task.increase_priority(&rejecting_scheme);
+        assert_eq!(task.priority, Some('B'));
+        task.priority = None;
+        task.lower_priority(&rejecting_scheme);
+        assert_eq!(task.priority, Some('B'));
+
+        // ...but priorities already above it are kept
+        task.priority = Some('A');
+        task.increase_priority(&rejecting_scheme);
+        assert_eq!(task.priority, Some('A'));
+        task.lower_priority(&rejecting_scheme);
+        assert_eq!(task.priority, Some('B'));
+    }
+
+    #[test]
+    fn priority_buckets() {
+        for (priority, expected_bucket) in [
+            ('A', Some(PriorityBucket::High)),
+            ('C', Some(PriorityBucket::High)),
+            ('D', Some(PriorityBucket::Medium)),
+            ('M', Some(PriorityBucket::Medium)),
+            ('N', Some(PriorityBucket::Low)),
+            ('Z', Some(PriorityBucket::Low)),
+            ('1', None),
+        ] {
+            assert_eq!(LETTERS.bucket(priority), expected_bucket);
+        }
+
+        // Smaller schemes are split the same way
+        let digits = PriorityScheme::new('1', '5').unwrap();
+        let buckets: Vec<_> = "12345".chars().map(|c| digits.bucket(c)).collect();
+        assert_eq!(
+            buckets,
+            [
+                Some(PriorityBucket::High),
+                Some(PriorityBucket::Medium),
+                Some(PriorityBucket::Medium),
+                Some(PriorityBucket::Low),
+                Some(PriorityBucket::Low)
+            ]
+        );
+        assert_eq!(digits.bucket('A'), None);
+    }
+
+    #[test]
+    fn age() {
+        let mut task = Task::new(&LETTERS, Some('A'), "Some task", None).unwrap();
+
+        // Unknown creation times give no age
+        let serialized = serde_json::to_value(&task).unwrap();
+        assert_eq!(serialized["created_at"], 0);
+        assert_eq!(serialized["age_days"], serde_json::Value::Null);
 
-        assert!(new_task_result.is_err(), "Task creation should fail")
+        task.created_at = chrono::Utc::now().timestamp() - 3 * 86400 - 60;
+        let serialized = serde_json::to_value(&task).unwrap();
+        assert_eq!(serialized["age_days"], 3);
     }
 }
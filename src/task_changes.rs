@@ -0,0 +1,13 @@
+use serde::Serialize;
+
+use crate::task::TaskId;
+
+// Tasks affected by each kind of change over some period of time. A task may appear under
+// several kinds, e.g. if it was both created and completed.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct TaskChanges {
+    pub created: Vec<TaskId>,
+    pub completed: Vec<TaskId>,
+    pub deleted: Vec<TaskId>,
+    pub edited: Vec<TaskId>, // Any other change, including flagging back as pending
+}
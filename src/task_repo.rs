@@ -1,30 +1,590 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 
+use chrono::{Months, NaiveDate, NaiveTime, Weekday};
+use rusqlite::Connection;
+use rusqlite::ErrorCode;
+use rusqlite::MAIN_DB;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
+use rusqlite::ToSql;
 use rusqlite::named_params;
 use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use serde::Serialize;
 
+use crate::note::Note;
+use crate::note::NoteId;
 use crate::presets::Preset;
 use crate::presets::PresetId;
 use crate::presets::PresetTask;
 use crate::presets::PresetTaskError;
-use crate::sql_connection_factory::SqlConnectionFactory;
+use crate::saved_view::TaskListFilters;
+use crate::sql_connection_factory::{SqlConnection, SqlConnectionFactory};
+use crate::stats::{MonthlySummary, PriorityCount, ProjectCount, Stats};
+use crate::task::PriorityBucket;
+use crate::task::PriorityScheme;
+use crate::task::Recurrence;
+use crate::task::Subtask;
+use crate::task::SubtaskId;
 use crate::task::Task;
 use crate::task::TaskError;
 use crate::task::TaskId;
+use crate::task::normalize_project;
+use crate::task::validate_description;
+use crate::task_changes::TaskChanges;
+
+// Original schema, where tasks had no project. Brand new databases are created with it, then
+// brought up to date by the migrations like any other.
+const ORIGINAL_SCHEMA: &str = "
+    CREATE TABLE tasks (
+        id INTEGER PRIMARY KEY,
+        priority TEXT NOT NULL,
+        description TEXT NOT NULL,
+        completed INTEGER NOT NULL
+    );
+
+    CREATE TABLE presets (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+
+    CREATE TABLE preset_tasks (
+        id INTEGER PRIMARY KEY,
+        preset_id INTEGER NOT NULL,
+        priority TEXT NOT NULL,
+        description TEXT NOT NULL,
+
+        FOREIGN KEY(preset_id)
+        REFERENCES presets(id)
+        ON DELETE CASCADE
+    );
+";
+
+// Change bringing the schema from a version to the next
+struct Migration {
+    // First table or column brought by the change, telling whether a database predating
+    // migrations already has it
+    introduces: SchemaItem,
+    sql: &'static str,
+}
+
+enum SchemaItem {
+    Table(&'static str),
+    Column(&'static str, &'static str),
+}
+
+// Schema changes, in order, starting from version 1: the original schema. New steps go at the
+// end, and must never be edited.
+const MIGRATIONS: &[Migration] = &[
+    // 2: tasks may pertain to a project
+    Migration {
+        introduces: SchemaItem::Column("tasks", "project"),
+        sql: "ALTER TABLE tasks ADD COLUMN project TEXT NOT NULL DEFAULT ''",
+    },
+    // 3: tasks remember the preset they were injected from
+    Migration {
+        introduces: SchemaItem::Column("tasks", "source_preset_id"),
+        sql: "ALTER TABLE tasks ADD COLUMN source_preset_id INTEGER REFERENCES presets(id) ON DELETE SET NULL",
+    },
+    // 4: tasks of the same priority may be ordered manually
+    Migration {
+        introduces: SchemaItem::Column("tasks", "order_index"),
+        sql: "ALTER TABLE tasks ADD COLUMN order_index INTEGER NOT NULL DEFAULT 0",
+    },
+    // 5: tasks may be due on some date
+    Migration {
+        introduces: SchemaItem::Column("tasks", "due_date"),
+        sql: "ALTER TABLE tasks ADD COLUMN due_date TEXT",
+    },
+    // 6: notes may be attached to tasks
+    Migration {
+        introduces: SchemaItem::Table("task_notes"),
+        sql: "
+            CREATE TABLE task_notes (
+                id INTEGER PRIMARY KEY,
+                task_id INTEGER NOT NULL,
+                body TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+
+                FOREIGN KEY(task_id)
+                REFERENCES tasks(id)
+                ON DELETE CASCADE
+            )
+        ",
+    },
+    // 7: changes to tasks are logged as events. Events outlive their task, hence no foreign key.
+    // They are logged by triggers, so that no change is missed whatever the query behind it.
+    Migration {
+        introduces: SchemaItem::Table("task_events"),
+        sql: "
+            CREATE TABLE task_events (
+                id INTEGER PRIMARY KEY,
+                task_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                occurred_at INTEGER NOT NULL
+            );
+
+            CREATE TRIGGER task_created AFTER INSERT ON tasks
+            BEGIN
+                INSERT INTO task_events (task_id, kind, occurred_at)
+                VALUES (NEW.id, 'created', unixepoch());
+            END;
+
+            CREATE TRIGGER task_completed AFTER UPDATE OF completed ON tasks
+            WHEN NEW.completed AND NOT OLD.completed
+            BEGIN
+                INSERT INTO task_events (task_id, kind, occurred_at)
+                VALUES (NEW.id, 'completed', unixepoch());
+            END;
+
+            CREATE TRIGGER task_edited AFTER UPDATE ON tasks
+            WHEN OLD.priority IS NOT NEW.priority
+                OR OLD.description IS NOT NEW.description
+                OR OLD.project IS NOT NEW.project
+                OR OLD.due_date IS NOT NEW.due_date
+                OR (OLD.completed AND NOT NEW.completed)
+            BEGIN
+                INSERT INTO task_events (task_id, kind, occurred_at)
+                VALUES (NEW.id, 'edited', unixepoch());
+            END;
+
+            CREATE TRIGGER task_deleted AFTER DELETE ON tasks
+            BEGIN
+                INSERT INTO task_events (task_id, kind, occurred_at)
+                VALUES (OLD.id, 'deleted', unixepoch());
+            END;
+        ",
+    },
+    // 8: tasks may be split into subtasks
+    Migration {
+        introduces: SchemaItem::Table("subtasks"),
+        sql: "
+            CREATE TABLE subtasks (
+                id INTEGER PRIMARY KEY,
+                task_id INTEGER NOT NULL,
+                description TEXT NOT NULL,
+                completed INTEGER NOT NULL,
+
+                FOREIGN KEY(task_id)
+                REFERENCES tasks(id)
+                ON DELETE CASCADE
+            )
+        ",
+    },
+    // 9: task list filters may be saved as named views
+    Migration {
+        introduces: SchemaItem::Table("saved_views"),
+        sql: "
+            CREATE TABLE saved_views (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                filters_json TEXT NOT NULL
+            )
+        ",
+    },
+    // 10: tasks record when they were created and completed, as Unix timestamps. They stay
+    // unknown for older tasks. Timestamps already set (e.g. when restoring a backup) are left
+    // untouched.
+    Migration {
+        introduces: SchemaItem::Column("tasks", "created_at"),
+        sql: "
+            ALTER TABLE tasks ADD COLUMN created_at INTEGER;
+            ALTER TABLE tasks ADD COLUMN completed_at INTEGER;
+
+            CREATE TRIGGER task_created_at AFTER INSERT ON tasks
+            WHEN NEW.created_at IS NULL OR (NEW.completed AND NEW.completed_at IS NULL)
+            BEGIN
+                UPDATE tasks SET
+                    created_at = COALESCE(created_at, unixepoch()),
+                    completed_at = CASE WHEN completed THEN COALESCE(completed_at, unixepoch()) END
+                WHERE id = NEW.id;
+            END;
+
+            CREATE TRIGGER task_completed_at AFTER UPDATE OF completed ON tasks
+            WHEN NEW.completed IS NOT OLD.completed
+            BEGIN
+                UPDATE tasks SET completed_at = CASE WHEN NEW.completed THEN unixepoch() END
+                WHERE id = NEW.id;
+            END;
+        ",
+    },
+    // 11: tasks may recur
+    Migration {
+        introduces: SchemaItem::Column("tasks", "recurrence"),
+        sql: "ALTER TABLE tasks ADD COLUMN recurrence TEXT",
+    },
+    // 12: tasks may be tagged. Like projects, tags are created on the fly when first given to a
+    // task.
+    Migration {
+        introduces: SchemaItem::Table("tags"),
+        sql: "
+            CREATE TABLE tags (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE
+            );
+
+            CREATE TABLE task_tags (
+                task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (task_id, tag_id)
+            );
+        ",
+    },
+    // 13: completed tasks may be moved to an archive. Archived tasks get IDs of their own, live
+    // tasks possibly reusing theirs in the meantime.
+    Migration {
+        introduces: SchemaItem::Table("archived_tasks"),
+        sql: "
+            CREATE TABLE archived_tasks (
+                id INTEGER PRIMARY KEY,
+                priority TEXT NOT NULL,
+                description TEXT NOT NULL,
+                completed INTEGER NOT NULL,
+                project TEXT NOT NULL,
+                source_preset_id INTEGER REFERENCES presets(id) ON DELETE SET NULL,
+                due_date TEXT,
+                created_at INTEGER,
+                completed_at INTEGER,
+                recurrence TEXT,
+                recurrence_paused INTEGER NOT NULL DEFAULT FALSE,
+                hidden INTEGER NOT NULL DEFAULT FALSE,
+                archived_at INTEGER NOT NULL
+            )
+        ",
+    },
+    // 14: recurrences may be paused
+    Migration {
+        introduces: SchemaItem::Column("tasks", "recurrence_paused"),
+        sql: "ALTER TABLE tasks ADD COLUMN recurrence_paused INTEGER NOT NULL DEFAULT FALSE",
+    },
+    // 15: projects may have a color. Projects still only exist through their tasks: this merely
+    // holds their optional attributes.
+    Migration {
+        introduces: SchemaItem::Table("projects"),
+        sql: "
+            CREATE TABLE projects (
+                name TEXT PRIMARY KEY,
+                color TEXT NOT NULL
+            )
+        ",
+    },
+    // 16: tasks may be hidden from the task list
+    Migration {
+        introduces: SchemaItem::Column("tasks", "hidden"),
+        sql: "ALTER TABLE tasks ADD COLUMN hidden INTEGER NOT NULL DEFAULT FALSE",
+    },
+    // 17: tasks may depend on others
+    Migration {
+        introduces: SchemaItem::Table("task_dependencies"),
+        sql: "
+            CREATE TABLE task_dependencies (
+                task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                depends_on_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                PRIMARY KEY (task_id, depends_on_id)
+            )
+        ",
+    },
+    // 18: completed tasks are counted per month, so that their details can be deleted
+    Migration {
+        introduces: SchemaItem::Table("monthly_summaries"),
+        sql: "
+            CREATE TABLE monthly_summaries (
+                year INTEGER NOT NULL,
+                month INTEGER NOT NULL,
+                project TEXT NOT NULL,
+                completed INTEGER NOT NULL,
+                PRIMARY KEY (year, month, project)
+            )
+        ",
+    },
+    // 19: the archive keeps the notes, subtasks and tags of archived tasks
+    Migration {
+        introduces: SchemaItem::Table("archived_task_notes"),
+        sql: "
+            CREATE TABLE archived_task_notes (
+                id INTEGER PRIMARY KEY,
+                task_id INTEGER NOT NULL REFERENCES archived_tasks(id) ON DELETE CASCADE,
+                body TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE archived_subtasks (
+                id INTEGER PRIMARY KEY,
+                task_id INTEGER NOT NULL REFERENCES archived_tasks(id) ON DELETE CASCADE,
+                description TEXT NOT NULL,
+                completed INTEGER NOT NULL
+            );
+
+            CREATE TABLE archived_task_tags (
+                task_id INTEGER NOT NULL REFERENCES archived_tasks(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (task_id, tag_id)
+            );
+        ",
+    },
+];
+// Version reached once all migrations are applied
+const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+// Latest version recorded before every schema change was a migration. Databases up to it were
+// also brought up to date by other means, and may already have any of the later changes.
+const LEGACY_SCHEMA_VERSION: u32 = 2;
+
+// Columns expected by `task_from_row`, in order
+const TASK_COLUMNS: &str = "id, priority, description, completed, project, source_preset_id, due_date, recurrence, recurrence_paused, COALESCE(created_at, 0), hidden";
+// Order in which tasks are shown to the user. Unprioritized tasks are stored with an empty
+// priority, and must come after all others.
+const TASK_LIST_ORDER: &str =
+    "completed ASC, priority = '' ASC, priority ASC, order_index ASC, description ASC";
+// Orders in which the task list may be sorted. Completed tasks always come last.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SortBy {
+    #[default]
+    Priority,
+    Description,
+    Created,
+    DueDate,
+}
+
+impl SortBy {
+    fn order_by(&self) -> &'static str {
+        match self {
+            SortBy::Priority => TASK_LIST_ORDER,
+            SortBy::Description => "completed ASC, description ASC, id ASC",
+            SortBy::Created => "completed ASC, created_at ASC, id ASC",
+            SortBy::DueDate => {
+                "completed ASC, due_date IS NULL ASC, due_date ASC, priority = '' ASC, priority ASC, order_index ASC, description ASC"
+            }
+        }
+    }
+}
+
+// How project names are compared when filtering on them
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ProjectMatch {
+    #[default]
+    Exact,
+    Normalized, // Ignoring case and accents, e.g. "Équipe" matching "equipe"
+}
+
+// Filters of the task list, each one left to `None` selecting every task. The task list usually
+// leaves hidden tasks out, with `Some(false)` as hidden filter.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TaskFilter<'a> {
+    pub project: Option<&'a str>,
+    pub project_match: ProjectMatch,
+    pub completed: Option<bool>,
+    pub tag: Option<&'a str>,
+    pub hidden: Option<bool>,
+    // Bounds of the priority range, both included, leaving unprioritized tasks out
+    pub highest_priority: Option<&'a str>,
+    pub lowest_priority: Option<&'a str>,
+}
+
+// Collation comparing project names the way `ProjectMatch::Normalized` does
+const PROJECT_NAME_COLLATION: &str = "PROJECT_NAME";
+
+// Lowercases the name and strips the accents of Latin letters
+fn fold_project_name(name: &str) -> String {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            c => c,
+        })
+        .collect()
+}
+
+// Drops surrounding whitespace, rejecting tags left empty
+fn validate_tag(tag: &str) -> Result<&str, TaskRepoError> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(TaskRepoError::Invalid {
+            reason: "Tags cannot be empty".into(),
+        });
+    }
+    Ok(tag)
+}
+
+// Notes may span several lines, but cannot be blank
+fn validate_note(body: &str) -> Result<&str, TaskRepoError> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(TaskRepoError::Invalid {
+            reason: "Notes cannot be empty".into(),
+        });
+    }
+    Ok(body)
+}
+
+// Rejects colors not formatted as "#rrggbb", lowercasing the others
+fn validate_color(color: &str) -> Result<String, TaskRepoError> {
+    let is_valid_color = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !is_valid_color {
+        return Err(TaskRepoError::Invalid {
+            reason: format!("Invalid color {color}, expected #rrggbb"),
+        });
+    }
+    Ok(color.to_ascii_lowercase())
+}
+
+// Slice of the task list, the first `offset` tasks being skipped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Page {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+// Every task and preset, presets holding their own tasks, along with project colors. Unlike
+// backups, the archive, saved views and the event log are left out.
+#[derive(Serialize, Debug)]
+pub struct ExportBundle {
+    pub tasks: Vec<ExportedTask>,
+    pub presets: Vec<Preset>,
+    pub project_colors: HashMap<String, String>,
+}
+
+// Task along with what is stored apart from it
+#[derive(Serialize, Debug)]
+pub struct ExportedTask {
+    #[serde(flatten)]
+    pub task: Task,
+    pub completed_at: Option<i64>, // Unix timestamp, None if pending or unknown
+    pub notes: Vec<Note>,
+    pub depends_on: Vec<TaskId>, // IDs of other tasks of the bundle
+}
+
+// Tables holding the details of tasks, in the task list then in the archive, along with their
+// columns besides the ID of the task
+const TASK_DETAIL_TABLES: [(&str, &str, &str); 3] = [
+    ("task_notes", "archived_task_notes", "body, created_at"),
+    ("subtasks", "archived_subtasks", "description, completed"),
+    ("task_tags", "archived_task_tags", "tag_id"),
+];
+
+// Tasks deleted by a cleanup: completed ones, except for the `:keep_min` most recently completed
+const CLEANED_UP_TASK_IDS: &str = "
+    SELECT id FROM tasks WHERE completed AND id NOT IN (
+        SELECT id FROM tasks WHERE completed
+        ORDER BY completed_at IS NULL ASC, completed_at DESC, id DESC
+        LIMIT :keep_min
+    )
+";
+
+// Tables whose rows are deleted by a cleanup, tasks first so that they are restored first, along
+// with the condition for a row to go with the cleaned up tasks and the one to be restored
+const CLEANUP_TABLES: [(&str, &str, &str); 5] = [
+    ("tasks", "id IN ({cleaned_up})", "TRUE"),
+    ("task_notes", "task_id IN ({cleaned_up})", "TRUE"),
+    ("subtasks", "task_id IN ({cleaned_up})", "TRUE"),
+    ("task_tags", "task_id IN ({cleaned_up})", "TRUE"),
+    (
+        "task_dependencies",
+        "task_id IN ({cleaned_up}) OR depends_on_id IN ({cleaned_up})",
+        "EXISTS (SELECT 1 FROM tasks WHERE id = :task_id)
+        AND EXISTS (SELECT 1 FROM tasks WHERE id = :depends_on_id)",
+    ),
+];
+
+// Rows deleted by a cleanup, as they were stored
+#[derive(Debug)]
+pub struct CleanedUpTasks {
+    tables: Vec<CleanedUpRows>,
+}
+
+#[derive(Debug)]
+struct CleanedUpRows {
+    table: &'static str,
+    restorable: &'static str,
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl CleanedUpTasks {
+    pub fn task_count(&self) -> usize {
+        self.tables
+            .iter()
+            .find(|cleaned_up_rows| cleaned_up_rows.table == "tasks")
+            .map_or(0, |cleaned_up_rows| cleaned_up_rows.rows.len())
+    }
+}
+
+// Columns expected by `subtask_from_row`, in order
+const SUBTASK_COLUMNS: &str = "id, task_id, description, completed";
 
 pub struct TaskRepo {
     connection_factory: Arc<dyn SqlConnectionFactory>,
+    connection: Option<SqlConnection>, // Opened on first use, then reused by all queries
 }
 
 #[derive(Debug)]
 pub enum TaskRepoError {
     Error { error: String },
+    NotFound { what: String },
+    Duplicate { what: String },
+    Invalid { reason: String }, // Input rejected before reaching storage
     SqlError { original_error: rusqlite::Error },
     IoError { original_error: std::io::Error },
     JinjaError { original_error: minijinja::Error }, // TODO: this is not really a repo error...
     TaskError { original_error: TaskError },         // TODO: this is not really a repo error...
     PresetTaskError { original_error: PresetTaskError }, // TODO: this is not really a repo error...
+    JsonError { original_error: serde_json::Error },
+}
+
+// Common SQL errors that callers may want to handle specifically
+#[derive(Debug, PartialEq)]
+pub enum SqlErrorKind {
+    ConstraintViolation, // e.g. a name that must be unique is already taken
+    Busy,                // The database is locked by another connection
+    NotFound,            // A query expected to return a row returned none
+}
+
+impl TaskRepoError {
+    pub fn sql_error_kind(&self) -> Option<SqlErrorKind> {
+        let TaskRepoError::SqlError { original_error } = self else {
+            return None;
+        };
+        match original_error {
+            rusqlite::Error::QueryReturnedNoRows => Some(SqlErrorKind::NotFound),
+            _ => match original_error.sqlite_error_code()? {
+                ErrorCode::ConstraintViolation => Some(SqlErrorKind::ConstraintViolation),
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => Some(SqlErrorKind::Busy),
+                _ => None,
+            },
+        }
+    }
+}
+
+// Meant for logs, as it details internal errors
+impl std::fmt::Display for TaskRepoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Error { error } => write!(f, "{}", error),
+            Self::NotFound { what } => write!(f, "{} not found in storage", what),
+            Self::Duplicate { what } => write!(f, "{} already exists", what),
+            Self::Invalid { reason } => write!(f, "Invalid input: {}", reason),
+            Self::SqlError { original_error } => write!(f, "SQL error: {}", original_error),
+            Self::IoError { original_error } => write!(f, "I/O error: {}", original_error),
+            Self::JinjaError { original_error } => {
+                write!(f, "Templating error: {}", original_error)
+            }
+            Self::TaskError { original_error } => write!(f, "Task error: {}", original_error),
+            Self::PresetTaskError { original_error } => {
+                write!(f, "Preset task error: {}", original_error)
+            }
+            Self::JsonError { original_error } => write!(f, "JSON error: {}", original_error),
+        }
+    }
 }
 
 impl From<rusqlite::Error> for TaskRepoError {
@@ -51,6 +611,14 @@ impl From<TaskError> for TaskRepoError {
     }
 }
 
+impl From<serde_json::Error> for TaskRepoError {
+    fn from(value: serde_json::Error) -> Self {
+        TaskRepoError::JsonError {
+            original_error: value,
+        }
+    }
+}
+
 impl From<PresetTaskError> for TaskRepoError {
     fn from(value: PresetTaskError) -> Self {
         TaskRepoError::PresetTaskError {
@@ -61,21 +629,33 @@ impl From<PresetTaskError> for TaskRepoError {
 
 impl TaskRepo {
     pub fn new(connection_factory: Arc<dyn SqlConnectionFactory>) -> TaskRepo {
-        TaskRepo { connection_factory }
+        TaskRepo {
+            connection_factory,
+            connection: None,
+        }
+    }
+
+    fn conn(&mut self) -> Result<&mut Connection, TaskRepoError> {
+        if self.connection.is_none() {
+            let conn = self.connection_factory.open()?;
+            conn.create_collation(PROJECT_NAME_COLLATION, |a, b| {
+                fold_project_name(a).cmp(&fold_project_name(b))
+            })?;
+            self.connection = Some(conn);
+        }
+        Ok(self
+            .connection
+            .as_mut()
+            .expect("Connection should have been opened"))
     }
 
     fn task_from_row(row: &Row) -> Result<Task, TaskRepoError> {
         Ok(Task {
             id: row.get(0)?,
-            priority: row
-                .get::<usize, String>(1)?
-                .chars()
-                .nth(0)
-                .ok_or(TaskRepoError::Error {
-                    error: String::from("Priority in storage was empty"),
-                })?,
+            priority: row.get::<usize, String>(1)?.chars().nth(0), // Empty for unprioritized tasks
             description: row.get(2)?,
             completed: row.get(3)?,
+            hidden: row.get(10)?,
             project: {
                 let raw: String = row.get(4)?;
                 match raw.len() {
@@ -83,6 +663,26 @@ impl TaskRepo {
                     _ => Some(raw),
                 }
             },
+            source_preset_id: row.get(5)?,
+            due_date: row.get(6)?,
+            subtasks: vec![], // Stored apart, as tags
+            recurrence: row
+                .get::<usize, Option<String>>(7)?
+                .map(|raw| raw.parse())
+                .transpose()?,
+            recurrence_paused: row.get(8)?,
+            tags: vec![],
+            blocked: false, // Depends on other tasks
+            created_at: row.get(9)?,
+        })
+    }
+
+    fn subtask_from_row(row: &Row) -> Result<Subtask, rusqlite::Error> {
+        Ok(Subtask {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            description: row.get(2)?,
+            completed: row.get(3)?,
         })
     }
 
@@ -102,394 +702,5341 @@ impl TaskRepo {
     }
 
     pub fn init_db(&mut self) -> Result<(), TaskRepoError> {
-        let conn = self.connection_factory.open()?;
-        conn.execute(
-            "
-            CREATE TABLE IF NOT EXISTS tasks (
-                id INTEGER PRIMARY KEY,
-                priority TEXT NOT NULL,
-                description TEXT NOT NULL,
-                completed INTEGER NOT NULL,
-                project TEXT NOT NULL
-            )
-            ",
-            (),
-        )?;
+        self.run_migrations()?;
+        Ok(())
+    }
 
+    // Applies the migrations the database is missing, all at once or not at all, returning the
+    // version reached
+    pub fn run_migrations(&mut self) -> Result<u32, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let version = Self::migrate(&tx)?;
+        tx.commit()?;
+        Ok(version)
+    }
+
+    fn migrate(conn: &Connection) -> Result<u32, TaskRepoError> {
         conn.execute(
-            "
-            CREATE TABLE IF NOT EXISTS presets (
-                id INTEGER PRIMARY KEY,
-                name TEXT NOT NULL UNIQUE
-            )
-            ",
+            "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)",
             (),
         )?;
+        let recorded_version: Option<u32> =
+            conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+                row.get(0)
+            })?;
 
-        conn.execute(
-            "
-            CREATE TABLE IF NOT EXISTS preset_tasks (
-                id INTEGER PRIMARY KEY,
-                preset_id INTEGER NOT NULL,
-                priority TEXT NOT NULL,
-                description TEXT NOT NULL,
+        let current_version = match recorded_version {
+            Some(version) => version,
+            None if !Self::table_exists(conn, "tasks")? => {
+                conn.execute_batch(ORIGINAL_SCHEMA)?;
+                1
+            }
+            // Databases predating recorded versions are treated like legacy ones
+            None => 1,
+        };
+        if current_version > SCHEMA_VERSION {
+            return Err(TaskRepoError::Error {
+                error: format!(
+                    "Database schema version {current_version} is newer than the supported {SCHEMA_VERSION}"
+                ),
+            });
+        }
 
-                FOREIGN KEY(preset_id)
-                REFERENCES presets(id)
-                ON DELETE CASCADE
-            )
-            ",
-            (),
+        for migration in &MIGRATIONS[current_version as usize - 1..] {
+            if current_version <= LEGACY_SCHEMA_VERSION
+                && Self::schema_item_exists(conn, &migration.introduces)?
+            {
+                continue;
+            }
+            conn.execute_batch(migration.sql)?;
+        }
+        conn.execute("DELETE FROM schema_version", ())?;
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?)",
+            [SCHEMA_VERSION],
         )?;
 
-        Ok(())
+        Ok(SCHEMA_VERSION)
     }
 
-    pub fn get_all_tasks(
-        &mut self,
-        project_filter: Option<&str>,
-    ) -> Result<Vec<Task>, TaskRepoError> {
-        let conn = self.connection_factory.open()?;
-
-        let mut stmt_sql: String =
-            "SELECT id, priority, description, completed, project FROM tasks ".into();
-        if project_filter.is_some() {
-            stmt_sql.push_str("WHERE project = :project ");
+    fn schema_item_exists(conn: &Connection, item: &SchemaItem) -> Result<bool, TaskRepoError> {
+        match item {
+            SchemaItem::Table(table) => Self::table_exists(conn, table),
+            SchemaItem::Column(table, column) => Self::column_exists(conn, table, column),
         }
-        stmt_sql.push_str("ORDER BY completed ASC, priority ASC, description ASC");
+    }
 
-        let mut stmt = conn.prepare(&stmt_sql)?;
-        let params = match project_filter {
-            None => vec![],
-            Some(s) => vec![s],
-        };
-        let rows = stmt.query_and_then(params_from_iter(params), Self::task_from_row)?;
-        rows.into_iter().collect()
+    fn table_exists(conn: &Connection, table: &str) -> Result<bool, TaskRepoError> {
+        let mut stmt =
+            conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")?;
+        Ok(stmt.exists([table])?)
     }
 
-    pub fn get_task(&mut self, task_id: TaskId) -> Result<Task, TaskRepoError> {
-        let conn = self.connection_factory.open()?;
-        let mut stmt = conn.prepare(
-            "
-            SELECT id, priority, description, completed, project FROM tasks
-            WHERE id = ?
-            ",
-        )?;
+    fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, TaskRepoError> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?"
+        ))?;
+        Ok(stmt.exists([column])?)
+    }
 
-        let mut rows = stmt.query([task_id])?;
-        let row = rows.next()?.ok_or(TaskRepoError::Error {
-            error: format!("Task {} not found in storage", task_id),
-        })?;
+    // Copies the whole database into the given file, overwriting it. Unlike a plain file copy,
+    // this is safe even when the database is being written to.
+    pub fn backup_to(&mut self, dest_path: &Path) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        conn.backup(MAIN_DB, dest_path, None)?;
 
-        Self::task_from_row(row)
+        Ok(())
     }
 
-    pub fn persist_task(&mut self, task: &Task) -> Result<(), TaskRepoError> {
-        let conn = self.connection_factory.open()?;
-        if task.id < 0 {
-            // New task, need to insert
-            let mut stmt = conn.prepare(
-                "
-            INSERT INTO tasks (priority, description, completed, project)
-            VALUES (:priority, :description, :completed, :project)
-            ",
-            )?;
+    // Replaces all data with the content of a database backup. The backup is validated first, and
+    // upgraded to the current schema if it comes from an older version.
+    pub fn restore_from(&mut self, backup_path: &Path) -> Result<(), TaskRepoError> {
+        let invalid_backup = |reason: String| TaskRepoError::Invalid {
+            reason: format!("Invalid backup: {}", reason),
+        };
 
-            let params = named_params! {":priority": String::from(task.priority), ":description": task.description, ":completed": task.completed, ":project": task.project.as_deref().unwrap_or("")};
-            stmt.execute(params)?;
-            Ok(())
-        } else {
-            // Existing task, need to update
-            let mut stmt = conn.prepare(
-                "
-            UPDATE tasks SET
-            priority = :priority, description = :description, completed = :completed
-            WHERE id = :id",
-            )?;
-            let params = named_params! {":priority": String::from(task.priority), ":description": task.description, ":completed": task.completed, ":id": task.id};
-            stmt.execute(params)?;
-            Ok(())
+        {
+            let backup_conn = Connection::open(backup_path)?;
+            let integrity: String = backup_conn
+                .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+                .map_err(|error| invalid_backup(error.to_string()))?;
+            if integrity != "ok" {
+                return Err(invalid_backup(integrity));
+            }
+            for table in ["tasks", "presets", "preset_tasks"] {
+                if !Self::table_exists(&backup_conn, table)? {
+                    return Err(invalid_backup(format!("table {} is missing", table)));
+                }
+            }
+            // Their schema is theirs to begin with, so failing to upgrade it is their fault
+            Self::migrate(&backup_conn).map_err(|error| invalid_backup(error.to_string()))?;
         }
-    }
 
-    pub fn persist_preset_task(&mut self, preset_task: PresetTask) -> Result<(), TaskRepoError> {
-        let conn = self.connection_factory.open()?;
-        if preset_task.id < 0 {
-            // New task, need to insert
-            let mut stmt = conn.prepare(
-                "
-            INSERT INTO preset_tasks (preset_id, priority, description)
-            VALUES (:preset_id, :priority, :description)
-            ",
-            )?;
+        let backup_path_str = backup_path.to_str().ok_or(TaskRepoError::Error {
+            error: format!("Backup path {} is not valid UTF-8", backup_path.display()),
+        })?;
+        let conn = self.conn()?;
+        conn.execute("ATTACH DATABASE ? AS backup", [backup_path_str])?;
 
-            let params = named_params! {":preset_id": preset_task.preset_id, ":priority": String::from(preset_task.priority), ":description": preset_task.description};
-            stmt.execute(params)?;
-            Ok(())
-        } else {
-            Err(TaskRepoError::Error {
-                error:
-                    "Cannot persist a non-new preset task (i.e. preset task update not implemented)"
-                        .into(),
-            })
-        }
+        // The backup must be detached even on failure, as the connection is reused afterwards
+        let result = Self::copy_from_backup(conn);
+        conn.execute("DETACH DATABASE backup", ())?;
+        result
     }
 
-    pub fn cleanup(&mut self) -> Result<(), TaskRepoError> {
-        let conn = self.connection_factory.open()?;
-
-        conn.execute("DELETE FROM tasks WHERE completed", [])?;
+    // All tables are copied over from the attached backup, column by column, in a single
+    // transaction
+    fn copy_from_backup(conn: &mut Connection) -> Result<(), TaskRepoError> {
+        let tx = conn.transaction()?;
 
-        Ok(())
-    }
+        // Tables are copied in no particular order, so foreign keys are only checked on commit
+        tx.execute("PRAGMA defer_foreign_keys = ON", ())?;
 
-    pub fn get_all_projects(&mut self) -> Result<Vec<String>, rusqlite::Error> {
-        let conn = self.connection_factory.open()?;
-        let mut stmt = conn.prepare(
-            "
-            SELECT DISTINCT project FROM tasks
-            WHERE project != ''
-            ORDER BY project ASC
-            ",
-        )?;
+        let tables: Vec<String> = tx
+            .prepare(
+                "SELECT name FROM main.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
 
-        stmt.query_map([], |row| row.get::<_, String>(0))?.collect()
-    }
+        // Everything is deleted first, so that cascading deletions do not affect copied rows
+        for table in &tables {
+            tx.execute(&format!("DELETE FROM main.{table}"), ())?;
+        }
+        for table in tables.iter().filter(|table| *table != "task_events") {
+            let columns: Vec<String> = tx
+                .prepare(&format!("SELECT name FROM pragma_table_info('{table}')"))?
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            let columns = columns.join(", ");
+            tx.execute(
+                &format!(
+                    "INSERT INTO main.{table} ({columns}) SELECT {columns} FROM backup.{table}"
+                ),
+                (),
+            )?;
+        }
 
-    pub fn rename_project(
-        &mut self,
-        current_project_name: &str,
-        new_project_name: &str,
-    ) -> Result<(), TaskRepoError> {
-        let conn = self.connection_factory.open()?;
-        let mut stmt = conn.prepare(
+        // Copying tasks has logged events of its own, replaced with the ones of the backup
+        tx.execute("DELETE FROM main.task_events", ())?;
+        tx.execute(
             "
-            UPDATE tasks
-            SET project = :new_project_name
-            WHERE project = :current_project_name
+            INSERT INTO main.task_events (id, task_id, kind, occurred_at)
+            SELECT id, task_id, kind, occurred_at FROM backup.task_events
             ",
+            (),
         )?;
-        stmt.execute(named_params!{":current_project_name": current_project_name, ":new_project_name": new_project_name})?;
+        tx.commit()?;
 
         Ok(())
     }
 
-    pub fn add_preset(&mut self, new_preset_name: &str) -> Result<(), rusqlite::Error> {
-        let conn = self.connection_factory.open()?;
-        let mut stmt = conn.prepare(
-            "
-            INSERT INTO presets
-            (name)
-            VALUES (:new_preset_name)
-            ",
-        )?;
-        stmt.execute(named_params! {":new_preset_name": new_preset_name})?;
+    // An empty project filter selects the tasks outside of any project
+    pub fn get_all_tasks(
+        &mut self,
+        filter: &TaskFilter,
+        sort_by: SortBy,
+        page: Option<Page>,
+    ) -> Result<Vec<Task>, TaskRepoError> {
+        let (conditions, mut params) = Self::task_list_conditions(filter);
 
-        Ok(())
+        let mut order_by = sort_by.order_by().to_string();
+        // SQLite integers are signed, so larger pages are as good as unlimited
+        let page = page.map(|page| {
+            (
+                i64::try_from(page.limit).unwrap_or(i64::MAX),
+                i64::try_from(page.offset).unwrap_or(i64::MAX),
+            )
+        });
+        if let Some((limit, offset)) = &page {
+            order_by.push_str(" LIMIT :limit OFFSET :offset");
+            params.push((":limit", limit));
+            params.push((":offset", offset));
+        }
+        self.get_tasks_where(&conditions, &params, &order_by)
     }
 
-    pub fn get_all_preset_names(&mut self) -> Result<Vec<String>, rusqlite::Error> {
-        let conn = self.connection_factory.open()?;
-        let mut stmt = conn.prepare(
-            "
-            SELECT DISTINCT name FROM presets
-            ORDER BY name ASC
-            ",
-        )?;
+    // Number of tasks `get_all_tasks` would return with the same filter, if not paginated
+    pub fn count_tasks(&mut self, filter: &TaskFilter) -> Result<usize, TaskRepoError> {
+        let (conditions, params) = Self::task_list_conditions(filter);
 
-        stmt.query_map([], |row| row.get::<_, String>(0))?.collect()
+        let mut stmt_sql = "SELECT COUNT(*) FROM tasks ".to_string();
+        if !conditions.is_empty() {
+            stmt_sql.push_str(&format!("WHERE {}", conditions.join(" AND ")));
+        }
+        let count: i64 = self
+            .conn()?
+            .query_row(&stmt_sql, params.as_slice(), |row| row.get(0))?;
+
+        Ok(count as usize)
     }
 
-    pub fn get_preset_id_from_preset_name(
-        &mut self,
+    // Conditions and parameters shared by `get_all_tasks` and `count_tasks`
+    fn task_list_conditions<'a>(
+        filter: &'a TaskFilter,
+    ) -> (Vec<&'static str>, Vec<(&'static str, &'a dyn ToSql)>) {
+        let mut conditions = vec![];
+        let mut params: Vec<(&str, &dyn ToSql)> = vec![];
+        if let Some(project) = &filter.project {
+            conditions.push(match filter.project_match {
+                ProjectMatch::Exact => "project = :project",
+                ProjectMatch::Normalized => "project = :project COLLATE PROJECT_NAME", // See PROJECT_NAME_COLLATION
+            });
+            params.push((":project", project));
+        }
+        if let Some(completed) = &filter.completed {
+            conditions.push("completed = :completed");
+            params.push((":completed", completed));
+        }
+        if let Some(tag) = &filter.tag {
+            conditions.push(
+                "id IN (SELECT task_id FROM task_tags JOIN tags ON tags.id = tag_id WHERE name = :tag)",
+            );
+            params.push((":tag", tag));
+        }
+        if let Some(hidden) = &filter.hidden {
+            conditions.push("hidden = :hidden");
+            params.push((":hidden", hidden));
+        }
+        // Unprioritized tasks have an empty priority, which sorts before any other
+        if let Some(highest_priority) = &filter.highest_priority {
+            conditions.push("priority != '' AND priority >= :highest_priority");
+            params.push((":highest_priority", highest_priority));
+        }
+        if let Some(lowest_priority) = &filter.lowest_priority {
+            conditions.push("priority != '' AND priority <= :lowest_priority");
+            params.push((":lowest_priority", lowest_priority));
+        }
+
+        (conditions, params)
+    }
+
+    // Pending tasks that still lack a due date
+    pub fn get_undated_tasks(
+        &mut self,
+        project_filter: Option<&str>,
+    ) -> Result<Vec<Task>, TaskRepoError> {
+        let mut conditions = vec!["completed = FALSE", "due_date IS NULL"];
+        let mut params: Vec<(&str, &dyn ToSql)> = vec![];
+        if let Some(project) = &project_filter {
+            conditions.push("project = :project");
+            params.push((":project", project));
+        }
+
+        self.get_tasks_where(&conditions, &params, TASK_LIST_ORDER)
+    }
+
+    // Earliest created pending task of each project, by project name
+    pub fn oldest_pending_per_project(&mut self) -> Result<Vec<(String, Task)>, TaskRepoError> {
+        let tasks = self.get_tasks_where(
+            &[
+                "project != ''",
+                "
+                id = (
+                    SELECT oldest.id FROM tasks AS oldest
+                    WHERE oldest.project = tasks.project AND NOT oldest.completed
+                    ORDER BY oldest.created_at ASC, oldest.id ASC
+                    LIMIT 1
+                )
+                ",
+            ],
+            &[],
+            "project ASC",
+        )?;
+
+        Ok(tasks
+            .into_iter()
+            .map(|task| (task.project.clone().unwrap_or_default(), task))
+            .collect())
+    }
+
+    // Tasks of the given priority, which must pertain to the priority scheme
+    pub fn get_by_priority(
+        &mut self,
+        priority_scheme: &PriorityScheme,
+        priority: char,
+        project_filter: Option<&str>,
+    ) -> Result<Vec<Task>, TaskRepoError> {
+        if !priority_scheme.contains(priority) {
+            return Err(TaskError::PriorityNotInRangeError(priority).into());
+        }
+
+        let priority = String::from(priority);
+        let mut conditions = vec!["priority = :priority"];
+        let mut params: Vec<(&str, &dyn ToSql)> = vec![(":priority", &priority)];
+        if let Some(project) = &project_filter {
+            conditions.push("project = :project");
+            params.push((":project", project));
+        }
+
+        self.get_tasks_where(&conditions, &params, TASK_LIST_ORDER)
+    }
+
+    // Tasks matching all of the given conditions, in the given order
+    fn get_tasks_where(
+        &mut self,
+        conditions: &[&str],
+        params: &[(&str, &dyn ToSql)],
+        order_by: &str,
+    ) -> Result<Vec<Task>, TaskRepoError> {
+        let conn = self.conn()?;
+
+        let mut stmt_sql = format!("SELECT {TASK_COLUMNS} FROM tasks ");
+        if !conditions.is_empty() {
+            stmt_sql.push_str(&format!("WHERE {} ", conditions.join(" AND ")));
+        }
+        stmt_sql.push_str(&format!("ORDER BY {order_by}"));
+
+        let mut tasks: Vec<Task> = conn
+            .prepare(&stmt_sql)?
+            .query_and_then(params, Self::task_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        // Subtasks of all tasks are fetched at once, then dispatched to their task
+        let task_ids: Vec<TaskId> = tasks.iter().map(|task| task.id).collect();
+        let mut subtasks: HashMap<TaskId, Vec<Subtask>> = HashMap::new();
+        for subtask in conn
+            .prepare(&format!(
+                "SELECT {SUBTASK_COLUMNS} FROM subtasks WHERE task_id IN ({}) ORDER BY id ASC",
+                Self::placeholders(task_ids.len())
+            ))?
+            .query_map(params_from_iter(&task_ids), Self::subtask_from_row)?
+        {
+            let subtask = subtask?;
+            subtasks.entry(subtask.task_id).or_default().push(subtask);
+        }
+        let mut tags: HashMap<TaskId, Vec<String>> = HashMap::new();
+        for task_tag in conn
+            .prepare(&format!(
+                "
+                SELECT task_id, name FROM task_tags JOIN tags ON tags.id = tag_id
+                WHERE task_id IN ({}) ORDER BY name ASC
+                ",
+                Self::placeholders(task_ids.len())
+            ))?
+            .query_map(params_from_iter(&task_ids), |row| {
+                Ok((row.get::<usize, TaskId>(0)?, row.get::<usize, String>(1)?))
+            })?
+        {
+            let (task_id, tag) = task_tag?;
+            tags.entry(task_id).or_default().push(tag);
+        }
+        let blocked_task_ids = conn
+            .prepare(&format!(
+                "
+                SELECT DISTINCT task_id FROM task_dependencies
+                JOIN tasks AS blocker ON blocker.id = depends_on_id
+                WHERE NOT blocker.completed AND task_id IN ({})
+                ",
+                Self::placeholders(task_ids.len())
+            ))?
+            .query_map(params_from_iter(&task_ids), |row| row.get(0))?
+            .collect::<Result<HashSet<TaskId>, _>>()?;
+        for task in &mut tasks {
+            task.subtasks = subtasks.remove(&task.id).unwrap_or_default();
+            task.tags = tags.remove(&task.id).unwrap_or_default();
+            task.blocked = blocked_task_ids.contains(&task.id);
+        }
+
+        Ok(tasks)
+    }
+
+    // Tasks completed between the two Unix timestamps, both included, by completion time
+    pub fn get_completed_between(
+        &mut self,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<Task>, TaskRepoError> {
+        self.get_tasks_where(
+            &["completed", "completed_at BETWEEN :from AND :to"],
+            &[(":from", &from), (":to", &to)],
+            "completed_at ASC, id ASC",
+        )
+    }
+
+    // Pending tasks due before `as_of`, the most overdue first. Hidden tasks are left out.
+    pub fn get_missed_tasks(&mut self, as_of: NaiveDate) -> Result<Vec<Task>, TaskRepoError> {
+        self.get_tasks_where(
+            &["NOT completed", "NOT hidden", "due_date < :as_of"],
+            &[(":as_of", &as_of)],
+            "due_date ASC, id ASC",
+        )
+    }
+
+    pub fn get_task(&mut self, task_id: TaskId) -> Result<Task, TaskRepoError> {
+        let mut task = {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare(&format!(
+                "
+                SELECT {TASK_COLUMNS} FROM tasks
+                WHERE id = ?
+                "
+            ))?;
+
+            let mut rows = stmt.query([task_id])?;
+            let row = rows.next()?.ok_or(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            })?;
+            Self::task_from_row(row)?
+        };
+
+        task.subtasks = self.get_subtasks(task_id)?;
+        task.tags = self.get_tags_for_task(task_id)?;
+        task.blocked = self
+            .get_blockers(task_id)?
+            .iter()
+            .any(|blocker| !blocker.completed);
+        Ok(task)
+    }
+
+    // Tasks the given task depends on, whether they are completed or not
+    pub fn get_blockers(&mut self, task_id: TaskId) -> Result<Vec<Task>, TaskRepoError> {
+        self.get_tasks_where(
+            &["id IN (SELECT depends_on_id FROM task_dependencies WHERE task_id = :task_id)"],
+            &[(":task_id", &task_id)],
+            TASK_LIST_ORDER,
+        )
+    }
+
+    // Adding the same dependency twice is a no-op. Dependencies that would make a task depend on
+    // itself, even indirectly, are rejected.
+    pub fn add_dependency(
+        &mut self,
+        task_id: TaskId,
+        depends_on_id: TaskId,
+    ) -> Result<(), TaskRepoError> {
+        self.get_task(task_id)?;
+        self.get_task(depends_on_id)?;
+
+        let conn = self.conn()?;
+        let creates_cycle: bool = conn.query_row(
+            "
+            WITH RECURSIVE indirect_blockers(id) AS (
+                SELECT :depends_on_id
+                UNION
+                SELECT task_dependencies.depends_on_id FROM task_dependencies
+                JOIN indirect_blockers ON task_dependencies.task_id = indirect_blockers.id
+            )
+            SELECT EXISTS (SELECT 1 FROM indirect_blockers WHERE id = :task_id)
+            ",
+            named_params! {":task_id": task_id, ":depends_on_id": depends_on_id},
+            |row| row.get(0),
+        )?;
+        if creates_cycle {
+            return Err(TaskRepoError::Invalid {
+                reason: format!(
+                    "Task {task_id} cannot depend on task {depends_on_id}, which depends on it"
+                ),
+            });
+        }
+
+        conn.execute(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id) VALUES (?, ?)",
+            (task_id, depends_on_id),
+        )?;
+
+        Ok(())
+    }
+
+    // Removing a missing dependency is a no-op
+    pub fn remove_dependency(
+        &mut self,
+        task_id: TaskId,
+        depends_on_id: TaskId,
+    ) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "DELETE FROM task_dependencies WHERE task_id = ? AND depends_on_id = ?",
+            (task_id, depends_on_id),
+        )?;
+
+        Ok(())
+    }
+
+    // Distinct descriptions starting with the prefix, in alphabetical order. As usual with LIKE,
+    // ASCII letters are matched whatever their case.
+    pub fn description_suggestions(
+        &mut self,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<String>, TaskRepoError> {
+        let escaped_prefix = prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT DISTINCT description FROM tasks
+            WHERE description LIKE :pattern ESCAPE '\\'
+            ORDER BY description ASC
+            LIMIT :limit
+            ",
+        )?;
+        let rows = stmt.query_map(
+            named_params! {":pattern": format!("{escaped_prefix}%"), ":limit": limit},
+            |row| row.get(0),
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // Inserts a pending copy of the task, returning its ID. Subtasks, notes and tags are not copied.
+    // Like any new task, the copy cannot share its description with a pending task of the same
+    // project, so that copies of pending tasks are suffixed, e.g. "Task (copy)", "Task (copy 2)".
+    pub fn duplicate_task(&mut self, task_id: TaskId) -> Result<TaskId, TaskRepoError> {
+        let mut copy = self.get_task(task_id)?;
+        copy.id = -1;
+        copy.completed = false;
+        copy.hidden = false;
+        copy.recurrence_paused = false;
+        copy.source_preset_id = None;
+
+        let description = copy.description.clone();
+        let conn = self.conn()?;
+        let mut copy_number = 1;
+        loop {
+            match Self::insert_task_unique(conn, &copy) {
+                Err(TaskRepoError::Duplicate { .. }) => {
+                    let suffixed = match copy_number {
+                        1 => format!("{description} (copy)"),
+                        _ => format!("{description} (copy {copy_number})"),
+                    };
+                    copy.description = validate_description(&suffixed)?.into();
+                    copy_number += 1;
+                }
+                inserted => return inserted,
+            }
+        }
+    }
+
+    // Tagging a task twice with the same tag is a no-op
+    pub fn add_tag_to_task(&mut self, task_id: TaskId, tag: &str) -> Result<(), TaskRepoError> {
+        let tag = validate_tag(tag)?;
+
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?)", [tag])?;
+        tx.execute(
+            "
+            INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+            SELECT :task_id, id FROM tags WHERE name = :tag
+            ",
+            named_params! {":task_id": task_id, ":tag": tag},
+        )?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    pub fn remove_tag_from_task(
+        &mut self,
+        task_id: TaskId,
+        tag: &str,
+    ) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let deleted_rows = conn.execute(
+            "
+            DELETE FROM task_tags
+            WHERE task_id = :task_id AND tag_id = (SELECT id FROM tags WHERE name = :tag)
+            ",
+            named_params! {":task_id": task_id, ":tag": tag},
+        )?;
+
+        match deleted_rows {
+            0 => Err(TaskRepoError::NotFound {
+                what: format!("Tag {} of task {}", tag, task_id),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_tags_for_task(&mut self, task_id: TaskId) -> Result<Vec<String>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT name FROM task_tags JOIN tags ON tags.id = tag_id
+            WHERE task_id = ? ORDER BY name ASC
+            ",
+        )?;
+        let rows = stmt.query_map([task_id], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn add_subtask(
+        &mut self,
+        task_id: TaskId,
+        description: &str,
+    ) -> Result<SubtaskId, TaskRepoError> {
+        let description = validate_description(description)?;
+
+        let conn = self.conn()?;
+        let inserted_rows = conn.execute(
+            "
+            INSERT INTO subtasks (task_id, description, completed)
+            SELECT :task_id, :description, FALSE
+            WHERE EXISTS (SELECT 1 FROM tasks WHERE id = :task_id)
+            ",
+            named_params! {":task_id": task_id, ":description": description},
+        )?;
+        match inserted_rows {
+            0 => Err(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            }),
+            _ => Ok(conn.last_insert_rowid()),
+        }
+    }
+
+    // Flags the subtask as completed if pending, and conversely. Returns the updated subtask.
+    pub fn toggle_subtask(&mut self, subtask_id: SubtaskId) -> Result<Subtask, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "UPDATE subtasks SET completed = NOT completed WHERE id = ? RETURNING {SUBTASK_COLUMNS}"
+        ))?;
+
+        stmt.query_row([subtask_id], Self::subtask_from_row)
+            .optional()?
+            .ok_or(TaskRepoError::NotFound {
+                what: format!("Subtask {}", subtask_id),
+            })
+    }
+
+    pub fn get_subtasks(&mut self, task_id: TaskId) -> Result<Vec<Subtask>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SUBTASK_COLUMNS} FROM subtasks WHERE task_id = ? ORDER BY id ASC"
+        ))?;
+        let rows = stmt.query_map([task_id], Self::subtask_from_row)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // Returns the ID of the task, newly assigned if it was inserted
+    pub fn persist_task(&mut self, task: &Task) -> Result<TaskId, TaskRepoError> {
+        let conn = self.conn()?;
+        if task.id < 0 {
+            // New task, need to insert
+            Self::insert_task(conn, task)
+        } else {
+            // Existing task, need to update
+            let mut stmt = conn.prepare(
+                "
+            UPDATE tasks SET
+            priority = :priority, description = :description, completed = :completed,
+            due_date = :due_date, recurrence = :recurrence
+            WHERE id = :id",
+            )?;
+            let params = named_params! {":priority": task.priority.map(String::from).unwrap_or_default(), ":description": task.description, ":completed": task.completed, ":due_date": task.due_date, ":recurrence": task.recurrence.as_ref().map(Recurrence::as_str), ":id": task.id};
+            stmt.execute(params)?;
+            Ok(task.id)
+        }
+    }
+
+    // Takes a connection rather than the repository, so that it can be part of a transaction
+    fn insert_task(conn: &Connection, task: &Task) -> Result<TaskId, TaskRepoError> {
+        let mut stmt = conn.prepare_cached(
+            "
+            INSERT INTO tasks (priority, description, completed, project, source_preset_id, due_date, recurrence)
+            VALUES (
+                :priority, :description, :completed,
+                -- Projects differing only by case are the same, spelled as first created
+                COALESCE((SELECT project FROM tasks WHERE project = :project COLLATE NOCASE ORDER BY id LIMIT 1), :project),
+                :source_preset_id, :due_date, :recurrence
+            )
+            ",
+        )?;
+
+        let params = named_params! {":priority": task.priority.map(String::from).unwrap_or_default(), ":description": task.description, ":completed": task.completed, ":project": task.project.as_deref().unwrap_or(""), ":source_preset_id": task.source_preset_id, ":due_date": task.due_date, ":recurrence": task.recurrence.as_ref().map(Recurrence::as_str)};
+        stmt.execute(params)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // Unlike `persist_task`, refuses to insert a task already pending in the same project
+    pub fn add_task_unique(&mut self, task: &Task) -> Result<TaskId, TaskRepoError> {
+        if task.id >= 0 {
+            return Err(TaskRepoError::Error {
+                error: format!("Task {} already exists", task.id),
+            });
+        }
+
+        Self::insert_task_unique(self.conn()?, task)
+    }
+
+    // Checks and inserts in a single statement, so that concurrent requests cannot both insert
+    fn insert_task_unique(conn: &Connection, task: &Task) -> Result<TaskId, TaskRepoError> {
+        let mut stmt = conn.prepare_cached(
+            "
+            INSERT INTO tasks (priority, description, completed, project, source_preset_id, due_date, recurrence)
+            SELECT
+                :priority, :description, :completed,
+                -- Projects differing only by case are the same, spelled as first created
+                COALESCE((SELECT project FROM tasks WHERE project = :project COLLATE NOCASE ORDER BY id LIMIT 1), :project),
+                :source_preset_id, :due_date, :recurrence
+            WHERE NOT EXISTS (
+                SELECT 1 FROM tasks
+                WHERE NOT completed AND description = :description
+                AND project = :project COLLATE NOCASE
+            )
+            ",
+        )?;
+
+        let params = named_params! {":priority": task.priority.map(String::from).unwrap_or_default(), ":description": task.description, ":completed": task.completed, ":project": task.project.as_deref().unwrap_or(""), ":source_preset_id": task.source_preset_id, ":due_date": task.due_date, ":recurrence": task.recurrence.as_ref().map(Recurrence::as_str)};
+        match stmt.execute(params)? {
+            0 => Err(TaskRepoError::Duplicate {
+                what: format!("Task \"{}\"", task.description),
+            }),
+            _ => Ok(conn.last_insert_rowid()),
+        }
+    }
+
+    pub fn add_note(&mut self, task_id: TaskId, body: &str) -> Result<NoteId, TaskRepoError> {
+        let body = validate_note(body)?;
+
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO task_notes (task_id, body, created_at) VALUES (:task_id, :body, unixepoch())",
+            named_params! {":task_id": task_id, ":body": body},
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // Oldest notes first
+    pub fn get_notes(&mut self, task_id: TaskId) -> Result<Vec<Note>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT id, task_id, body, created_at FROM task_notes
+            WHERE task_id = ?
+            ORDER BY created_at ASC, id ASC
+            ",
+        )?;
+        let rows = stmt.query_map([task_id], |row| {
+            Ok(Note {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                body: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // Notes of other tasks are left untouched, as if not found
+    pub fn delete_note(&mut self, task_id: TaskId, note_id: NoteId) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let deleted_rows = conn.execute(
+            "DELETE FROM task_notes WHERE id = ? AND task_id = ?",
+            [note_id, task_id],
+        )?;
+
+        match deleted_rows {
+            0 => Err(TaskRepoError::NotFound {
+                what: format!("Note {} of task {}", note_id, task_id),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Moves a task into a project, or out of any project if None
+    pub fn set_task_project(
+        &mut self,
+        task_id: TaskId,
+        project: Option<&str>,
+    ) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            UPDATE tasks SET project = COALESCE(
+                (SELECT project FROM tasks WHERE project = :project COLLATE NOCASE AND id != :id ORDER BY id LIMIT 1),
+                :project
+            )
+            WHERE id = :id
+            ",
+        )?;
+        let updated_rows =
+            stmt.execute(named_params! {":project": project.unwrap_or(""), ":id": task_id})?;
+
+        match updated_rows {
+            0 => Err(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn persist_preset_task(&mut self, preset_task: PresetTask) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        if preset_task.id < 0 {
+            // New task, need to insert
+            let mut stmt = conn.prepare(
+                "
+            INSERT INTO preset_tasks (preset_id, priority, description)
+            VALUES (:preset_id, :priority, :description)
+            ",
+            )?;
+
+            let params = named_params! {":preset_id": preset_task.preset_id, ":priority": String::from(preset_task.priority), ":description": preset_task.description};
+            stmt.execute(params)?;
+            Ok(())
+        } else {
+            Err(TaskRepoError::Error {
+                error:
+                    "Cannot persist a non-new preset task (i.e. preset task update not implemented)"
+                        .into(),
+            })
+        }
+    }
+
+    // Inserts all new preset tasks at once, or none of them. Returns the number of inserted tasks.
+    pub fn persist_preset_tasks(
+        &mut self,
+        preset_tasks: Vec<PresetTask>,
+    ) -> Result<usize, TaskRepoError> {
+        if preset_tasks.iter().any(|preset_task| preset_task.id >= 0) {
+            return Err(TaskRepoError::Error {
+                error:
+                    "Cannot persist a non-new preset task (i.e. preset task update not implemented)"
+                        .into(),
+            });
+        }
+
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "
+                INSERT INTO preset_tasks (preset_id, priority, description)
+                VALUES (:preset_id, :priority, :description)
+                ",
+            )?;
+            for preset_task in &preset_tasks {
+                stmt.execute(named_params! {":preset_id": preset_task.preset_id, ":priority": String::from(preset_task.priority), ":description": preset_task.description})?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(preset_tasks.len())
+    }
+
+    // Moves the task before the previous task of the same priority, if any
+    pub fn move_task_up(&mut self, task_id: TaskId) -> Result<(), TaskRepoError> {
+        self.move_task(task_id, |position| position.saturating_sub(1))
+    }
+
+    // Moves the task after the next task of the same priority, if any
+    pub fn move_task_down(&mut self, task_id: TaskId) -> Result<(), TaskRepoError> {
+        self.move_task(task_id, |position| position + 1)
+    }
+
+    // Moves the task at the given position among the tasks of the same priority, 0 being the
+    // first one. Positions past the end move the task last.
+    pub fn reorder_task(
+        &mut self,
+        task_id: TaskId,
+        new_position: usize,
+    ) -> Result<(), TaskRepoError> {
+        self.move_task(task_id, |_| new_position)
+    }
+
+    // The new position is computed from the current one
+    fn move_task(
+        &mut self,
+        task_id: TaskId,
+        new_position: impl FnOnce(usize) -> usize,
+    ) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let not_found = || TaskRepoError::NotFound {
+            what: format!("Task {}", task_id),
+        };
+
+        // Tasks of the same project sharing the same priority (and completion state) are shown
+        // next to each other
+        let mut sibling_ids: Vec<TaskId> = {
+            let mut stmt = tx.prepare(
+                "
+                SELECT siblings.id FROM tasks
+                JOIN tasks AS siblings
+                    ON siblings.priority = tasks.priority
+                    AND siblings.completed = tasks.completed
+                    AND siblings.project = tasks.project
+                WHERE tasks.id = ?
+                ORDER BY siblings.order_index ASC, siblings.description ASC
+                ",
+            )?;
+            stmt.query_map([task_id], |row| row.get(0))?
+                .collect::<Result<_, _>>()?
+        };
+
+        let position = sibling_ids
+            .iter()
+            .position(|id| *id == task_id)
+            .ok_or_else(not_found)?;
+        let new_position = new_position(position).min(sibling_ids.len() - 1);
+        let id = sibling_ids.remove(position);
+        sibling_ids.insert(new_position, id);
+
+        // Siblings may share the same order index (e.g. 0 by default), so they are renumbered
+        {
+            let mut stmt =
+                tx.prepare("UPDATE tasks SET order_index = :order_index WHERE id = :id")?;
+            for (order_index, id) in (0_i64..).zip(&sibling_ids) {
+                stmt.execute(named_params! {":order_index": order_index, ":id": id})?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    // Name of the preset the task was injected from, if any (and if it still exists)
+    pub fn get_source_preset_name(
+        &mut self,
+        task_id: TaskId,
+    ) -> Result<Option<String>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT presets.name FROM tasks
+            LEFT JOIN presets ON presets.id = tasks.source_preset_id
+            WHERE tasks.id = ?
+            ",
+        )?;
+
+        stmt.query_row([task_id], |row| row.get::<_, Option<String>>(0))
+            .optional()?
+            .ok_or(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            })
+    }
+
+    // Returns the number of tasks flagged as completed, unknown IDs being ignored
+    pub fn complete_tasks(&mut self, task_ids: &[TaskId]) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let updated_rows = conn.execute(
+            &format!(
+                "UPDATE tasks SET completed = TRUE WHERE id IN ({})",
+                Self::placeholders(task_ids.len())
+            ),
+            params_from_iter(task_ids),
+        )?;
+
+        Ok(updated_rows)
+    }
+
+    // Flags the task as completed. If it is recurring, a pending copy of it is created, due at the
+    // next occurrence (counted from today if it had no due date). Returns the ID of the copy.
+    pub fn complete_and_recur(&mut self, task_id: TaskId) -> Result<Option<TaskId>, TaskRepoError> {
+        let task = self.get_task(task_id)?;
+
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("UPDATE tasks SET completed = TRUE WHERE id = ?", [task_id])?;
+
+        let mut recurring_task_id = None;
+        if let Some(recurrence) = task.recurrence.filter(|_| !task.recurrence_paused) {
+            let due_date = recurrence.next_due_date(
+                task.due_date
+                    .unwrap_or_else(|| chrono::Local::now().date_naive()),
+            )?;
+            tx.execute(
+                "
+                INSERT INTO tasks (priority, description, completed, project, due_date, recurrence)
+                SELECT priority, description, FALSE, project, :due_date, recurrence
+                FROM tasks WHERE id = :id
+                ",
+                named_params! {":due_date": due_date, ":id": task_id},
+            )?;
+            recurring_task_id = Some(tx.last_insert_rowid());
+        }
+        tx.commit()?;
+
+        Ok(recurring_task_id)
+    }
+
+    // Changes how the task recurs, or stops it from recurring. Weekly recurrences may be anchored
+    // on a weekday, the task then being due on the first such day from its due date (or today).
+    pub fn set_recurrence(
+        &mut self,
+        task_id: TaskId,
+        recurrence: Option<Recurrence>,
+        weekday: Option<Weekday>,
+    ) -> Result<(), TaskRepoError> {
+        let task = self.get_task(task_id)?;
+
+        let due_date = match (recurrence, weekday) {
+            (_, None) => task.due_date,
+            (Some(Recurrence::Weekly), Some(weekday)) => Some(Recurrence::on_weekday(
+                task.due_date
+                    .unwrap_or_else(|| chrono::Local::now().date_naive()),
+                weekday,
+            )?),
+            (_, Some(_)) => {
+                return Err(TaskRepoError::Invalid {
+                    reason: "Only weekly recurrences can be anchored on a weekday".into(),
+                });
+            }
+        };
+
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE tasks SET recurrence = :recurrence, due_date = :due_date WHERE id = :id",
+            named_params! {":recurrence": recurrence.as_ref().map(Recurrence::as_str), ":due_date": due_date, ":id": task_id},
+        )?;
+
+        Ok(())
+    }
+
+    // Paused recurring tasks are simply completed, until their recurrence is resumed
+    pub fn set_recurrence_paused(
+        &mut self,
+        task_id: TaskId,
+        paused: bool,
+    ) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let updated_rows = conn.execute(
+            "UPDATE tasks SET recurrence_paused = ? WHERE id = ?",
+            (paused, task_id),
+        )?;
+
+        match updated_rows {
+            0 => Err(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Hidden tasks leave the task list as they are, unlike the ones moved to the archive by
+    // `archive_completed`
+    fn set_hidden(&mut self, task_id: TaskId, hidden: bool) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let updated_rows = conn.execute(
+            "UPDATE tasks SET hidden = ? WHERE id = ?",
+            (hidden, task_id),
+        )?;
+
+        match updated_rows {
+            0 => Err(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn hide_task(&mut self, task_id: TaskId) -> Result<(), TaskRepoError> {
+        self.set_hidden(task_id, true)
+    }
+
+    pub fn unhide_task(&mut self, task_id: TaskId) -> Result<(), TaskRepoError> {
+        self.set_hidden(task_id, false)
+    }
+
+    // Priorities are checked as on task creation. Returns the updated task.
+    pub fn set_priority(
+        &mut self,
+        task_id: TaskId,
+        priority_scheme: &PriorityScheme,
+        priority: char,
+    ) -> Result<Task, TaskRepoError> {
+        let priority = priority_scheme.assignable(priority)?;
+
+        let conn = self.conn()?;
+        let updated_rows = conn.execute(
+            "UPDATE tasks SET priority = ? WHERE id = ?",
+            (String::from(priority), task_id),
+        )?;
+        if updated_rows == 0 {
+            return Err(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            });
+        }
+
+        self.get_task(task_id)
+    }
+
+    // Same as `Task::increase_priority` (negative step) or `Task::lower_priority` (positive step),
+    // but in a single statement so that concurrent shifts are not lost. Returns the updated task.
+    pub fn shift_priority(
+        &mut self,
+        task_id: TaskId,
+        priority_scheme: &PriorityScheme,
+        step: i32,
+    ) -> Result<Task, TaskRepoError> {
+        // Unprioritized tasks start from the bottom when increased, from the top when lowered
+        let unprioritized_start = match step < 0 {
+            true => priority_scheme.lowest(),
+            false => priority_scheme.max_assignable(),
+        };
+
+        let conn = self.conn()?;
+        let updated_rows = conn.execute(
+            "
+            UPDATE tasks SET priority = CASE
+                WHEN priority = '' THEN :unprioritized_start
+                ELSE char(MIN(
+                    MAX(unicode(priority) + :step, MIN(unicode(priority), :max_assignable)),
+                    :lowest
+                ))
+            END
+            WHERE id = :id
+            ",
+            named_params! {
+                ":unprioritized_start": String::from(unprioritized_start),
+                ":step": step,
+                ":max_assignable": priority_scheme.max_assignable() as u32,
+                ":lowest": priority_scheme.lowest() as u32,
+                ":id": task_id,
+            },
+        )?;
+        if updated_rows == 0 {
+            return Err(TaskRepoError::NotFound {
+                what: format!("Task {}", task_id),
+            });
+        }
+
+        self.get_task(task_id)
+    }
+
+    // Returns the number of deleted tasks, unknown IDs being ignored
+    pub fn delete_tasks(&mut self, task_ids: &[TaskId]) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let deleted_rows = conn.execute(
+            &format!(
+                "DELETE FROM tasks WHERE id IN ({})",
+                Self::placeholders(task_ids.len())
+            ),
+            params_from_iter(task_ids),
+        )?;
+
+        Ok(deleted_rows)
+    }
+
+    // Returns the number of newly completed tasks
+    pub fn complete_all_in_project(&mut self, project: &str) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let updated_rows = conn.execute(
+            "UPDATE tasks SET completed = TRUE WHERE project = ? AND NOT completed",
+            [project],
+        )?;
+
+        Ok(updated_rows)
+    }
+
+    // Opposite of `complete_all_in_project`, for all projects if no filter is given. An empty
+    // project filter selects the tasks outside of any project. Returns the number of reopened
+    // tasks.
+    pub fn reopen_all(&mut self, project_filter: Option<&str>) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let updated_rows = conn.execute(
+            "
+            UPDATE tasks SET completed = FALSE
+            WHERE completed AND (:project IS NULL OR project = :project)
+            ",
+            named_params! {":project": project_filter},
+        )?;
+
+        Ok(updated_rows)
+    }
+
+    // Pending tasks due before `as_of` become due on it. Returns the number of moved tasks.
+    pub fn reset_overdue(&mut self, as_of: NaiveDate) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let updated_rows = tx.execute(
+            "UPDATE tasks SET due_date = :as_of WHERE NOT completed AND due_date < :as_of",
+            named_params! {":as_of": as_of},
+        )?;
+        tx.commit()?;
+
+        Ok(updated_rows)
+    }
+
+    // Returns the number of deleted tasks, whether pending or completed
+    pub fn delete_all_in_project(&mut self, project: &str) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let deleted_rows = conn.execute("DELETE FROM tasks WHERE project = ?", [project])?;
+
+        Ok(deleted_rows)
+    }
+
+    // Moves all tasks of the project out of any project, which makes the project vanish.
+    // Returns the number of moved tasks, whether pending or completed.
+    pub fn clear_project(&mut self, project: &str) -> Result<usize, TaskRepoError> {
+        self.merge_projects(project, "")
+    }
+
+    // Placeholders for as many parameters in a "IN (...)" clause
+    fn placeholders(count: usize) -> String {
+        vec!["?"; count].join(", ")
+    }
+
+    // Moves all completed tasks to the archive, along with their notes, subtasks and tags. Their
+    // dependencies are dropped: being completed, they no longer block any task.
+    // Returns the number of archived tasks.
+    pub fn archive_completed(&mut self) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        let task_ids: Vec<TaskId> = tx
+            .prepare("SELECT id FROM tasks WHERE completed ORDER BY id ASC")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        for task_id in &task_ids {
+            tx.execute(
+                "
+                INSERT INTO archived_tasks (
+                    priority, description, completed, project, source_preset_id, due_date,
+                    created_at, completed_at, recurrence, recurrence_paused, hidden, archived_at
+                )
+                SELECT
+                    priority, description, completed, project, source_preset_id, due_date,
+                    created_at, completed_at, recurrence, recurrence_paused, hidden, unixepoch()
+                FROM tasks WHERE id = ?
+                ",
+                [task_id],
+            )?;
+            Self::copy_task_details(&tx, true, *task_id, tx.last_insert_rowid())?;
+        }
+        tx.execute("DELETE FROM tasks WHERE completed", ())?;
+        tx.commit()?;
+
+        Ok(task_ids.len())
+    }
+
+    // Copies the notes, subtasks and tags of a task to the archive, or back from it
+    fn copy_task_details(
+        conn: &Connection,
+        to_archive: bool,
+        from_task_id: TaskId,
+        to_task_id: TaskId,
+    ) -> Result<(), TaskRepoError> {
+        for (live_table, archived_table, columns) in TASK_DETAIL_TABLES {
+            let (from_table, to_table) = match to_archive {
+                true => (live_table, archived_table),
+                false => (archived_table, live_table),
+            };
+            conn.execute(
+                &format!(
+                    "
+                    INSERT INTO {to_table} (task_id, {columns})
+                    SELECT ?, {columns} FROM {from_table} WHERE task_id = ? ORDER BY rowid
+                    "
+                ),
+                [to_task_id, from_task_id],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Most recently archived first. IDs are the ones of the archive, not of the task list.
+    pub fn get_archived_tasks(&mut self) -> Result<Vec<Task>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TASK_COLUMNS} FROM archived_tasks ORDER BY archived_at DESC, id DESC"
+        ))?;
+
+        let tasks = stmt
+            .query_and_then([], Self::task_from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(tasks)
+    }
+
+    // Moves the archived task back to the task list along with its details, under a new ID which
+    // is returned
+    pub fn restore_task(&mut self, archived_task_id: TaskId) -> Result<TaskId, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        let restored_rows = tx.execute(
+            "
+            INSERT INTO tasks (
+                priority, description, completed, project, source_preset_id, due_date,
+                created_at, completed_at, recurrence, recurrence_paused, hidden
+            )
+            SELECT
+                priority, description, completed, project, source_preset_id, due_date,
+                created_at, completed_at, recurrence, recurrence_paused, hidden
+            FROM archived_tasks WHERE id = ?
+            ",
+            [archived_task_id],
+        )?;
+        if restored_rows == 0 {
+            return Err(TaskRepoError::NotFound {
+                what: format!("Archived task {}", archived_task_id),
+            });
+        }
+        let task_id = tx.last_insert_rowid();
+        Self::copy_task_details(&tx, false, archived_task_id, task_id)?;
+        tx.execute(
+            "DELETE FROM archived_tasks WHERE id = ?",
+            [archived_task_id],
+        )?;
+        tx.commit()?;
+
+        Ok(task_id)
+    }
+
+    // Deletes completed tasks, except for the `keep_min` most recently completed ones if set
+    // Returns every deleted row, with all its columns, so that they may be given back to
+    // `undo_cleanup`
+    pub fn cleanup(&mut self, keep_min: Option<usize>) -> Result<CleanedUpTasks, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let keep_min = keep_min.unwrap_or(0) as i64;
+
+        let mut cleaned_up_tasks = CleanedUpTasks { tables: vec![] };
+        for (table, deleted_with_task, restorable) in CLEANUP_TABLES {
+            let condition = deleted_with_task.replace("{cleaned_up}", CLEANED_UP_TASK_IDS);
+            let mut stmt = tx.prepare(&format!("SELECT * FROM {table} WHERE {condition}"))?;
+            let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+            let rows = stmt
+                .query_map(named_params! {":keep_min": keep_min}, |row| {
+                    (0..columns.len())
+                        .map(|index| row.get::<_, Value>(index))
+                        .collect()
+                })?
+                .collect::<Result<_, _>>()?;
+            cleaned_up_tasks.tables.push(CleanedUpRows {
+                table,
+                restorable,
+                columns,
+                rows,
+            });
+        }
+
+        // Notes, subtasks, tags and dependencies go along thanks to their foreign keys
+        tx.execute(
+            &format!("DELETE FROM tasks WHERE id IN ({CLEANED_UP_TASK_IDS})"),
+            named_params! {":keep_min": keep_min},
+        )?;
+        tx.commit()?;
+
+        Ok(cleaned_up_tasks)
+    }
+
+    // Puts back the rows deleted by a cleanup under their original IDs, all at once or none of
+    // them, which fails if one of these IDs was taken since. Dependencies on tasks deleted since
+    // are dropped. Returns the number of restored tasks.
+    pub fn undo_cleanup(
+        &mut self,
+        cleaned_up_tasks: &CleanedUpTasks,
+    ) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        for cleaned_up_rows in &cleaned_up_tasks.tables {
+            let columns = cleaned_up_rows.columns.join(", ");
+            let placeholders: Vec<String> = cleaned_up_rows
+                .columns
+                .iter()
+                .map(|column| format!(":{column}"))
+                .collect();
+            let mut stmt = tx.prepare(&format!(
+                "INSERT INTO {} ({columns}) SELECT {} WHERE {}",
+                cleaned_up_rows.table,
+                placeholders.join(", "),
+                cleaned_up_rows.restorable
+            ))?;
+
+            for row in &cleaned_up_rows.rows {
+                let params: Vec<(&str, &dyn ToSql)> = placeholders
+                    .iter()
+                    .zip(row)
+                    .map(|(placeholder, value)| (placeholder.as_str(), value as &dyn ToSql))
+                    .collect();
+                stmt.execute(params.as_slice())?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(cleaned_up_tasks.task_count())
+    }
+
+    pub fn get_stats(&mut self) -> Result<Stats, TaskRepoError> {
+        let conn = self.conn()?;
+
+        let (total, completed): (i64, i64) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(completed), 0) FROM tasks",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        // Unprioritized tasks and tasks outside of any project come last, as in the task list
+        let by_priority = conn
+            .prepare(
+                "
+                SELECT priority, COUNT(*) FROM tasks
+                GROUP BY priority
+                ORDER BY priority = '' ASC, priority ASC
+                ",
+            )?
+            .query_map([], |row| {
+                Ok(PriorityCount {
+                    priority: row.get::<usize, String>(0)?.chars().nth(0),
+                    count: row.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        let by_project = conn
+            .prepare(
+                "
+                SELECT project, COUNT(*) FROM tasks
+                GROUP BY project
+                ORDER BY project = '' ASC, project ASC
+                ",
+            )?
+            .query_map([], |row| {
+                let project: String = row.get(0)?;
+                Ok(ProjectCount {
+                    project: (!project.is_empty()).then_some(project),
+                    count: row.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(Stats {
+            total,
+            completed,
+            pending: total - completed,
+            by_priority,
+            by_project,
+        })
+    }
+
+    // Deletes the tasks completed during the month (in UTC), only keeping their number by project.
+    // Tasks completed at an unknown time are left alone. Summarizing the same month again adds
+    // the tasks completed since to the existing counts.
+    pub fn summarize_month(
+        &mut self,
+        year: i32,
+        month: u32,
+    ) -> Result<MonthlySummary, TaskRepoError> {
+        let invalid_month = || TaskRepoError::Invalid {
+            reason: format!("Invalid month {year}-{month}"),
+        };
+        let month_start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(invalid_month)?;
+        let next_month_start = month_start
+            .checked_add_months(Months::new(1))
+            .ok_or_else(invalid_month)?;
+        let timestamp = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc().timestamp();
+        let params = named_params! {
+            ":year": year,
+            ":month": month,
+            ":from": timestamp(month_start),
+            ":to": timestamp(next_month_start),
+        };
+
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "
+            INSERT INTO monthly_summaries (year, month, project, completed)
+            SELECT :year, :month, project, COUNT(*) FROM tasks
+            WHERE completed AND completed_at >= :from AND completed_at < :to
+            GROUP BY project
+            ON CONFLICT (year, month, project) DO UPDATE SET completed = completed + excluded.completed
+            ",
+            params,
+        )?;
+        tx.execute(
+            "DELETE FROM tasks WHERE completed AND completed_at >= :from AND completed_at < :to",
+            &params[2..],
+        )?;
+        tx.commit()?;
+
+        self.get_monthly_summary(year, month)
+    }
+
+    // Tasks outside of any project come last
+    pub fn get_monthly_summary(
+        &mut self,
+        year: i32,
+        month: u32,
+    ) -> Result<MonthlySummary, TaskRepoError> {
+        let conn = self.conn()?;
+        let by_project = conn
+            .prepare(
+                "
+                SELECT project, completed FROM monthly_summaries
+                WHERE year = :year AND month = :month
+                ORDER BY project = '' ASC, project ASC
+                ",
+            )?
+            .query_map(named_params! {":year": year, ":month": month}, |row| {
+                let project: String = row.get(0)?;
+                Ok(ProjectCount {
+                    project: (!project.is_empty()).then_some(project),
+                    count: row.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(MonthlySummary {
+            year,
+            month,
+            by_project,
+        })
+    }
+
+    // Mean time in seconds between creation and completion of completed tasks, by priority.
+    // Unprioritized tasks and tasks with unknown timestamps are left out.
+    pub fn avg_completion_time_by_priority(&mut self) -> Result<Vec<(char, f64)>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT priority, AVG(completed_at - created_at) FROM tasks
+            WHERE completed AND priority != ''
+                AND created_at IS NOT NULL AND completed_at IS NOT NULL
+            GROUP BY priority
+            ORDER BY priority ASC
+            ",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<usize, String>(0)?, row.get::<usize, f64>(1)?))
+        })?;
+
+        let mut averages = vec![];
+        for row in rows {
+            let (priority, average) = row?;
+            let priority = priority.chars().nth(0).ok_or(TaskRepoError::Error {
+                error: "Empty priority found in storage".into(),
+            })?;
+            averages.push((priority, average));
+        }
+
+        Ok(averages)
+    }
+
+    // Tasks changed between the two Unix timestamps, both included
+    pub fn get_changes(&mut self, from: i64, to: i64) -> Result<TaskChanges, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT DISTINCT kind, task_id FROM task_events
+            WHERE occurred_at BETWEEN :from AND :to
+            ORDER BY task_id ASC
+            ",
+        )?;
+        let rows = stmt.query_map(named_params! {":from": from, ":to": to}, |row| {
+            Ok((row.get::<usize, String>(0)?, row.get::<usize, TaskId>(1)?))
+        })?;
+
+        let mut changes = TaskChanges::default();
+        for row in rows {
+            let (kind, task_id) = row?;
+            match kind.as_str() {
+                "created" => changes.created.push(task_id),
+                "completed" => changes.completed.push(task_id),
+                "deleted" => changes.deleted.push(task_id),
+                "edited" => changes.edited.push(task_id),
+                _ => {
+                    return Err(TaskRepoError::Error {
+                        error: format!("Unknown task event kind {kind}"),
+                    });
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    pub fn export_todotxt(&mut self) -> Result<String, TaskRepoError> {
+        let lines: Vec<String> = self
+            .get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)?
+            .iter()
+            .map(Task::to_todotxt)
+            .collect();
+
+        Ok(lines.join("\n"))
+    }
+
+    // Follows RFC 4180: one header line, then one record per task, with CRLF line breaks.
+    pub fn export_csv(&mut self) -> Result<String, TaskRepoError> {
+        let mut csv = String::from("id,priority,description,completed,project\r\n");
+        for task in self.get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)? {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\r\n",
+                task.id,
+                task.priority.map(String::from).unwrap_or_default(),
+                Self::csv_field(&task.description),
+                task.completed,
+                Self::csv_field(task.project.as_deref().unwrap_or("")),
+            ));
+        }
+
+        Ok(csv)
+    }
+
+    // Fields containing commas, quotes or line breaks must be quoted, with quotes doubled
+    fn csv_field(field: &str) -> String {
+        if field.contains([',', '"', '\r', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.into()
+        }
+    }
+
+    // Follows RFC 5545: one VTODO per task with a due date, with CRLF line breaks. The high,
+    // medium and low priority buckets of the scheme give iCalendar priorities 1, 5 and 9.
+    pub fn export_ical(
+        &mut self,
+        priority_scheme: &PriorityScheme,
+    ) -> Result<String, TaskRepoError> {
+        let dtstamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".into(),
+            "PRODID:-//tasker//tasker//EN".into(),
+        ];
+        for task in self.get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)? {
+            let Some(due_date) = task.due_date else {
+                continue;
+            };
+            lines.push("BEGIN:VTODO".into());
+            lines.push(format!("UID:task-{}@tasker", task.id));
+            lines.push(format!("DTSTAMP:{dtstamp}"));
+            lines.push(format!("SUMMARY:{}", Self::ical_text(&task.description)));
+            match task
+                .priority
+                .and_then(|priority| priority_scheme.bucket(priority))
+            {
+                Some(PriorityBucket::High) => lines.push("PRIORITY:1".into()),
+                Some(PriorityBucket::Medium) => lines.push("PRIORITY:5".into()),
+                Some(PriorityBucket::Low) => lines.push("PRIORITY:9".into()),
+                None => {}
+            }
+            lines.push(
+                if task.completed {
+                    "STATUS:COMPLETED"
+                } else {
+                    "STATUS:NEEDS-ACTION"
+                }
+                .into(),
+            );
+            lines.push(format!("DUE;VALUE=DATE:{}", due_date.format("%Y%m%d")));
+            lines.push("END:VTODO".into());
+        }
+        lines.push("END:VCALENDAR".into());
+
+        Ok(lines
+            .iter()
+            .map(|line| Self::ical_fold(line) + "\r\n")
+            .collect())
+    }
+
+    // Backslashes, semicolons, commas and line breaks must be escaped in text values
+    fn ical_text(text: &str) -> String {
+        text.replace('\\', "\\\\")
+            .replace(';', "\\;")
+            .replace(',', "\\,")
+            .replace("\r\n", "\\n")
+            .replace('\n', "\\n")
+    }
+
+    // Lines longer than 75 bytes must be split, continuation lines starting with a space
+    fn ical_fold(line: &str) -> String {
+        let mut folded = String::new();
+        let mut line_length = 0;
+        for c in line.chars() {
+            if line_length + c.len_utf8() > 75 {
+                folded.push_str("\r\n ");
+                line_length = 1;
+            }
+            folded.push(c);
+            line_length += c.len_utf8();
+        }
+        folded
+    }
+
+    pub fn export_all(&mut self) -> Result<ExportBundle, TaskRepoError> {
+        let tasks = self
+            .get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)?
+            .into_iter()
+            .map(|task| {
+                let completed_at = self.conn()?.query_row(
+                    "SELECT completed_at FROM tasks WHERE id = ?",
+                    [task.id],
+                    |row| row.get(0),
+                )?;
+                Ok(ExportedTask {
+                    completed_at,
+                    notes: self.get_notes(task.id)?,
+                    depends_on: self
+                        .get_blockers(task.id)?
+                        .iter()
+                        .map(|blocker| blocker.id)
+                        .collect(),
+                    task,
+                })
+            })
+            .collect::<Result<_, TaskRepoError>>()?;
+        let presets = self
+            .get_all_preset_names()?
+            .iter()
+            .map(|preset_name| self.get_preset(preset_name))
+            .collect::<Result<_, _>>()?;
+        let project_colors = self.get_project_colors()?;
+
+        Ok(ExportBundle {
+            tasks,
+            presets,
+            project_colors,
+        })
+    }
+
+    // Adds everything at once, or nothing. Tasks and presets get new IDs, which links between them
+    // follow; links to tasks or presets missing from the bundle are dropped. Presets whose name is
+    // taken are imported under a numbered name, e.g. "Weekly (2)", and projects which already have
+    // a color keep it.
+    pub fn import_all(&mut self, bundle: ExportBundle) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        let mut new_preset_ids = HashMap::new();
+        for preset in &bundle.presets {
+            let mut name = preset.name.clone();
+            let mut copy_number = 1;
+            while tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM presets WHERE name = ?)",
+                [&name],
+                |row| row.get(0),
+            )? {
+                copy_number += 1;
+                name = format!("{} ({})", preset.name, copy_number);
+            }
+
+            tx.execute(
+                "INSERT INTO presets (name) VALUES (:name)",
+                named_params! {":name": name},
+            )?;
+            let preset_id = tx.last_insert_rowid();
+            new_preset_ids.insert(preset.id, preset_id);
+
+            let mut stmt = tx.prepare_cached(
+                "
+                INSERT INTO preset_tasks (preset_id, priority, description)
+                VALUES (:preset_id, :priority, :description)
+                ",
+            )?;
+            for preset_task in &preset.tasks {
+                stmt.execute(named_params! {":preset_id": preset_id, ":priority": String::from(preset_task.priority), ":description": preset_task.description})?;
+            }
+        }
+
+        let mut new_task_ids = HashMap::new();
+        for ExportedTask {
+            task,
+            completed_at,
+            notes,
+            ..
+        } in &bundle.tasks
+        {
+            let source_preset_id = task
+                .source_preset_id
+                .and_then(|preset_id| new_preset_ids.get(&preset_id).copied());
+            tx.prepare_cached(
+                "
+                INSERT INTO tasks (
+                    priority, description, completed, project, source_preset_id, due_date,
+                    recurrence, recurrence_paused, hidden, created_at, completed_at
+                )
+                VALUES (
+                    :priority, :description, :completed,
+                    -- Projects differing only by case are the same, spelled as first created
+                    COALESCE((SELECT project FROM tasks WHERE project = :project COLLATE NOCASE ORDER BY id LIMIT 1), :project),
+                    :source_preset_id, :due_date, :recurrence, :recurrence_paused, :hidden,
+                    NULLIF(:created_at, 0), CASE WHEN :completed THEN :completed_at END
+                )
+                ",
+            )?
+            .execute(named_params! {":priority": task.priority.map(String::from).unwrap_or_default(), ":description": task.description, ":completed": task.completed, ":project": task.project.as_deref().unwrap_or(""), ":source_preset_id": source_preset_id, ":due_date": task.due_date, ":recurrence": task.recurrence.as_ref().map(Recurrence::as_str), ":recurrence_paused": task.recurrence_paused, ":hidden": task.hidden, ":created_at": task.created_at, ":completed_at": completed_at})?;
+            let task_id = tx.last_insert_rowid();
+            new_task_ids.insert(task.id, task_id);
+
+            for subtask in &task.subtasks {
+                tx.execute(
+                    "INSERT INTO subtasks (task_id, description, completed) VALUES (?, ?, ?)",
+                    (
+                        task_id,
+                        validate_description(&subtask.description)?,
+                        subtask.completed,
+                    ),
+                )?;
+            }
+            for tag in &task.tags {
+                let tag = validate_tag(tag)?;
+                tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?)", [tag])?;
+                tx.execute(
+                    "
+                    INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+                    SELECT :task_id, id FROM tags WHERE name = :tag
+                    ",
+                    named_params! {":task_id": task_id, ":tag": tag},
+                )?;
+            }
+            for note in notes {
+                tx.execute(
+                    "
+                    INSERT INTO task_notes (task_id, body, created_at)
+                    VALUES (?, ?, COALESCE(NULLIF(?, 0), unixepoch()))
+                    ",
+                    (task_id, validate_note(&note.body)?, note.created_at),
+                )?;
+            }
+        }
+
+        for exported_task in &bundle.tasks {
+            for depends_on_id in &exported_task.depends_on {
+                if let (Some(task_id), Some(depends_on_id)) = (
+                    new_task_ids.get(&exported_task.task.id),
+                    new_task_ids.get(depends_on_id),
+                ) {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id) VALUES (?, ?)",
+                        [task_id, depends_on_id],
+                    )?;
+                }
+            }
+        }
+
+        for (project, color) in &bundle.project_colors {
+            tx.execute(
+                "
+                INSERT OR IGNORE INTO projects (name, color)
+                VALUES (
+                    COALESCE((SELECT project FROM tasks WHERE project = :name COLLATE NOCASE ORDER BY id LIMIT 1), :name),
+                    :color
+                )
+                ",
+                named_params! {":name": project, ":color": validate_color(color)?},
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    // Returns the number of imported tasks. Blank lines are ignored.
+    pub fn import_todotxt(
+        &mut self,
+        priority_scheme: &PriorityScheme,
+        text: &str,
+    ) -> Result<usize, TaskRepoError> {
+        let tasks: Result<Vec<Task>, TaskError> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Task::from_todotxt(priority_scheme, line))
+            .collect();
+
+        self.import_tasks(&tasks?)
+    }
+
+    // Either all tasks are imported, or none of them
+    pub fn import_tasks(&mut self, tasks: &[Task]) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        for task in tasks {
+            Self::insert_task(&tx, task)?;
+        }
+        tx.commit()?;
+
+        Ok(tasks.len())
+    }
+
+    // Normalized matching collapses projects differing only by case or accents, keeping the
+    // spelling of the earliest task
+    pub fn get_all_projects(
+        &mut self,
+        project_match: ProjectMatch,
+    ) -> Result<Vec<String>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(match project_match {
+            ProjectMatch::Exact => {
+                "
+                SELECT DISTINCT project FROM tasks
+                WHERE project != ''
+                ORDER BY project ASC
+                "
+            }
+            ProjectMatch::Normalized => {
+                "
+                SELECT project FROM (
+                    SELECT project, MIN(id) FROM tasks
+                    WHERE project != ''
+                    GROUP BY project COLLATE PROJECT_NAME
+                )
+                ORDER BY project ASC
+                "
+            }
+        })?;
+
+        Ok(stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<_, _>>()?)
+    }
+
+    // (project, number of tasks) for every project, by name. Projects only existing through their
+    // tasks, none of them can be empty.
+    pub fn get_project_task_counts(&mut self) -> Result<Vec<(String, usize)>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT project, COUNT(*) FROM tasks
+            WHERE project != ''
+            GROUP BY project
+            ORDER BY project ASC
+            ",
+        )?;
+
+        Ok(stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<Result<_, _>>()?)
+    }
+
+    // (project, pending tasks, completed tasks) for every project, by name
+    pub fn get_project_stats(&mut self) -> Result<Vec<(String, usize, usize)>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT project, COUNT(*) - SUM(completed), SUM(completed) FROM tasks
+            WHERE project != ''
+            GROUP BY project
+            ORDER BY project ASC
+            ",
+        )?;
+
+        Ok(stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get::<_, i64>(1)? as usize,
+                    row.get::<_, i64>(2)? as usize,
+                ))
+            })?
+            .collect::<Result<_, _>>()?)
+    }
+
+    // (project, pending tasks, completed tasks) for every project by name, then for tasks outside
+    // of any project (if any). Hidden tasks are not accounted for.
+    pub fn board_counts(&mut self) -> Result<Vec<(Option<String>, u32, u32)>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT NULLIF(project, ''), COUNT(*) - SUM(completed), SUM(completed) FROM tasks
+            WHERE NOT hidden
+            GROUP BY project
+            ORDER BY project = '' ASC, project ASC
+            ",
+        )?;
+
+        Ok(stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?)
+    }
+
+    // Share of completed tasks of each project, from 0 to 1. Projects existing only through their
+    // tasks, none of them can be empty.
+    pub fn project_completion_ratios(&mut self) -> Result<Vec<(String, f64)>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT project, CAST(SUM(completed) AS REAL) / COUNT(*) FROM tasks
+            WHERE project != ''
+            GROUP BY project
+            ORDER BY project ASC
+            ",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // Spelling of the project among tasks outside of `excluded_project`, the given one if none
+    // such task pertains to it. Projects differing only by case are the same.
+    fn existing_project_spelling(
+        conn: &Connection,
+        project: &str,
+        excluded_project: &str,
+    ) -> Result<String, TaskRepoError> {
+        let existing_project = conn
+            .query_row(
+                "
+                SELECT project FROM tasks
+                WHERE project = :project COLLATE NOCASE AND project != :excluded_project
+                ORDER BY id LIMIT 1
+                ",
+                named_params! {":project": project, ":excluded_project": excluded_project},
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(existing_project.unwrap_or_else(|| project.into()))
+    }
+
+    // The new name is trimmed, and spelled like the project it may match whatever the case
+    pub fn rename_project(
+        &mut self,
+        current_project_name: &str,
+        new_project_name: &str,
+    ) -> Result<(), TaskRepoError> {
+        let new_project_name =
+            normalize_project(Some(new_project_name)).ok_or(TaskRepoError::Invalid {
+                reason: "Projects cannot be renamed to a blank name".into(),
+            })?;
+
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let new_project_name =
+            Self::existing_project_spelling(&tx, new_project_name, current_project_name)?;
+        tx.execute(
+            "
+            UPDATE tasks
+            SET project = :new_project_name
+            WHERE project = :current_project_name
+            ",
+            named_params! {":current_project_name": current_project_name, ":new_project_name": new_project_name},
+        )?;
+
+        Self::move_project_color(&tx, current_project_name, &new_project_name)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    // Colors are expected as in HTML color inputs, i.e. "#rrggbb"
+    pub fn set_project_color(&mut self, name: &str, color: &str) -> Result<(), TaskRepoError> {
+        let color = validate_color(color)?;
+
+        let conn = self.conn()?;
+        conn.execute(
+            "
+            INSERT INTO projects (name, color) VALUES (:name, :color)
+            ON CONFLICT(name) DO UPDATE SET color = excluded.color
+            ",
+            named_params! {":name": name, ":color": color},
+        )?;
+
+        Ok(())
+    }
+
+    // Projects without any color are simply missing
+    pub fn get_project_colors(&mut self) -> Result<HashMap<String, String>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT name, color FROM projects")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // Unlike renaming, the target project may already have tasks. Returns the number of moved tasks.
+    // The target is normalized as when renaming, except that a blank one means no project at all.
+    pub fn merge_projects(&mut self, from: &str, into: &str) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let into = match normalize_project(Some(into)) {
+            Some(into) => Self::existing_project_spelling(&tx, into, from)?,
+            None => String::new(),
+        };
+        let moved_rows = tx.execute(
+            "UPDATE tasks SET project = :into WHERE project = :from",
+            named_params! {":from": from, ":into": into},
+        )?;
+        Self::move_project_color(&tx, from, &into)?;
+        tx.commit()?;
+
+        Ok(moved_rows)
+    }
+
+    // The color follows the project, unless the project it goes to already has one. Tasks outside
+    // of any project have no color.
+    fn move_project_color(conn: &Connection, from: &str, into: &str) -> Result<(), TaskRepoError> {
+        if !into.is_empty() {
+            conn.execute(
+                "UPDATE OR IGNORE projects SET name = :into WHERE name = :from",
+                named_params! {":from": from, ":into": into},
+            )?;
+        }
+        conn.execute(
+            "DELETE FROM projects WHERE name = :from AND name != :into",
+            named_params! {":from": from, ":into": into},
+        )?;
+
+        Ok(())
+    }
+
+    // Moves the tasks of the project to another one (possibly no project at all) if set, deletes
+    // them otherwise. Returns the number of moved or deleted tasks.
+    pub fn delete_project(
+        &mut self,
+        project: &str,
+        reassign_to: Option<&str>,
+    ) -> Result<usize, TaskRepoError> {
+        // Tasks outside of any project are stored with an empty project, which is not a project
+        if project.is_empty() {
+            return Err(TaskRepoError::Invalid {
+                reason: "Tasks outside of any project cannot be deleted as a project".into(),
+            });
+        }
+
+        match reassign_to {
+            Some(reassign_to) => self.merge_projects(project, reassign_to),
+            None => self.delete_all_in_project(project),
+        }
+    }
+
+    pub fn add_preset(&mut self, new_preset_name: &str) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            INSERT INTO presets
+            (name)
+            VALUES (:new_preset_name)
+            ",
+        )?;
+        stmt.execute(named_params! {":new_preset_name": new_preset_name})?;
+
+        Ok(())
+    }
+
+    pub fn rename_preset(&mut self, old_name: &str, new_name: &str) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let updated_rows = conn
+            .execute(
+                "UPDATE presets SET name = :new_name WHERE name = :old_name",
+                named_params! {":old_name": old_name, ":new_name": new_name},
+            )
+            .map_err(|error| match error.sqlite_error_code() {
+                Some(ErrorCode::ConstraintViolation) => TaskRepoError::Error {
+                    error: format!("Preset {} already exists", new_name),
+                },
+                _ => error.into(),
+            })?;
+
+        match updated_rows {
+            0 => Err(TaskRepoError::Error {
+                error: format!("Preset {} not found in storage", old_name),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_all_preset_names(&mut self) -> Result<Vec<String>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT DISTINCT name FROM presets
+            ORDER BY name ASC
+            ",
+        )?;
+
+        Ok(stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<_, _>>()?)
+    }
+
+    // Saves the filters under the given name, replacing any view already saved under it
+    pub fn save_view(
+        &mut self,
+        name: &str,
+        filters: &TaskListFilters,
+    ) -> Result<(), TaskRepoError> {
+        let filters_json = serde_json::to_string(filters)?;
+        let conn = self.conn()?;
+        conn.execute(
+            "
+            INSERT INTO saved_views (name, filters_json) VALUES (:name, :filters_json)
+            ON CONFLICT(name) DO UPDATE SET filters_json = excluded.filters_json
+            ",
+            named_params! {":name": name, ":filters_json": filters_json},
+        )?;
+
+        Ok(())
+    }
+
+    pub fn get_all_view_names(&mut self) -> Result<Vec<String>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT name FROM saved_views ORDER BY name ASC")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn get_view(&mut self, name: &str) -> Result<TaskListFilters, TaskRepoError> {
+        let conn = self.conn()?;
+        let filters_json: String = conn
+            .query_row(
+                "SELECT filters_json FROM saved_views WHERE name = ?",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(TaskRepoError::NotFound {
+                what: format!("View {}", name),
+            })?;
+
+        Ok(serde_json::from_str(&filters_json)?)
+    }
+
+    pub fn delete_view(&mut self, name: &str) -> Result<(), TaskRepoError> {
+        let conn = self.conn()?;
+        let deleted_rows = conn.execute("DELETE FROM saved_views WHERE name = ?", [name])?;
+
+        match deleted_rows {
+            0 => Err(TaskRepoError::NotFound {
+                what: format!("View {}", name),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_preset_id_from_preset_name(
+        &mut self,
         preset_name: &str,
     ) -> Result<PresetId, TaskRepoError> {
-        let conn = self.connection_factory.open()?;
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare("SELECT id FROM presets WHERE name = :preset_name")?;
+        let mut rows = stmt.query(named_params! {":preset_name" : preset_name})?;
+        let row = rows.next()?.ok_or(TaskRepoError::NotFound {
+            what: format!("Preset {}", preset_name),
+        })?;
+        Ok(row.get(0)?)
+    }
+
+    // Statements creating the tables as currently stored, e.g. to check that migrations were applied
+    pub fn get_schema(&mut self) -> Result<String, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND sql IS NOT NULL ORDER BY name ASC",
+        )?;
+        let statements: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(statements.join(";\n\n"))
+    }
+
+    // Preset tasks whose preset no longer exists, e.g. if deleted while foreign keys were not
+    // enforced
+    pub fn find_orphaned_preset_tasks(&mut self) -> Result<Vec<PresetTask>, TaskRepoError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT preset_tasks.id, preset_tasks.preset_id, preset_tasks.priority, preset_tasks.description
+            FROM preset_tasks
+            LEFT JOIN presets ON presets.id = preset_tasks.preset_id
+            WHERE presets.id IS NULL
+            ",
+        )?;
+
+        let rows = stmt.query_and_then([], Self::preset_task_from_row)?;
+        rows.into_iter().collect()
+    }
+
+    // Returns the number of purged preset tasks
+    pub fn purge_orphaned_preset_tasks(&mut self) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+
+        Ok(conn.execute(
+            "DELETE FROM preset_tasks WHERE preset_id NOT IN (SELECT id FROM presets)",
+            [],
+        )?)
+    }
+
+    // Uppercases priorities of tasks and preset tasks stored before priorities were validated.
+    // Returns the number of fixed rows.
+    pub fn normalize_all_priorities(&mut self) -> Result<usize, TaskRepoError> {
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut fixed = 0;
+        for table in ["tasks", "preset_tasks"] {
+            fixed += tx.execute(
+                &format!("UPDATE {table} SET priority = UPPER(priority) WHERE priority != UPPER(priority)"),
+                [],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(fixed)
+    }
+
+    pub fn get_preset(&mut self, preset_name: &str) -> Result<Preset, TaskRepoError> {
+        // Fetch preset ID
+        let preset_id = self.get_preset_id_from_preset_name(preset_name)?;
+
+        let conn = self.conn()?;
+
+        // Rebuild PresetTask collection
+        let mut stmt = conn.prepare(
+            "
+            SELECT id, preset_id, priority, description FROM preset_tasks
+            WHERE preset_id = :preset_id
+            ",
+        )?;
+        let rows = stmt.query_and_then(
+            named_params! {":preset_id": preset_id},
+            Self::preset_task_from_row,
+        )?;
+        let tasks: Result<Vec<PresetTask>, TaskRepoError> = rows.into_iter().collect();
+
+        // Bind together and return everything
+        Ok(Preset {
+            id: preset_id,
+            name: preset_name.to_string(),
+            tasks: tasks?,
+        })
+    }
+
+    // Adds the preset tasks to the task list, in a project bearing the preset's name. Unless
+    // forced, tasks whose description is already pending in this project are skipped.
+    // Returns how many tasks were actually added.
+    pub fn inject_preset(
+        &mut self,
+        priority_scheme: &PriorityScheme,
+        preset_name: &str,
+        force: bool,
+    ) -> Result<usize, TaskRepoError> {
+        let preset = self.get_preset(preset_name)?;
+
+        // Either all tasks are injected, or none of them
+        let conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut injected = 0;
+        for preset_task in preset.tasks {
+            let mut task = Task::new(
+                priority_scheme,
+                Some(preset_task.priority),
+                &preset_task.description,
+                Some(preset_name),
+            )?;
+            task.source_preset_id = Some(preset.id);
+
+            if force {
+                Self::insert_task(&tx, &task)?;
+            } else {
+                match Self::insert_task_unique(&tx, &task) {
+                    Ok(_) => {}
+                    Err(TaskRepoError::Duplicate { .. }) => continue,
+                    Err(error) => return Err(error),
+                }
+            }
+            injected += 1;
+        }
+        tx.commit()?;
+
+        Ok(injected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use chrono::Datelike;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::sql_connection_factory::tests::{
+        InMemorySqliteConnectionFactory, TempDirSqliteConnectionFactory,
+    };
+
+    use super::*;
+    use crate::task::LETTERS;
+
+    // Counts connections opened to an underlying temporary database
+    struct CountingConnectionFactory {
+        inner: InMemorySqliteConnectionFactory,
+        opened_connections: AtomicUsize,
+    }
+
+    impl SqlConnectionFactory for CountingConnectionFactory {
+        fn open(&self) -> Result<SqlConnection, rusqlite::Error> {
+            self.opened_connections.fetch_add(1, Ordering::SeqCst);
+            self.inner.open()
+        }
+    }
+
+    #[test]
+    fn connection_is_reused() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(CountingConnectionFactory {
+            inner: InMemorySqliteConnectionFactory::new()?,
+            opened_connections: AtomicUsize::new(0),
+        });
+        let mut task_repo = TaskRepo::new(connection_factory.clone());
+
+        task_repo.init_db()?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Important task", None)?)?;
+        task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        task_repo.get_all_projects(ProjectMatch::Exact)?;
+        task_repo.get_all_preset_names()?;
+
+        assert_eq!(
+            connection_factory.opened_connections.load(Ordering::SeqCst),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_all_is_ordered() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        assert!(task_repo.get_task(-1).is_err());
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None).unwrap())?;
+        task_repo
+            .persist_task(&Task::new(&LETTERS, Some('Z'), "Unimportant task", None).unwrap())?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Important task", None).unwrap())?;
+        task_repo.persist_task(
+            &Task::new(&LETTERS, Some('A'), "Another important task", None).unwrap(),
+        )?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Unprioritized task", None).unwrap())?;
+
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks.len(), 5);
+
+        // Tasks should be sorted per decreasing priority, then alphabetically.
+        // Unprioritized tasks come last.
+        let tasks_descriptions: Vec<_> =
+            tasks.iter().map(|task| task.description.clone()).collect();
+
+        assert_eq!(
+            tasks_descriptions,
+            vec![
+                "Another important task",
+                "Important task",
+                "Medium task",
+                "Unimportant task",
+                "Unprioritized task"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reordering() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "First task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Second task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Other task", None)?)?;
+
+        let descriptions = |task_repo: &mut TaskRepo| -> Result<Vec<String>, TaskRepoError> {
+            Ok(task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None,
+                )?
+                .into_iter()
+                .map(|task| task.description)
+                .collect())
+        };
+
+        // Moving down swaps with the next task of the same priority
+        task_repo.move_task_down(1)?;
+        assert_eq!(
+            descriptions(&mut task_repo)?,
+            ["Second task", "First task", "Other task"]
+        );
+
+        // The last task of a priority cannot go any further
+        task_repo.move_task_down(1)?;
+        assert_eq!(
+            descriptions(&mut task_repo)?,
+            ["Second task", "First task", "Other task"]
+        );
+
+        // Moving up reverts it
+        task_repo.move_task_up(1)?;
+        assert_eq!(
+            descriptions(&mut task_repo)?,
+            ["First task", "Second task", "Other task"]
+        );
+
+        // New tasks share the default order index, so they are sorted by description
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Third task", None)?)?;
+        assert_eq!(
+            descriptions(&mut task_repo)?,
+            ["First task", "Third task", "Second task", "Other task"]
+        );
+
+        // Positions past the end move the task last among its siblings
+        task_repo.reorder_task(1, 42)?;
+        assert_eq!(
+            descriptions(&mut task_repo)?,
+            ["Third task", "Second task", "First task", "Other task"]
+        );
+
+        // Siblings are shifted to make room
+        task_repo.reorder_task(1, 0)?;
+        task_repo.reorder_task(4, 2)?;
+        assert_eq!(
+            descriptions(&mut task_repo)?,
+            ["First task", "Second task", "Third task", "Other task"]
+        );
+
+        // Tasks of other projects are not siblings, even if shown in between
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Work task", Some("work"))?)?;
+        task_repo.move_task_down(1)?;
+        assert_eq!(
+            descriptions(&mut task_repo)?,
+            [
+                "Second task",
+                "Work task",
+                "First task",
+                "Third task",
+                "Other task"
+            ]
+        );
+
+        assert!(matches!(
+            task_repo.move_task_down(42),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn persisting() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let task_id = task_repo
+            .persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None).unwrap())?;
+        let mut retrieved_task = task_repo.get_task(task_id)?;
+        assert_eq!(retrieved_task.id, task_id);
+
+        // Should be unchanged
+        assert_eq!(retrieved_task.priority, Some('B'));
+        assert_eq!(retrieved_task.description, "Medium task");
+        assert!(!retrieved_task.completed);
+
+        // Let's update it
+        retrieved_task.lower_priority(&LETTERS);
+        retrieved_task.description = "A new description".into();
+        retrieved_task.completed = true;
+
+        // Updates keep the ID
+        assert_eq!(task_repo.persist_task(&retrieved_task)?, task_id);
+
+        // Let's retrieve it again
+        let retrieved_task = task_repo.get_task(task_id)?;
+
+        // Should have new fields
+        assert_eq!(retrieved_task.priority, Some('C'));
+        assert_eq!(retrieved_task.description, "A new description");
+        assert!(retrieved_task.completed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cleanup() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('C'),
+            "Some low importance task",
+            None,
+        )?)?;
+
+        // Pending tasks are spared
+        task_repo.cleanup(None)?;
+        let mut existing_task = task_repo.get_task(1)?;
+        assert_eq!(existing_task.description, "Some low importance task");
+
+        task_repo.add_note(1, "Some note")?;
+        task_repo.add_subtask(1, "Some subtask")?;
+        task_repo.add_tag_to_task(1, "some-tag")?;
+        task_repo.set_recurrence_paused(1, true)?;
+        task_repo.hide_task(1)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Some dependent task", None)?)?;
+        task_repo.add_dependency(2, 1)?;
+
+        existing_task = task_repo.get_task(1)?;
+        existing_task.completed = true;
+        task_repo.persist_task(&existing_task)?;
+        task_repo
+            .conn()?
+            .execute("UPDATE tasks SET created_at = 1000 WHERE id = 1", [])?;
+        let timestamps = |task_repo: &mut TaskRepo| -> Result<(i64, i64), TaskRepoError> {
+            Ok(task_repo.conn()?.query_row(
+                "SELECT created_at, completed_at FROM tasks WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?)
+        };
+        let (created_at, completed_at) = timestamps(&mut task_repo)?;
+
+        // Completed tasks are deleted, along with their details
+        let cleaned_up_tasks = task_repo.cleanup(None)?;
+        assert!(task_repo.get_task(1).is_err());
+        assert_eq!(cleaned_up_tasks.task_count(), 1);
+        assert!(task_repo.get_blockers(2)?.is_empty());
+
+        // Until the cleanup is undone, which restores them exactly as they were
+        assert_eq!(task_repo.undo_cleanup(&cleaned_up_tasks)?, 1);
+        let restored_task = task_repo.get_task(1)?;
+        assert_eq!(restored_task.priority, Some('C'));
+        assert_eq!(restored_task.description, "Some low importance task");
+        assert!(restored_task.completed);
+        assert!(restored_task.hidden);
+        assert!(restored_task.recurrence_paused);
+        assert_eq!(restored_task.tags, vec!["some-tag"]);
+        assert_eq!(restored_task.subtasks.len(), 1);
+        assert_eq!(task_repo.get_notes(1)?.len(), 1);
+        assert_eq!(timestamps(&mut task_repo)?, (created_at, completed_at));
+        assert_eq!(created_at, 1000);
+        assert_eq!(task_repo.get_blockers(2)?.len(), 1);
+
+        // Tasks reusing the ID of cleaned up ones prevent the undo, which then restores nothing
+        let cleaned_up_tasks = task_repo.cleanup(None)?;
+        task_repo.delete_tasks(&[2])?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Some new task", None)?)?;
+        assert!(task_repo.undo_cleanup(&cleaned_up_tasks).is_err());
+        assert_eq!(task_repo.get_task(1)?.description, "Some new task");
+        assert!(task_repo.get_notes(1)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_operations() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for task_id in 1..=4 {
+            task_repo.persist_task(&Task::new(
+                &LETTERS,
+                Some('B'),
+                &format!("Task {task_id}"),
+                None,
+            )?)?;
+        }
+
+        // Unknown tasks do not prevent the others from being processed
+        assert_eq!(task_repo.complete_tasks(&[1, 2, 42])?, 2);
+        assert!(task_repo.get_task(1)?.completed);
+        assert!(task_repo.get_task(2)?.completed);
+        assert!(!task_repo.get_task(3)?.completed);
+
+        assert_eq!(task_repo.delete_tasks(&[2, 3, 42])?, 2);
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        let descriptions: Vec<&str> = tasks.iter().map(|task| task.description.as_str()).collect();
+        assert_eq!(descriptions, ["Task 4", "Task 1"]);
+
+        // Nothing to do without any ID
+        assert_eq!(task_repo.complete_tasks(&[])?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reopen_all() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Task 1", Some("project"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Task 2", Some("project"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('C'), "Task 3", Some("other"))?)?;
+        assert_eq!(task_repo.complete_tasks(&[1, 2, 3])?, 3);
+
+        let pending_tasks = |task_repo: &mut TaskRepo| -> Result<Vec<TaskId>, TaskRepoError> {
+            Ok(task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        completed: Some(false),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None,
+                )?
+                .iter()
+                .map(|task| task.id)
+                .collect())
+        };
+
+        // Tasks outside of the project stay completed
+        assert_eq!(task_repo.reopen_all(Some("project"))?, 2);
+        assert_eq!(pending_tasks(&mut task_repo)?, [1, 2]);
+        let completed_at: Option<i64> = task_repo.conn()?.query_row(
+            "SELECT completed_at FROM tasks WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(completed_at, None);
+
+        // Without a filter, all of them are reopened
+        assert_eq!(task_repo.reopen_all(None)?, 1);
+        assert_eq!(pending_tasks(&mut task_repo)?, [1, 2, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_operations_by_project() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Task 1", Some("project"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Task 2", Some("project"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('C'), "Task 3", Some("other"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('D'), "Task 4", None)?)?;
+
+        // Tasks outside of the project are left untouched
+        assert_eq!(task_repo.complete_all_in_project("project")?, 2);
+        assert_eq!(task_repo.complete_all_in_project("project")?, 0);
+        assert!(task_repo.get_task(1)?.completed);
+        assert!(task_repo.get_task(2)?.completed);
+        assert!(!task_repo.get_task(3)?.completed);
+        assert!(!task_repo.get_task(4)?.completed);
+        let completed_at: Option<i64> = task_repo.conn()?.query_row(
+            "SELECT completed_at FROM tasks WHERE id = 2",
+            [],
+            |row| row.get(0),
+        )?;
+        assert!(completed_at.is_some());
+
+        assert_eq!(task_repo.delete_all_in_project("project")?, 2);
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        let descriptions: Vec<&str> = tasks.iter().map(|task| task.description.as_str()).collect();
+        assert_eq!(descriptions, ["Task 3", "Task 4"]);
+        assert_eq!(task_repo.delete_all_in_project("unknown")?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn archive() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Pending task", None)?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('B'),
+            "Done task",
+            Some("project"),
+        )?)?;
+        task_repo.complete_tasks(&[2])?;
+        task_repo.add_note(2, "Some note")?;
+        task_repo.add_subtask(2, "Some subtask")?;
+        task_repo.add_tag_to_task(2, "tag")?;
+
+        // Completed tasks leave the task list for the archive
+        assert_eq!(task_repo.archive_completed()?, 1);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            1
+        );
+        let archived_tasks = task_repo.get_archived_tasks()?;
+        assert_eq!(archived_tasks.len(), 1);
+        assert_eq!(archived_tasks[0].description, "Done task");
+        assert_eq!(archived_tasks[0].project, Some("project".into()));
+        assert!(archived_tasks[0].completed);
+
+        // The ID of the archived task is reused in the meantime, which does not prevent restoring it
+        task_repo.persist_task(&Task::new(&LETTERS, Some('C'), "New task", None)?)?;
+        assert_eq!(task_repo.get_task(2)?.description, "New task");
+        let restored_task_id = task_repo.restore_task(archived_tasks[0].id)?;
+        assert_ne!(restored_task_id, 2);
+        let restored_task = task_repo.get_task(restored_task_id)?;
+        assert_eq!(restored_task.description, "Done task");
+        assert!(restored_task.completed);
+        assert!(task_repo.get_archived_tasks()?.is_empty());
+        assert!(matches!(
+            task_repo.restore_task(archived_tasks[0].id),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        // Its details were kept in the archive
+        assert_eq!(restored_task.subtasks[0].description, "Some subtask");
+        assert_eq!(restored_task.tags, ["tag"]);
+        let notes = task_repo.get_notes(restored_task_id)?;
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].body, "Some note");
+
+        Ok(())
+    }
+
+    #[test]
+    fn hide_tasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Visible task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Hidden task", None)?)?;
+        task_repo.hide_task(2)?;
+        assert!(task_repo.get_task(2)?.hidden);
+
+        // Hidden tasks are left out by default, but still pending
+        let descriptions = |task_repo: &mut TaskRepo, hidden_filter| {
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        completed: Some(false),
+                        hidden: hidden_filter,
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None,
+                )
+                .map(|tasks| {
+                    tasks
+                        .into_iter()
+                        .map(|task| task.description)
+                        .collect::<Vec<_>>()
+                })
+        };
+        assert_eq!(descriptions(&mut task_repo, Some(false))?, ["Visible task"]);
+        assert_eq!(descriptions(&mut task_repo, Some(true))?, ["Hidden task"]);
+        assert_eq!(
+            descriptions(&mut task_repo, None)?,
+            ["Visible task", "Hidden task"]
+        );
+        assert_eq!(
+            task_repo.count_tasks(&TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            })?,
+            1
+        );
+
+        // They stay hidden when moved to the archive and back
+        task_repo.complete_tasks(&[2])?;
+        task_repo.archive_completed()?;
+        let archived_task_id = task_repo.get_archived_tasks()?[0].id;
+        let restored_task_id = task_repo.restore_task(archived_task_id)?;
+        assert!(task_repo.get_task(restored_task_id)?.hidden);
+
+        task_repo.unhide_task(restored_task_id)?;
+        assert!(!task_repo.get_task(restored_task_id)?.hidden);
+        assert!(matches!(
+            task_repo.hide_task(99),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cleanup_keep_min() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for task_id in 1..=5 {
+            let mut task = Task::new(&LETTERS, Some('B'), &format!("Task {task_id}"), None)?;
+            task.completed = true;
+            task_repo.persist_task(&task)?;
+        }
+
+        // The two most recent tasks survive, still completed
+        task_repo.cleanup(Some(2))?;
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        let descriptions: Vec<&str> = tasks.iter().map(|task| task.description.as_str()).collect();
+        assert_eq!(descriptions, ["Task 4", "Task 5"]);
+        assert!(tasks.iter().all(|task| task.completed));
+
+        Ok(())
+    }
+
+    #[test]
+    fn project_handling() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        // By default, tasks do not pertain to any project
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None).unwrap())?;
+        let global_task = task_repo.get_task(1)?;
+        assert_eq!(global_task.project, None);
+
+        let all_projects = task_repo.get_all_projects(ProjectMatch::Exact)?;
+        assert_eq!(all_projects.len(), 0);
+
+        // Tasks may have dedicated projects. Projects are created "on-the-fly"
+        task_repo.persist_task(
+            &Task::new(&LETTERS, Some('A'), "Important task", "project".into()).unwrap(),
+        )?;
+        let project_task = task_repo.get_task(2)?;
+        assert_eq!(project_task.project, Some("project".into()));
+
+        let all_projects = task_repo.get_all_projects(ProjectMatch::Exact)?;
+        assert_eq!(all_projects, ["project"]);
+        assert_eq!(task_repo.get_project_stats()?, [("project".into(), 1, 0)]);
+
+        // We can filter per project.
+        let filtered_tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: Some("project"),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(filtered_tasks.len(), 1);
+        assert_eq!(filtered_tasks[0].description, "Important task");
+
+        // We can rename projects
+        task_repo.rename_project("project", "project_2")?;
+        let all_projects = task_repo.get_all_projects(ProjectMatch::Exact)?;
+        assert_eq!(all_projects, ["project_2"]);
+        task_repo.complete_tasks(&[2])?;
+        assert_eq!(task_repo.get_project_stats()?, [("project_2".into(), 0, 1)]);
+        let filtered_tasks_old_project = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: Some("project"),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(filtered_tasks_old_project.len(), 0);
+        let filtered_tasks_new_project = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: Some("project_2"),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(filtered_tasks_new_project.len(), 1);
+        assert_eq!(filtered_tasks_new_project[0].description, "Important task");
+
+        Ok(())
+    }
+
+    #[test]
+    fn dependencies() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for description in ["Buy paint", "Paint walls", "Hang pictures"] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('A'), description, None)?)?;
+        }
+        task_repo.add_dependency(2, 1)?;
+        task_repo.add_dependency(3, 2)?;
+        task_repo.add_dependency(3, 2)?;
+        let blockers = task_repo.get_blockers(3)?;
+        assert_eq!(blockers.len(), 1);
+        assert_eq!(blockers[0].description, "Paint walls");
+
+        // Tasks cannot depend on themselves, even indirectly
+        assert!(task_repo.add_dependency(1, 1).is_err());
+        assert!(task_repo.add_dependency(1, 2).is_err());
+        assert!(task_repo.add_dependency(1, 3).is_err());
+        assert!(task_repo.get_blockers(1)?.is_empty());
+        assert!(matches!(
+            task_repo.add_dependency(1, 99),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        // Tasks are blocked as long as a task they depend on is pending
+        let blocked_descriptions = |task_repo: &mut TaskRepo| {
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None,
+                )
+                .map(|tasks| {
+                    tasks
+                        .into_iter()
+                        .filter(|task| task.blocked)
+                        .map(|task| task.description)
+                        .collect::<Vec<_>>()
+                })
+        };
+        assert_eq!(
+            blocked_descriptions(&mut task_repo)?,
+            ["Hang pictures", "Paint walls"]
+        );
+        task_repo.complete_tasks(&[1])?;
+        assert_eq!(blocked_descriptions(&mut task_repo)?, ["Hang pictures"]);
+        assert!(!task_repo.get_task(2)?.blocked);
+        assert!(task_repo.get_task(3)?.blocked);
+
+        task_repo.remove_dependency(3, 2)?;
+        assert!(!task_repo.get_task(3)?.blocked);
+
+        // Dependencies go away with their tasks
+        task_repo.delete_tasks(&[1])?;
+        assert!(task_repo.get_blockers(2)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn description_suggestions() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for description in ["Buy milk", "Buy bread", "Call mom", "Buy milk", "100% done"] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('A'), description, None)?)?;
+        }
+
+        assert_eq!(
+            task_repo.description_suggestions("Buy", 10)?,
+            ["Buy bread", "Buy milk"]
+        );
+        assert_eq!(task_repo.description_suggestions("Buy", 1)?, ["Buy bread"]);
+        assert!(task_repo.description_suggestions("Walk", 10)?.is_empty());
+
+        // Wildcards are matched literally
+        assert!(task_repo.description_suggestions("%", 10)?.is_empty());
+        assert!(task_repo.description_suggestions("B_y", 10)?.is_empty());
+        assert_eq!(
+            task_repo.description_suggestions("100%", 10)?,
+            ["100% done"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_task() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Some task", Some("work"))?)?;
+        task_repo.complete_tasks(&[1])?;
+
+        // The copy starts pending, with a new ID
+        let copy_id = task_repo.duplicate_task(1)?;
+        assert_ne!(copy_id, 1);
+        let copy = task_repo.get_task(copy_id)?;
+        assert_eq!(copy.priority, Some('B'));
+        assert_eq!(copy.description, "Some task");
+        assert_eq!(copy.project, Some("work".into()));
+        assert!(!copy.completed);
+        assert!(task_repo.get_task(1)?.completed);
+
+        // Further copies are suffixed, as they would be rejected as duplicates of the pending one
+        let copy_id = task_repo.duplicate_task(1)?;
+        assert_eq!(task_repo.get_task(copy_id)?.description, "Some task (copy)");
+        let copy_id = task_repo.duplicate_task(1)?;
+        assert_eq!(
+            task_repo.get_task(copy_id)?.description,
+            "Some task (copy 2)"
+        );
+
+        assert!(matches!(
+            task_repo.duplicate_task(99),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn board_counts() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (description, project, completed) in [
+            ("Task 1", Some("work"), false),
+            ("Task 2", Some("work"), true),
+            ("Task 3", Some("work"), true),
+            ("Task 4", Some("home"), false),
+            ("Task 5", None, false),
+            ("Task 6", None, true),
+        ] {
+            let mut task = Task::new(&LETTERS, Some('B'), description, project)?;
+            task.completed = completed;
+            task_repo.persist_task(&task)?;
+        }
+
+        // Hidden tasks are left out
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Task 7", Some("home"))?)?;
+        task_repo.hide_task(7)?;
+
+        assert_eq!(
+            task_repo.board_counts()?,
+            [
+                (Some("home".into()), 1, 0),
+                (Some("work".into()), 1, 2),
+                (None, 1, 1)
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn project_completion_ratios() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for description in ["Task 1", "Task 2", "Task 3", "Task 4"] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('A'), description, Some("work"))?)?;
+        }
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Chore", Some("house"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Loose task", None)?)?;
+        task_repo.complete_tasks(&[1, 6])?;
+
+        // Tasks out of any project are left out
+        assert_eq!(
+            task_repo.project_completion_ratios()?,
+            [("house".into(), 0.0), ("work".into(), 0.25)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn project_colors() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        // Projects need no color to exist
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Some task", Some("work"))?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Other task",
+            Some("house"),
+        )?)?;
+        assert_eq!(
+            task_repo.get_all_projects(ProjectMatch::Exact)?,
+            ["house", "work"]
+        );
+        assert!(task_repo.get_project_colors()?.is_empty());
+
+        // Colors may be set, then changed
+        task_repo.set_project_color("work", "#FF0000")?;
+        task_repo.set_project_color("house", "#00ff00")?;
+        task_repo.set_project_color("house", "#0000ff")?;
+        assert_eq!(
+            task_repo.get_project_colors()?,
+            HashMap::from([
+                ("work".into(), "#ff0000".into()),
+                ("house".into(), "#0000ff".into())
+            ])
+        );
+
+        // Anything but a hex color is rejected
+        for invalid_color in ["red", "#fff", "#ff00zz", "\"#ff0000"] {
+            assert!(task_repo.set_project_color("work", invalid_color).is_err());
+        }
+
+        // The color follows the project when renamed
+        task_repo.rename_project("work", "work")?;
+        task_repo.rename_project("work", "job")?;
+        assert_eq!(
+            task_repo.get_project_colors()?.get("job"),
+            Some(&"#ff0000".into())
+        );
+        assert_eq!(task_repo.get_project_colors()?.get("work"), None);
+
+        // Unless renamed into a project which already has a color
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Job task", Some("job"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "House task", Some("house"))?)?;
+        task_repo.rename_project("job", "house")?;
+        assert_eq!(
+            task_repo.get_project_colors()?,
+            HashMap::from([("house".into(), "#0000ff".into())])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unique_tasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.add_task_unique(&Task::new(&LETTERS, Some('A'), "Some task", Some("work"))?)?;
+
+        // The same pending task cannot be added twice, whatever its priority
+        assert!(matches!(
+            task_repo.add_task_unique(&Task::new(&LETTERS, Some('B'), "Some task", Some("work"))?),
+            Err(TaskRepoError::Duplicate { .. })
+        ));
+
+        // It may be added to another project, or outside of any project
+        task_repo.add_task_unique(&Task::new(&LETTERS, Some('A'), "Some task", Some("house"))?)?;
+        task_repo.add_task_unique(&Task::new(&LETTERS, Some('A'), "Some task", None)?)?;
+
+        // Or once the previous one is completed
+        task_repo.complete_tasks(&[1])?;
+        task_repo.add_task_unique(&Task::new(&LETTERS, Some('A'), "Some task", Some("work"))?)?;
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            4
+        );
+
+        // Unchecked insertions are still allowed
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Some task", Some("work"))?)?;
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            5
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sql_error_kinds() {
+        let sqlite_failure = |code| TaskRepoError::SqlError {
+            original_error: rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), None),
+        };
+
+        assert_eq!(
+            sqlite_failure(rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE).sql_error_kind(),
+            Some(SqlErrorKind::ConstraintViolation)
+        );
+        assert_eq!(
+            sqlite_failure(rusqlite::ffi::SQLITE_BUSY).sql_error_kind(),
+            Some(SqlErrorKind::Busy)
+        );
+        assert_eq!(
+            TaskRepoError::from(rusqlite::Error::QueryReturnedNoRows).sql_error_kind(),
+            Some(SqlErrorKind::NotFound)
+        );
+
+        // Other errors are left to the caller
+        assert_eq!(
+            sqlite_failure(rusqlite::ffi::SQLITE_CORRUPT).sql_error_kind(),
+            None
+        );
+        assert_eq!(
+            TaskRepoError::Error {
+                error: "Some error".into()
+            }
+            .sql_error_kind(),
+            None
+        );
+    }
+
+    #[test]
+    fn tasks_by_priority() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (priority, description, project) in [
+            (Some('A'), "Important task", None),
+            (Some('A'), "Important project task", Some("project")),
+            (Some('C'), "Minor task", None),
+            (None, "Unprioritized task", None),
+        ] {
+            task_repo.persist_task(&Task::new(&LETTERS, priority, description, project)?)?;
+        }
+
+        let descriptions = |tasks: Vec<Task>| -> Vec<String> {
+            tasks.into_iter().map(|task| task.description).collect()
+        };
+        assert_eq!(
+            descriptions(task_repo.get_by_priority(&LETTERS, 'A', None)?),
+            ["Important project task", "Important task"]
+        );
+        assert_eq!(
+            descriptions(task_repo.get_by_priority(&LETTERS, 'A', Some("project"))?),
+            ["Important project task"]
+        );
+        assert!(task_repo.get_by_priority(&LETTERS, 'B', None)?.is_empty());
+
+        // Priorities outside of the scheme are rejected
+        assert!(task_repo.get_by_priority(&LETTERS, '1', None).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pagination() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (priority, description, project) in [
+            (Some('A'), "Task 1", Some("project")),
+            (Some('B'), "Task 2", None),
+            (Some('C'), "Task 3", Some("project")),
+            (Some('A'), "Task 4", Some("project")),
+            (Some('D'), "Task 5", Some("project")),
+        ] {
+            task_repo.persist_task(&Task::new(&LETTERS, priority, description, project)?)?;
+        }
+        task_repo.complete_tasks(&[4])?;
+
+        // Completed tasks still sink to the last page
+        let page_descriptions = |task_repo: &mut TaskRepo, project, offset| {
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project,
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    Some(Page { limit: 2, offset }),
+                )
+                .map(|tasks| {
+                    tasks
+                        .into_iter()
+                        .map(|task| task.description)
+                        .collect::<Vec<_>>()
+                })
+        };
+        assert_eq!(
+            page_descriptions(&mut task_repo, None, 0)?,
+            ["Task 1", "Task 2"]
+        );
+        assert_eq!(
+            page_descriptions(&mut task_repo, None, 2)?,
+            ["Task 3", "Task 5"]
+        );
+        assert_eq!(page_descriptions(&mut task_repo, None, 4)?, ["Task 4"]);
+        assert!(page_descriptions(&mut task_repo, None, 6)?.is_empty());
+        assert!(page_descriptions(&mut task_repo, None, usize::MAX)?.is_empty());
+        assert_eq!(
+            task_repo.count_tasks(&TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            })?,
+            5
+        );
+
+        // Filters apply before pagination
+        assert_eq!(
+            page_descriptions(&mut task_repo, Some("project"), 2)?,
+            ["Task 5", "Task 4"]
+        );
+        assert_eq!(
+            task_repo.count_tasks(&TaskFilter {
+                project: Some("project"),
+                hidden: Some(false),
+                ..Default::default()
+            })?,
+            4
+        );
+        assert_eq!(
+            task_repo.count_tasks(&TaskFilter {
+                project: Some("project"),
+                completed: Some(false),
+                hidden: Some(false),
+                ..Default::default()
+            })?,
+            3
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sort_orders() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (priority, description, due_date) in [
+            (Some('B'), "Banana", NaiveDate::from_ymd_opt(2024, 5, 1)),
+            (Some('A'), "Cherry", None),
+            (None, "Apple", NaiveDate::from_ymd_opt(2024, 4, 1)),
+            (Some('A'), "Done", NaiveDate::from_ymd_opt(2024, 1, 1)),
+        ] {
+            let mut task = Task::new(&LETTERS, priority, description, None)?;
+            task.due_date = due_date;
+            task_repo.persist_task(&task)?;
+        }
+        task_repo.complete_tasks(&[4])?;
+
+        // Completed tasks always come last
+        for (sort_by, expected_descriptions) in [
+            (SortBy::Priority, ["Cherry", "Banana", "Apple", "Done"]),
+            (SortBy::Description, ["Apple", "Banana", "Cherry", "Done"]),
+            (SortBy::Created, ["Banana", "Cherry", "Apple", "Done"]),
+            (SortBy::DueDate, ["Apple", "Banana", "Cherry", "Done"]),
+        ] {
+            let descriptions: Vec<String> = task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    sort_by,
+                    None,
+                )?
+                .into_iter()
+                .map(|task| task.description)
+                .collect();
+            assert_eq!(descriptions, expected_descriptions, "{:?}", sort_by);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn missed_tasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let as_of = NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        for (description, due_date) in [
+            ("Late task", NaiveDate::from_ymd_opt(2024, 5, 9)),
+            ("Very late task", NaiveDate::from_ymd_opt(2024, 5, 5)),
+            ("Future task", NaiveDate::from_ymd_opt(2024, 5, 11)),
+            ("Today task", Some(as_of)),
+            ("Undated task", None),
+            ("Completed late task", NaiveDate::from_ymd_opt(2024, 5, 1)),
+        ] {
+            let mut task = Task::new(&LETTERS, Some('A'), description, None)?;
+            task.due_date = due_date;
+            task_repo.persist_task(&task)?;
+        }
+        task_repo.complete_tasks(&[6])?;
+
+        let missed_tasks: Vec<String> = task_repo
+            .get_missed_tasks(as_of)?
+            .into_iter()
+            .map(|task| task.description)
+            .collect();
+        assert_eq!(missed_tasks, ["Very late task", "Late task"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reset_overdue() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let as_of = NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        for (description, due_date) in [
+            ("Very late task", NaiveDate::from_ymd_opt(2024, 1, 1)),
+            ("Late task", NaiveDate::from_ymd_opt(2024, 5, 9)),
+            ("Future task", NaiveDate::from_ymd_opt(2024, 5, 11)),
+            ("Undated task", None),
+            ("Completed late task", NaiveDate::from_ymd_opt(2024, 1, 1)),
+        ] {
+            let mut task = Task::new(&LETTERS, Some('A'), description, None)?;
+            task.due_date = due_date;
+            task_repo.persist_task(&task)?;
+        }
+        task_repo.complete_tasks(&[5])?;
+
+        // Only pending overdue tasks are moved
+        assert_eq!(task_repo.reset_overdue(as_of)?, 2);
+        let due_dates: Vec<Option<NaiveDate>> = (1..=5)
+            .map(|task_id| task_repo.get_task(task_id).map(|task| task.due_date))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(
+            due_dates,
+            [
+                Some(as_of),
+                Some(as_of),
+                NaiveDate::from_ymd_opt(2024, 5, 11),
+                None,
+                NaiveDate::from_ymd_opt(2024, 1, 1)
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn clear_project() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (description, project) in [
+            ("Task 1", Some("work")),
+            ("Task 2", Some("work")),
+            ("Task 3", Some("home")),
+            ("Task 4", None),
+        ] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('B'), description, project)?)?;
+        }
+        assert_eq!(
+            task_repo.get_project_task_counts()?,
+            [("home".into(), 1), ("work".into(), 2)]
+        );
+
+        // The project vanishes, its tasks being kept out of any project
+        assert_eq!(task_repo.clear_project("work")?, 2);
+        assert_eq!(task_repo.get_all_projects(ProjectMatch::Exact)?, ["home"]);
+        assert_eq!(task_repo.get_project_task_counts()?, [("home".into(), 1)]);
+        assert_eq!(task_repo.get_task(1)?.project, None);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some(""),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            3
+        );
+
+        // Unknown projects have nothing to clear
+        assert_eq!(task_repo.clear_project("unknown")?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_projects() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (description, project) in [
+            ("Task 1", Some("Work")),
+            ("Task 2", Some("Work")),
+            ("Task 3", Some("Job")),
+            ("Task 4", None),
+        ] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('B'), description, project)?)?;
+        }
+
+        task_repo.set_project_color("Work", "#ff0000")?;
+        task_repo.set_project_color("Job", "#00ff00")?;
+
+        // The target keeps its own color, the one of the merged project being dropped
+        assert_eq!(task_repo.merge_projects("Work", "Job")?, 2);
+        assert_eq!(task_repo.get_all_projects(ProjectMatch::Exact)?, ["Job"]);
+        assert_eq!(
+            task_repo.get_project_colors()?,
+            HashMap::from([("Job".into(), "#00ff00".into())])
+        );
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some("Job"),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            3
+        );
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some(""),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            1
+        );
+
+        // Nothing to move from unknown projects
+        assert_eq!(task_repo.merge_projects("unknown", "work")?, 0);
+
+        // The target keeps its spelling whatever the one given
+        assert_eq!(task_repo.merge_projects("", " JOB ")?, 1);
+        assert_eq!(task_repo.get_all_projects(ProjectMatch::Exact)?, ["Job"]);
+
+        // Projects merged into one without a color bring theirs, unless merged out of any project
+        assert_eq!(task_repo.merge_projects("Job", "Career")?, 4);
+        assert_eq!(
+            task_repo.get_project_colors()?,
+            HashMap::from([("Career".into(), "#00ff00".into())])
+        );
+        assert_eq!(task_repo.merge_projects("Career", "")?, 4);
+        assert!(task_repo.get_project_colors()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn delete_project() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (description, project) in [
+            ("Task 1", Some("old")),
+            ("Task 2", Some("old")),
+            ("Task 3", Some("obsolete")),
+            ("Task 4", Some("kept")),
+            ("Task 5", None),
+        ] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('B'), description, project)?)?;
+        }
+
+        // Tasks may be reassigned to another project...
+        assert_eq!(task_repo.delete_project("old", Some("kept"))?, 2);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some("kept"),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            3
+        );
+
+        // ...or deleted along with the project
+        assert_eq!(task_repo.delete_project("obsolete", None)?, 1);
+        assert_eq!(task_repo.get_all_projects(ProjectMatch::Exact)?, ["kept"]);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            4
+        );
+
+        // Tasks outside of any project are not a project
+        assert!(task_repo.delete_project("", None).is_err());
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some(""),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn no_project_filter() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Project task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Uncategorized task", None)?)?;
+
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: Some(""),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "Uncategorized task");
+        assert_eq!(tasks[0].project, None);
+
+        // Filtering on real projects is unaffected
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: Some("project"),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "Project task");
+
+        Ok(())
+    }
+
+    #[test]
+    fn completed_filter() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Pending task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('B'),
+            "Completed task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('C'),
+            "Other completed task",
+            None,
+        )?)?;
+        for task_id in [2, 3] {
+            let mut task = task_repo.get_task(task_id)?;
+            task.completed = true;
+            task_repo.persist_task(&task)?;
+        }
+
+        let pending_tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                completed: Some(false),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(pending_tasks.len(), 1);
+        assert_eq!(pending_tasks[0].description, "Pending task");
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        completed: Some(true),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            2
+        );
+
+        // Composes with the project filter
+        let completed_project_tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: Some("project"),
+                completed: Some(true),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(completed_project_tasks.len(), 1);
+        assert_eq!(completed_project_tasks[0].description, "Completed task");
+
+        Ok(())
+    }
+
+    #[test]
+    fn undated_tasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let mut dated_task = Task::new(&LETTERS, Some('A'), "Dated task", None)?;
+        dated_task.due_date = NaiveDate::from_ymd_opt(2026, 1, 1);
+        task_repo.persist_task(&dated_task)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Undated task", None)?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            None,
+            "Other undated task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('C'),
+            "Completed undated task",
+            None,
+        )?)?;
+        let mut completed_task = task_repo.get_task(4)?;
+        completed_task.completed = true;
+        task_repo.persist_task(&completed_task)?;
+
+        assert_eq!(task_repo.get_task(1)?.due_date, dated_task.due_date);
+
+        // Only pending tasks without a due date are returned
+        let undated_tasks = task_repo.get_undated_tasks(None)?;
+        let descriptions: Vec<&str> = undated_tasks
+            .iter()
+            .map(|task| task.description.as_str())
+            .collect();
+        assert_eq!(descriptions, ["Undated task", "Other undated task"]);
+
+        let undated_project_tasks = task_repo.get_undated_tasks(Some("project"))?;
+        assert_eq!(undated_project_tasks.len(), 1);
+        assert_eq!(undated_project_tasks[0].description, "Other undated task");
+
+        Ok(())
+    }
+
+    #[test]
+    fn subtasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Big task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Small task", None)?)?;
+        let first_subtask_id = task_repo.add_subtask(1, "First step")?;
+        task_repo.add_subtask(1, " Second step ")?;
+        assert_eq!(task_repo.get_subtasks(1)?[1].description, "Second step");
+
+        // Subtasks are described like tasks, and only added to existing ones
+        assert!(matches!(
+            task_repo.add_subtask(1, " "),
+            Err(TaskRepoError::TaskError { .. })
+        ));
+        assert!(matches!(
+            task_repo.add_subtask(99, "Step"),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        // Subtasks may be completed, and pending again
+        assert!(task_repo.toggle_subtask(first_subtask_id)?.completed);
+        assert!(matches!(
+            task_repo.toggle_subtask(99),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        // Subtasks come along with their task
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks[0].subtasks.len(), 2);
+        assert_eq!(
+            tasks[0]
+                .subtasks
+                .iter()
+                .filter(|subtask| subtask.completed)
+                .count(),
+            1
+        );
+        assert_eq!(tasks[1].subtasks.len(), 0);
+        assert_eq!(task_repo.get_task(1)?.subtasks, tasks[0].subtasks);
+
+        assert!(!task_repo.toggle_subtask(first_subtask_id)?.completed);
+
+        // Subtasks are deleted along with their task
+        task_repo.delete_tasks(&[1])?;
+        assert_eq!(task_repo.get_subtasks(1)?.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tags() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Home task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Urgent home task", None)?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('C'),
+            "Untagged task",
+            Some("project"),
+        )?)?;
+        task_repo.add_tag_to_task(1, "@home")?;
+        task_repo.add_tag_to_task(2, "urgent")?;
+        task_repo.add_tag_to_task(2, "@home")?;
+        task_repo.add_tag_to_task(2, "@home")?; // No-op
+        assert!(task_repo.add_tag_to_task(3, " ").is_err());
+
+        // Tags come along with their task, sorted
+        assert_eq!(task_repo.get_tags_for_task(2)?, ["@home", "urgent"]);
+        assert_eq!(task_repo.get_task(2)?.tags, ["@home", "urgent"]);
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks[0].tags, ["@home"]);
+        assert!(tasks[2].tags.is_empty());
+
+        // Tag filtering composes with the other filters
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        tag: Some("@home"),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            2
+        );
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: Some("project"),
+                tag: Some("@home"),
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "Home task");
+        assert!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        tag: Some("unknown"),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .is_empty()
+        );
+
+        task_repo.remove_tag_from_task(2, "@home")?;
+        assert_eq!(task_repo.get_tags_for_task(2)?, ["urgent"]);
+        assert!(matches!(
+            task_repo.remove_tag_from_task(2, "@home"),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        // Tags are removed along with their task
+        task_repo.delete_tasks(&[1])?;
+        assert!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        tag: Some("@home"),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn notes() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Annotated task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Other task", None)?)?;
+        assert_eq!(task_repo.get_notes(1)?.len(), 0);
+
+        // Notes are listed in the order they were added, per task
+        let first_note_id = task_repo.add_note(1, "First note")?;
+        task_repo.add_note(1, "Second note")?;
+        task_repo.add_note(2, "Unrelated note")?;
+        let notes = task_repo.get_notes(1)?;
+        let bodies: Vec<&str> = notes.iter().map(|note| note.body.as_str()).collect();
+        assert_eq!(bodies, ["First note", "Second note"]);
+        assert!(notes[0].created_at > 0);
+
+        // Notes may be deleted one by one, through their own task only...
+        assert!(matches!(
+            task_repo.delete_note(2, first_note_id),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+        task_repo.delete_note(1, first_note_id)?;
+        assert_eq!(task_repo.get_notes(1)?.len(), 1);
+        assert!(matches!(
+            task_repo.delete_note(1, first_note_id),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        // ... or along with their task
+        let mut task = task_repo.get_task(1)?;
+        task.completed = true;
+        task_repo.persist_task(&task)?;
+        task_repo.cleanup(None)?;
+        let conn = task_repo.conn()?;
+        let remaining_notes: i64 =
+            conn.query_row("SELECT COUNT(*) FROM task_notes", [], |row| row.get(0))?;
+        assert_eq!(remaining_notes, 1);
+
+        // Multi-line notes are kept as is, blank ones are rejected
+        task_repo.add_note(2, "  First line\nSecond line\n")?;
+        assert_eq!(task_repo.get_notes(2)?[1].body, "First line\nSecond line");
+        assert!(task_repo.add_note(2, " \n\t").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stats() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        // Empty task lists have no breakdown
+        let stats = task_repo.get_stats()?;
+        assert_eq!(stats.total, 0);
+        assert_eq!(stats.by_priority, []);
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None)?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Important task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('B'),
+            "Other task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Unprioritized task", None)?)?;
+        let mut completed_task = task_repo.get_task(1)?;
+        completed_task.completed = true;
+        task_repo.persist_task(&completed_task)?;
+
+        let stats = task_repo.get_stats()?;
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.completed, 1);
+        assert_eq!(stats.pending, 3);
+        assert_eq!(
+            stats.by_priority,
+            [
+                PriorityCount {
+                    priority: Some('A'),
+                    count: 1
+                },
+                PriorityCount {
+                    priority: Some('B'),
+                    count: 2
+                },
+                PriorityCount {
+                    priority: None,
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            stats.by_project,
+            [
+                ProjectCount {
+                    project: Some("project".into()),
+                    count: 2
+                },
+                ProjectCount {
+                    project: None,
+                    count: 2
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn changes() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Completed task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Edited task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('C'), "Untouched task", None)?)?;
+        task_repo.move_task_up(3)?; // Reordering is not an edit
+
+        let mut completed_task = task_repo.get_task(1)?;
+        completed_task.completed = true;
+        task_repo.persist_task(&completed_task)?;
+        task_repo.cleanup(None)?;
+
+        let mut edited_task = task_repo.get_task(2)?;
+        edited_task.lower_priority(&LETTERS);
+        task_repo.persist_task(&edited_task)?;
+        task_repo.persist_task(&edited_task)?; // Unchanged, hence not an edit either
+
+        let changes = task_repo.get_changes(0, i64::MAX)?;
+        assert_eq!(
+            changes,
+            TaskChanges {
+                created: vec![1, 2, 3],
+                completed: vec![1],
+                deleted: vec![1],
+                edited: vec![2],
+            }
+        );
+
+        // Nothing happened before the epoch
+        assert_eq!(task_repo.get_changes(0, 0)?, TaskChanges::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn avg_completion_time_by_priority() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (priority, description) in [
+            (Some('A'), "Quick task"),
+            (Some('A'), "Other quick task"),
+            (Some('B'), "Slow task"),
+            (Some('B'), "Pending task"),
+            (None, "Unprioritized task"),
+        ] {
+            task_repo.persist_task(&Task::new(&LETTERS, priority, description, None)?)?;
+        }
+        task_repo.complete_tasks(&[1, 2, 3, 5])?;
+
+        // Timestamps are set on creation and completion
+        let (created_at, completed_at): (Option<i64>, Option<i64>) = task_repo.conn()?.query_row(
+            "SELECT created_at, completed_at FROM tasks WHERE id = 4",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert!(created_at.is_some());
+        assert!(completed_at.is_none());
+        assert_eq!(Some(task_repo.get_task(4)?.created_at), created_at);
+
+        // Tasks created before timestamps were recorded have an unknown creation time
+        task_repo
+            .conn()?
+            .execute("UPDATE tasks SET created_at = NULL WHERE id = 4", ())?;
+        assert_eq!(task_repo.get_task(4)?.created_at, 0);
+
+        for (task_id, created_at, completed_at) in
+            [(1, 100, 200), (2, 100, 400), (3, 0, 1000), (5, 0, 10)]
+        {
+            task_repo.conn()?.execute(
+                "UPDATE tasks SET created_at = ?, completed_at = ? WHERE id = ?",
+                (created_at, completed_at, task_id),
+            )?;
+        }
+
+        assert_eq!(
+            task_repo.avg_completion_time_by_priority()?,
+            [('A', 200.0), ('B', 1000.0)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn completed_between() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for description in ["Old task", "Late task", "Early task", "Pending task"] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('B'), description, None)?)?;
+        }
+        task_repo.complete_tasks(&[1, 2, 3])?;
+        for (task_id, completed_at) in [(1, 100), (2, 300), (3, 200)] {
+            task_repo.conn()?.execute(
+                "UPDATE tasks SET completed_at = ? WHERE id = ?",
+                (completed_at, task_id),
+            )?;
+        }
+
+        let tasks = task_repo.get_completed_between(200, 300)?;
+        let descriptions: Vec<&str> = tasks.iter().map(|task| task.description.as_str()).collect();
+        assert_eq!(descriptions, ["Early task", "Late task"]);
+        assert!(task_repo.get_completed_between(301, 400)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn shift_priority() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let priority_scheme = PriorityScheme::new('A', 'E')?
+            .with_max_assignable('B', crate::task::PriorityCapMode::Reject)?;
+        task_repo.persist_task(&Task::new(&priority_scheme, None, "Some task", None)?)?;
+        task_repo.persist_task(&Task::new(&priority_scheme, None, "Other task", None)?)?;
+
+        // Unprioritized tasks start from the bottom, or from the top
+        let task = task_repo.shift_priority(1, &priority_scheme, -1)?;
+        assert_eq!(task.priority, Some('E'));
+        let task = task_repo.shift_priority(2, &priority_scheme, 1)?;
+        assert_eq!(task.priority, Some('B'));
+
+        // Priorities stay within the assignable ones
+        let task = task_repo.shift_priority(1, &priority_scheme, 1)?;
+        assert_eq!(task.priority, Some('E'));
+        for _ in 0..5 {
+            task_repo.shift_priority(1, &priority_scheme, -1)?;
+        }
+        assert_eq!(task_repo.get_task(1)?.priority, Some('B'));
+
+        // Whatever the starting priority, this behaves as shifting the task in memory
+        for priority in [None, Some('A'), Some('B'), Some('C'), Some('E')] {
+            for step in [-1, 1] {
+                let mut task = task_repo.get_task(2)?;
+                task.priority = priority;
+                task_repo.persist_task(&task)?;
+
+                match step {
+                    -1 => task.increase_priority(&priority_scheme),
+                    _ => task.lower_priority(&priority_scheme),
+                }
+                let shifted_task = task_repo.shift_priority(2, &priority_scheme, step)?;
+                assert_eq!(shifted_task.priority, task.priority);
+            }
+        }
+
+        assert!(matches!(
+            task_repo.shift_priority(99, &priority_scheme, 1),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_priority_shifts() -> Result<(), TaskRepoError> {
+        // Each thread needs a connection of its own to the same database
+        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory.clone());
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('Z'), "Some task", None)?)?;
+
+        let barrier = std::sync::Barrier::new(2);
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    let mut task_repo = TaskRepo::new(connection_factory.clone());
+                    barrier.wait();
+                    for _ in 0..10 {
+                        task_repo.shift_priority(1, &LETTERS, -1).unwrap();
+                    }
+                });
+            }
+        });
+
+        // No increase got lost
+        assert_eq!(task_repo.get_task(1)?.priority, Some('F'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_priority() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None)?)?;
+
+        // Setting the same priority twice is harmless
+        for _ in 0..2 {
+            assert_eq!(
+                task_repo.set_priority(1, &LETTERS, 'A')?.priority,
+                Some('A')
+            );
+            assert_eq!(task_repo.get_task(1)?.priority, Some('A'));
+        }
+
+        // Priorities outside of the scheme are rejected
+        assert!(task_repo.set_priority(1, &LETTERS, '1').is_err());
+        assert_eq!(task_repo.get_task(1)?.priority, Some('A'));
+        assert!(task_repo.set_priority(99, &LETTERS, 'A').is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn oldest_pending_per_project() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        for (description, project, created_at) in [
+            ("Recent work task", Some("work"), 300),
+            ("Old work task", Some("work"), 100),
+            ("Oldest work task", Some("work"), 50),
+            ("Recent house task", Some("house"), 200),
+            ("Old house task", Some("house"), 150),
+            ("Task without project", None, 0),
+        ] {
+            task_repo.persist_task(&Task::new(&LETTERS, Some('A'), description, project)?)?;
+            task_repo.conn()?.execute(
+                "UPDATE tasks SET created_at = ? WHERE description = ?",
+                (created_at, description),
+            )?;
+        }
+        // Completed tasks are not neglected, however old they are
+        task_repo.complete_tasks(&[3])?;
+
+        let oldest_tasks: Vec<(String, String)> = task_repo
+            .oldest_pending_per_project()?
+            .into_iter()
+            .map(|(project, task)| (project, task.description))
+            .collect();
+        assert_eq!(
+            oldest_tasks,
+            [
+                ("house".into(), "Old house task".into()),
+                ("work".into(), "Old work task".into())
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn recurring_tasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let mut recurring_task = Task::new(&LETTERS, Some('B'), "Water the plants", Some("house"))?;
+        recurring_task.due_date = NaiveDate::from_ymd_opt(2024, 5, 10);
+        recurring_task.recurrence = Some(Recurrence::Weekly);
+        task_repo.persist_task(&recurring_task)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "One-off task", None)?)?;
+
+        // The original task is completed, and a fresh copy is due a week later
+        let new_task_id = task_repo.complete_and_recur(1)?.expect("Task should recur");
+        assert!(task_repo.get_task(1)?.completed);
+        let new_task = task_repo.get_task(new_task_id)?;
+        assert!(!new_task.completed);
+        assert_eq!(new_task.priority, Some('B'));
+        assert_eq!(new_task.description, "Water the plants");
+        assert_eq!(new_task.project, Some("house".into()));
+        assert_eq!(new_task.due_date, NaiveDate::from_ymd_opt(2024, 5, 17));
+        assert_eq!(new_task.recurrence, Some(Recurrence::Weekly));
+
+        // Other tasks are simply completed
+        assert_eq!(task_repo.complete_and_recur(2)?, None);
+        assert!(task_repo.get_task(2)?.completed);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        completed: Some(false),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            1
+        );
+
+        // Tasks due at the end of time cannot recur, and are then left pending
+        let mut task = task_repo.get_task(new_task_id)?;
+        task.due_date = Some(NaiveDate::MAX);
+        task_repo.persist_task(&task)?;
+        assert!(matches!(
+            task_repo.complete_and_recur(new_task_id),
+            Err(TaskRepoError::TaskError { .. })
+        ));
+        assert!(!task_repo.get_task(new_task_id)?.completed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_recurrence() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let mut recurring_task = Task::new(&LETTERS, Some('B'), "Water the plants", None)?;
+        recurring_task.due_date = NaiveDate::from_ymd_opt(2024, 5, 10); // A Friday
+        recurring_task.recurrence = Some(Recurrence::Daily);
+        task_repo.persist_task(&recurring_task)?;
+
+        // From daily to weekly, the next occurrence is a week later
+        task_repo.set_recurrence(1, Some(Recurrence::Weekly), None)?;
+        assert_eq!(task_repo.get_task(1)?.recurrence, Some(Recurrence::Weekly));
+        let new_task_id = task_repo.complete_and_recur(1)?.expect("Task should recur");
+        let new_task = task_repo.get_task(new_task_id)?;
+        assert_eq!(new_task.due_date, NaiveDate::from_ymd_opt(2024, 5, 17));
+        assert_eq!(new_task.recurrence, Some(Recurrence::Weekly));
+
+        // Anchoring on a weekday moves the due date to the next such day
+        task_repo.set_recurrence(new_task_id, Some(Recurrence::Weekly), Some(Weekday::Mon))?;
+        assert_eq!(
+            task_repo.get_task(new_task_id)?.due_date,
+            NaiveDate::from_ymd_opt(2024, 5, 20)
+        );
+        let next_task_id = task_repo
+            .complete_and_recur(new_task_id)?
+            .expect("Task should recur");
+        assert_eq!(
+            task_repo.get_task(next_task_id)?.due_date,
+            NaiveDate::from_ymd_opt(2024, 5, 27)
+        );
+
+        // Only weekly recurrences fall on a weekday
+        assert!(
+            task_repo
+                .set_recurrence(next_task_id, Some(Recurrence::Monthly), Some(Weekday::Mon))
+                .is_err()
+        );
+
+        // Tasks may also stop recurring
+        task_repo.set_recurrence(next_task_id, None, None)?;
+        assert_eq!(task_repo.complete_and_recur(next_task_id)?, None);
+
+        assert!(task_repo.set_recurrence(99, None, None).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn paused_recurrence() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let mut recurring_task = Task::new(&LETTERS, Some('B'), "Water the plants", None)?;
+        recurring_task.due_date = NaiveDate::from_ymd_opt(2024, 5, 10);
+        recurring_task.recurrence = Some(Recurrence::Weekly);
+        task_repo.persist_task(&recurring_task)?;
+
+        // A paused task is completed without any new occurrence
+        task_repo.set_recurrence_paused(1, true)?;
+        assert!(task_repo.get_task(1)?.recurrence_paused);
+        assert_eq!(task_repo.complete_and_recur(1)?, None);
+        assert!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        completed: Some(false),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .is_empty()
+        );
+
+        // Once resumed, it recurs again on next completion
+        task_repo.set_recurrence_paused(1, false)?;
+        let mut task = task_repo.get_task(1)?;
+        task.completed = false;
+        task_repo.persist_task(&task)?;
+        let new_task_id = task_repo.complete_and_recur(1)?.expect("Task should recur");
+        let new_task = task_repo.get_task(new_task_id)?;
+        assert_eq!(new_task.due_date, NaiveDate::from_ymd_opt(2024, 5, 17));
+        assert!(!new_task.recurrence_paused);
+
+        assert!(task_repo.set_recurrence_paused(99, true).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_import_round_trip() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.add_preset("new preset")?;
+        let preset_id = task_repo.get_preset_id_from_preset_name("new preset")?;
+        task_repo.persist_preset_task(PresetTask::new(&LETTERS, 'A', "Preset task", preset_id)?)?;
+        let mut injected_task = Task::new(&LETTERS, Some('A'), "Preset task", None)?;
+        injected_task.source_preset_id = Some(preset_id);
+        task_repo.persist_task(&injected_task)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('B'),
+            "Project task",
+            Some("project"),
+        )?)?;
+
+        task_repo.add_note(2, "Some note")?;
+        task_repo.add_subtask(2, "Some subtask")?;
+        task_repo.add_tag_to_task(2, "some-tag")?;
+        task_repo.add_dependency(2, 1)?;
+        task_repo.hide_task(2)?;
+        task_repo.set_project_color("project", "#ff0000")?;
+        task_repo
+            .conn()?
+            .execute("UPDATE tasks SET created_at = 1000 WHERE id = 2", [])?;
+
+        let bundle = task_repo.export_all()?;
+        assert_eq!(bundle.tasks.len(), 2);
+        assert_eq!(bundle.presets.len(), 1);
+
+        let other_connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut other_task_repo = TaskRepo::new(other_connection_factory);
+        other_task_repo.init_db()?;
+        other_task_repo.add_preset("existing preset")?; // Preset IDs differ between both repos
+        other_task_repo.persist_task(&Task::new(&LETTERS, None, "Existing task", None)?)?; // Task IDs too
+        other_task_repo.import_all(bundle)?;
+
+        // Projects, presets and the links to them are kept
+        let other_bundle = other_task_repo.export_all()?;
+        let imported_task = |description| {
+            other_bundle
+                .tasks
+                .iter()
+                .find(|exported_task| exported_task.task.description == description)
+                .unwrap()
+        };
+        let imported_preset_task = &imported_task("Preset task").task;
+        assert_eq!(
+            other_task_repo.get_source_preset_name(imported_preset_task.id)?,
+            Some("new preset".into())
+        );
+        assert_eq!(other_bundle.presets[1].name, "new preset");
+        assert_eq!(other_bundle.presets[1].tasks[0].description, "Preset task");
+
+        // As are the details of tasks, their dependencies and project colors
+        let imported_task = imported_task("Project task");
+        assert_eq!(imported_task.task.project.as_deref(), Some("project"));
+        assert!(imported_task.task.hidden);
+        assert_eq!(imported_task.task.created_at, 1000);
+        assert_eq!(imported_task.task.subtasks[0].description, "Some subtask");
+        assert_eq!(imported_task.task.tags, vec!["some-tag"]);
+        assert_eq!(imported_task.notes[0].body, "Some note");
+        assert_eq!(imported_task.depends_on, vec![imported_preset_task.id]);
+        assert_eq!(other_bundle.project_colors["project"], "#ff0000");
+
+        // Importing again keeps both copies of presets, the imported ones being renamed
+        other_task_repo.set_project_color("project", "#00ff00")?;
+        other_task_repo.import_all(task_repo.export_all()?)?;
+        let other_bundle = other_task_repo.export_all()?;
+        assert_eq!(other_bundle.tasks.len(), 5);
+        assert_eq!(
+            other_task_repo.get_all_preset_names()?,
+            ["existing preset", "new preset", "new preset (2)"]
+        );
+        // Existing project colors are kept
+        assert_eq!(other_bundle.project_colors["project"], "#00ff00");
+
+        // Invalid details are rejected, nothing being imported then
+        let mut bundle = task_repo.export_all()?;
+        bundle.tasks[1].task.tags.push(" ".into());
+        assert!(matches!(
+            other_task_repo.import_all(bundle),
+            Err(TaskRepoError::Invalid { .. })
+        ));
+        assert_eq!(other_task_repo.export_all()?.tasks.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn todotxt_round_trip() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None)?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Important task",
+            Some("project"),
+        )?)?;
+        let mut completed_task = Task::new(&LETTERS, Some('C'), "Done task", Some("project"))?;
+        completed_task.completed = true;
+        task_repo.persist_task(&completed_task)?;
+
+        let exported = task_repo.export_todotxt()?;
+        assert_eq!(
+            exported,
+            "(A) Important task +project\n(B) Medium task\nx (C) Done task +project"
+        );
+
+        // Reimport everything into a brand new repo
+        let other_connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut other_task_repo = TaskRepo::new(other_connection_factory);
+        other_task_repo.init_db()?;
+
+        assert_eq!(other_task_repo.import_todotxt(&LETTERS, &exported)?, 3);
+        assert_eq!(other_task_repo.export_todotxt()?, exported);
+
+        // Invalid lines are rejected as a whole
+        assert!(
+            other_task_repo
+                .import_todotxt(&LETTERS, "(A) Valid\n(a) Invalid")
+                .is_err()
+        );
+        assert_eq!(
+            other_task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            3
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrations() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Original schema, where tasks had no project
+        task_repo.conn()?.execute_batch(
+            "
+            CREATE TABLE tasks (
+                id INTEGER PRIMARY KEY,
+                priority TEXT NOT NULL,
+                description TEXT NOT NULL,
+                completed INTEGER NOT NULL
+            );
+            INSERT INTO tasks (priority, description, completed) VALUES ('A', 'Old task', FALSE);
+            INSERT INTO tasks (priority, description, completed) VALUES ('B', 'Done task', TRUE);
+            ",
+        )?;
+
+        task_repo.init_db()?;
+
+        // Tasks are kept, out of any project
+        let tasks = task_repo.get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)?;
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].description, "Old task");
+        assert_eq!(tasks[0].project, None);
+        assert!(tasks[1].completed);
+
+        // And can now be moved to one
+        task_repo.set_task_project(1, Some("project"))?;
+        assert_eq!(
+            task_repo.get_all_projects(ProjectMatch::Exact)?,
+            ["project"]
+        );
+
+        // The version reached is recorded, so that migrations are not applied twice
+        assert_eq!(task_repo.run_migrations()?, SCHEMA_VERSION);
+        task_repo.init_db()?;
+        assert_eq!(
+            task_repo
+                .get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)?
+                .len(),
+            2
+        );
+
+        // Databases from more recent versions are not touched
+        task_repo
+            .conn()?
+            .execute("UPDATE schema_version SET version = version + 1", ())?;
+        assert!(task_repo.run_migrations().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unrecorded_schema_version() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Brand new databases need no migration
+        assert_eq!(task_repo.run_migrations()?, SCHEMA_VERSION);
+        task_repo.init_db()?;
+
+        // Neither do databases predating recorded versions, which already have projects
+        task_repo.conn()?.execute("DROP TABLE schema_version", ())?;
+        assert_eq!(task_repo.run_migrations()?, SCHEMA_VERSION);
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Task", Some("project"))?)?;
+        assert_eq!(
+            task_repo.get_all_projects(ProjectMatch::Exact)?,
+            ["project"]
+        );
 
-        let mut stmt = conn.prepare("SELECT id FROM presets WHERE name = :preset_name")?;
-        let mut rows = stmt.query(named_params! {":preset_name" : preset_name})?;
-        let row = rows.next()?.ok_or(TaskRepoError::Error {
-            error: format!("Preset {} not found in storage", preset_name),
-        })?;
-        Ok(row.get(0)?)
+        Ok(())
     }
 
-    pub fn get_preset(&mut self, preset_name: &str) -> Result<Preset, TaskRepoError> {
-        let conn = self.connection_factory.open()?;
+    #[test]
+    fn failed_import_is_rolled_back() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
 
-        // Fetch preset ID
-        let preset_id = self.get_preset_id_from_preset_name(preset_name)?;
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
 
-        // Rebuild PresetTask collection
-        let mut stmt = conn.prepare(
+        // Storage refuses a task in the middle of the batch
+        task_repo.conn()?.execute_batch(
             "
-            SELECT id, preset_id, priority, description FROM preset_tasks
-            WHERE preset_id = :preset_id
+            CREATE TRIGGER refuse_task BEFORE INSERT ON tasks WHEN NEW.description = 'Refused'
+            BEGIN SELECT RAISE(ABORT, 'Refused task'); END;
             ",
         )?;
-        let rows = stmt.query_and_then(
-            named_params! {":preset_id": preset_id},
-            Self::preset_task_from_row,
-        )?;
-        let tasks: Result<Vec<PresetTask>, TaskRepoError> = rows.into_iter().collect();
 
-        // Bind together and return everything
-        Ok(Preset {
-            id: preset_id,
-            name: preset_name.to_string(),
-            tasks: tasks?,
-        })
+        assert!(
+            task_repo
+                .import_todotxt(&LETTERS, "(A) First\n(B) Refused\n(C) Last")
+                .is_err()
+        );
+        assert!(
+            task_repo
+                .get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)?
+                .is_empty()
+        );
+
+        // Same for preset injection
+        task_repo.add_preset("preset")?;
+        let preset_id = task_repo.get_preset_id_from_preset_name("preset")?;
+        task_repo.persist_preset_task(PresetTask::new(&LETTERS, 'A', "First", preset_id)?)?;
+        task_repo.persist_preset_task(PresetTask::new(&LETTERS, 'B', "Refused", preset_id)?)?;
+        assert!(task_repo.inject_preset(&LETTERS, "preset", false).is_err());
+        assert!(
+            task_repo
+                .get_all_tasks(&TaskFilter::default(), SortBy::Priority, None)?
+                .is_empty()
+        );
+
+        // The connection is still usable afterwards
+        assert_eq!(
+            task_repo.import_todotxt(&LETTERS, "(A) First\n(C) Last")?,
+            2
+        );
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
+    #[test]
+    fn csv_export() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
 
-    use crate::sql_connection_factory::tests::TempDirSqliteConnectionFactory;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Buy milk, eggs",
+            Some("house"),
+        )?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Read \"Dune\"", None)?)?;
 
-    use super::*;
+        assert_eq!(
+            task_repo.export_csv()?,
+            "id,priority,description,completed,project\r\n\
+             1,A,\"Buy milk, eggs\",false,house\r\n\
+             2,,\"Read \"\"Dune\"\"\",false,\r\n"
+        );
+
+        Ok(())
+    }
 
     #[test]
-    fn get_all_is_ordered() -> Result<(), TaskRepoError> {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+    fn ical_export() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
         let mut task_repo = TaskRepo::new(connection_factory);
 
         // Has to be called always to initialize schema
         task_repo.init_db()?;
 
-        assert!(task_repo.get_task(-1).is_err());
+        let mut task = Task::new(&LETTERS, Some('A'), "Buy milk, eggs; bread", None)?;
+        task.due_date = NaiveDate::from_ymd_opt(2024, 5, 10);
+        task_repo.persist_task(&task)?;
+        let mut task = Task::new(&LETTERS, Some('E'), "Done task", None)?;
+        task.due_date = NaiveDate::from_ymd_opt(2024, 5, 11);
+        task.completed = true;
+        task_repo.persist_task(&task)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Undated task", None)?)?;
+        let long_description = ["Long task"; 10].join(" ");
+        let mut task = Task::new(&LETTERS, None, &long_description, None)?;
+        task.due_date = NaiveDate::from_ymd_opt(2024, 5, 12);
+        task_repo.persist_task(&task)?;
 
-        task_repo.persist_task(&Task::new('B', "Medium task", None).unwrap())?;
-        task_repo.persist_task(&Task::new('Z', "Unimportant task", None).unwrap())?;
-        task_repo.persist_task(&Task::new('A', "Important task", None).unwrap())?;
-        task_repo.persist_task(&Task::new('A', "Another important task", None).unwrap())?;
+        let ical = task_repo.export_ical(&LETTERS)?;
+        assert!(ical.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ical.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ical.matches("BEGIN:VTODO\r\n").count(), 3);
+        assert!(ical.lines().all(|line| line.len() <= 75));
 
-        let tasks = task_repo.get_all_tasks(None)?;
-        assert_eq!(tasks.len(), 4);
+        // Undated tasks are not exported
+        assert!(!ical.contains("Undated task"));
 
-        // Tasks should be sorted per decreasing priority, then alphabetically
-        let tasks_descriptions: Vec<_> =
-            tasks.iter().map(|task| task.description.clone()).collect();
+        // Tasks come in the order of the task list
+        let vtodos: Vec<&str> = ical.split("BEGIN:VTODO").skip(1).collect();
+        assert!(vtodos[0].contains("SUMMARY:Buy milk\\, eggs\\; bread\r\n"));
+        assert!(vtodos[0].contains("PRIORITY:1\r\n"));
+        assert!(vtodos[0].contains("STATUS:NEEDS-ACTION\r\n"));
+        assert!(vtodos[0].contains("DUE;VALUE=DATE:20240510\r\n"));
+        assert!(!vtodos[1].contains("PRIORITY"));
+        assert!(vtodos[1].replace("\r\n ", "").contains(&long_description));
+        assert!(vtodos[2].contains("SUMMARY:Done task\r\n"));
+        assert!(vtodos[2].contains("PRIORITY:5\r\n"));
+        assert!(vtodos[2].contains("STATUS:COMPLETED\r\n"));
+
+        // Priorities are bucketed according to the scheme
+        let ical = task_repo.export_ical(&PriorityScheme::new('A', 'E')?)?;
+        let vtodos: Vec<&str> = ical.split("BEGIN:VTODO").skip(1).collect();
+        assert!(vtodos[0].contains("PRIORITY:1\r\n"));
+        assert!(vtodos[2].contains("PRIORITY:9\r\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_task_project() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
 
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None)?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('A'),
+            "Other task",
+            Some("project"),
+        )?)?;
+
+        // Move a task into a project
+        task_repo.set_task_project(1, Some("project"))?;
+        assert_eq!(task_repo.get_task(1)?.project, Some("project".into()));
         assert_eq!(
-            tasks_descriptions,
-            vec![
-                "Another important task",
-                "Important task",
-                "Medium task",
-                "Unimportant task"
-            ]
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some("project"),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            2
+        );
+
+        // Move it back out of any project
+        task_repo.set_task_project(1, None)?;
+        assert_eq!(task_repo.get_task(1)?.project, None);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some("project"),
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            1
         );
 
+        // Unknown tasks cannot be moved
+        assert!(matches!(
+            task_repo.set_task_project(42, Some("project")),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
         Ok(())
     }
 
     #[test]
-    fn persisting() -> Result<(), TaskRepoError> {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+    fn project_case_normalization() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
         let mut task_repo = TaskRepo::new(connection_factory);
 
         // Has to be called always to initialize schema
         task_repo.init_db()?;
 
-        task_repo.persist_task(&Task::new('B', "Medium task", None).unwrap())?;
+        // Projects differing only by case or surrounding whitespace are the same one
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 1", Some("Project"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 2", Some("project "))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 3", Some(" "))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 4", None)?)?;
+        task_repo.set_task_project(4, Some("PROJECT"))?;
+        assert_eq!(
+            task_repo.get_all_projects(ProjectMatch::Exact)?,
+            ["Project"]
+        );
+        assert_eq!(task_repo.get_task(2)?.project, Some("Project".into()));
+        assert_eq!(task_repo.get_task(3)?.project, None);
 
-        // Cheating a bit here, we can guess the ID of a task
-        let mut retrieved_task = task_repo.get_task(1)?;
+        // Which also counts when looking for duplicates
+        assert!(matches!(
+            task_repo.add_task_unique(&Task::new(&LETTERS, None, "Task 1", Some("PROJECT"))?),
+            Err(TaskRepoError::Duplicate { .. })
+        ));
 
-        // Should be unchanged
-        assert_eq!(retrieved_task.priority, 'B');
-        assert_eq!(retrieved_task.description, "Medium task");
-        assert!(!retrieved_task.completed);
+        // The spelling can still be changed for the whole project
+        task_repo.rename_project("Project", "project")?;
+        assert_eq!(
+            task_repo.get_all_projects(ProjectMatch::Exact)?,
+            ["project"]
+        );
 
-        // Let's update it
-        retrieved_task.lower_priority();
-        retrieved_task.description = "A new description".into();
-        retrieved_task.completed = true;
+        // Renaming into another project keeps the spelling of the latter
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 5", Some("Other"))?)?;
+        task_repo.rename_project("project", " OTHER ")?;
+        assert_eq!(task_repo.get_all_projects(ProjectMatch::Exact)?, ["Other"]);
 
-        task_repo.persist_task(&retrieved_task)?;
+        // Projects cannot be given a blank name
+        assert!(matches!(
+            task_repo.rename_project("Other", " "),
+            Err(TaskRepoError::Invalid { .. })
+        ));
 
-        // Let's retrieve it again
-        let retrieved_task = task_repo.get_task(1)?;
+        Ok(())
+    }
 
-        // Should have new fields
-        assert_eq!(retrieved_task.priority, 'C');
-        assert_eq!(retrieved_task.description, "A new description");
-        assert!(retrieved_task.completed);
+    #[test]
+    fn summarize_month() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        let march_2024 = NaiveDate::from_ymd_opt(2024, 3, 31)
+            .unwrap()
+            .and_hms_opt(23, 59, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp();
+        let april_2024 = march_2024 + 60;
+        for (description, project, completed_at) in [
+            ("March task", Some("work"), Some(march_2024)),
+            ("Other March task", None, Some(march_2024)),
+            ("April task", Some("work"), Some(april_2024)),
+            ("Pending task", Some("work"), None),
+        ] {
+            let mut task = Task::new(&LETTERS, Some('A'), description, project)?;
+            task.completed = completed_at.is_some();
+            let task_id = task_repo.persist_task(&task)?;
+            task_repo.conn()?.execute(
+                "UPDATE tasks SET completed_at = ? WHERE id = ?",
+                (completed_at, task_id),
+            )?;
+        }
+
+        let expected_summary = MonthlySummary {
+            year: 2024,
+            month: 3,
+            by_project: vec![
+                ProjectCount {
+                    project: Some("work".into()),
+                    count: 1,
+                },
+                ProjectCount {
+                    project: None,
+                    count: 1,
+                },
+            ],
+        };
+        assert_eq!(task_repo.summarize_month(2024, 3)?, expected_summary);
+
+        // Only tasks completed during the month are gone, their counts persisting
+        let descriptions: Vec<String> = task_repo
+            .get_all_tasks(&TaskFilter::default(), SortBy::Description, None)?
+            .into_iter()
+            .map(|task| task.description)
+            .collect();
+        assert_eq!(descriptions, ["Pending task", "April task"]);
+        assert_eq!(task_repo.get_monthly_summary(2024, 3)?, expected_summary);
+        assert_eq!(task_repo.get_monthly_summary(2024, 4)?.by_project, []);
+
+        // Summarizing again changes nothing when no task was completed since
+        assert_eq!(task_repo.summarize_month(2024, 3)?, expected_summary);
+
+        assert!(matches!(
+            task_repo.summarize_month(2024, 13),
+            Err(TaskRepoError::Invalid { .. })
+        ));
+        // The month after the last representable one cannot be computed
+        assert!(matches!(
+            task_repo.summarize_month(NaiveDate::MAX.year(), 12),
+            Err(TaskRepoError::Invalid { .. })
+        ));
 
         Ok(())
     }
 
     #[test]
-    fn cleanup() -> Result<(), TaskRepoError> {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+    fn normalized_project_match() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
         let mut task_repo = TaskRepo::new(connection_factory);
 
         // Has to be called always to initialize schema
         task_repo.init_db()?;
 
-        task_repo.persist_task(&Task::new('C', "Some low importance task", None)?)?;
-
-        // Pending tasks are spared
-        task_repo.cleanup()?;
-        let mut existing_task = task_repo.get_task(1)?;
-        assert_eq!(existing_task.description, "Some low importance task");
+        // Spellings stored before projects were normalized on insertion
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 1", Some("Work"))?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 2", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Task 3", Some("Home"))?)?;
+        task_repo
+            .conn()?
+            .execute("UPDATE tasks SET project = 'wörk' WHERE id = 2", [])?;
 
-        existing_task.completed = true;
-        task_repo.persist_task(&existing_task)?;
+        let filter = |task_repo: &mut TaskRepo, project_match| {
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        project: Some("work"),
+                        project_match,
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None,
+                )
+                .map(|tasks| tasks.iter().map(|task| task.id).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            filter(&mut task_repo, ProjectMatch::Exact)?,
+            Vec::<TaskId>::new()
+        );
+        assert_eq!(filter(&mut task_repo, ProjectMatch::Normalized)?, [1, 2]);
+        assert_eq!(
+            task_repo.count_tasks(&TaskFilter {
+                project: Some("WÖRK"),
+                project_match: ProjectMatch::Normalized,
+                ..Default::default()
+            })?,
+            2
+        );
 
-        // Completed tasks are deleted
-        task_repo.cleanup()?;
-        assert!(task_repo.get_task(1).is_err());
+        // Variants are listed once, with the earliest spelling
+        assert_eq!(
+            task_repo.get_all_projects(ProjectMatch::Exact)?,
+            ["Home", "Work", "wörk"]
+        );
+        assert_eq!(
+            task_repo.get_all_projects(ProjectMatch::Normalized)?,
+            ["Home", "Work"]
+        );
 
         Ok(())
     }
 
     #[test]
-    fn project_handling() -> Result<(), TaskRepoError> {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+    fn inject_preset() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
         let mut task_repo = TaskRepo::new(connection_factory);
 
         // Has to be called always to initialize schema
         task_repo.init_db()?;
 
-        // By default, tasks do not pertain to any project
-        task_repo.persist_task(&Task::new('B', "Medium task", None).unwrap())?;
-        let global_task = task_repo.get_task(1)?;
-        assert_eq!(global_task.project, None);
+        task_repo.add_preset("Moving")?;
+        let preset_id = task_repo.get_preset_id_from_preset_name("Moving")?;
+        task_repo.persist_preset_task(PresetTask::new(&LETTERS, 'A', "Pack boxes", preset_id)?)?;
+        task_repo.persist_preset_task(PresetTask::new(&LETTERS, 'B', "Clean flat", preset_id)?)?;
 
-        let all_projects = task_repo.get_all_projects()?;
-        assert_eq!(all_projects.len(), 0);
+        // First injection adds everything, in the preset's project
+        assert_eq!(task_repo.inject_preset(&LETTERS, "Moving", false)?, 2);
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks.len(), 2);
+        assert!(
+            tasks
+                .iter()
+                .all(|task| task.project.as_deref() == Some("Moving")
+                    && task.source_preset_id == Some(preset_id))
+        );
 
-        // Tasks may have dedicated projects. Projects are created "on-the-fly"
-        task_repo.persist_task(&Task::new('A', "Important task", "project".into()).unwrap())?;
-        let project_task = task_repo.get_task(2)?;
-        assert_eq!(project_task.project, Some("project".into()));
+        // Second injection adds nothing, even if a priority was changed in the meantime
+        task_repo.set_priority(tasks[0].id, &LETTERS, 'C')?;
+        assert_eq!(task_repo.inject_preset(&LETTERS, "Moving", false)?, 0);
 
-        let all_projects = task_repo.get_all_projects()?;
-        assert_eq!(all_projects, ["project"]);
+        // Completed tasks do not count as duplicates
+        let mut task = task_repo.get_task(tasks[0].id)?;
+        task.completed = true;
+        task_repo.persist_task(&task)?;
+        assert_eq!(task_repo.inject_preset(&LETTERS, "Moving", false)?, 1);
 
-        // We can filter per project.
-        let filtered_tasks = task_repo.get_all_tasks(Some("project"))?;
-        assert_eq!(filtered_tasks.len(), 1);
-        assert_eq!(filtered_tasks[0].description, "Important task");
+        // Forcing injects everything again
+        assert_eq!(task_repo.inject_preset(&LETTERS, "Moving", true)?, 2);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            5
+        );
 
-        // We can rename projects
-        task_repo.rename_project("project", "project_2")?;
-        let all_projects = task_repo.get_all_projects()?;
-        assert_eq!(all_projects, ["project_2"]);
-        let filtered_tasks_old_project = task_repo.get_all_tasks(Some("project"))?;
-        assert_eq!(filtered_tasks_old_project.len(), 0);
-        let filtered_tasks_new_project = task_repo.get_all_tasks(Some("project_2"))?;
-        assert_eq!(filtered_tasks_new_project.len(), 1);
-        assert_eq!(filtered_tasks_new_project[0].description, "Important task");
+        // Unknown presets cannot be injected
+        assert!(task_repo.inject_preset(&LETTERS, "Unknown", false).is_err());
 
         Ok(())
     }
 
     #[test]
     fn presets() -> Result<(), TaskRepoError> {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new()?);
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
         let mut task_repo = TaskRepo::new(connection_factory);
 
         // Has to be called always to initialize schema
@@ -502,7 +6049,12 @@ mod tests {
         let preset_id = task_repo.get_preset_id_from_preset_name("new preset")?;
 
         // Add a new preset task
-        task_repo.persist_preset_task(PresetTask::new('A', "some description", preset_id)?)?;
+        task_repo.persist_preset_task(PresetTask::new(
+            &LETTERS,
+            'A',
+            "some description",
+            preset_id,
+        )?)?;
 
         // We should be able to see it now
         let preset = task_repo.get_preset("new preset")?;
@@ -512,10 +6064,325 @@ mod tests {
         assert_eq!(preset_task.priority, 'A');
 
         // No non-preset task should have been added
-        assert_eq!(task_repo.get_all_tasks(None)?.len(), 0);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            0
+        );
+
+        // Injected tasks remember which preset they come from
+        let mut injected_task =
+            Task::new(&LETTERS, Some('A'), "some description", Some("new preset"))?;
+        injected_task.source_preset_id = Some(preset_id);
+        task_repo.persist_task(&injected_task)?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "manual task", None)?)?;
+        assert_eq!(
+            task_repo.get_source_preset_name(1)?,
+            Some("new preset".into())
+        );
+        assert_eq!(task_repo.get_source_preset_name(2)?, None);
+        assert!(matches!(
+            task_repo.get_source_preset_name(42),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        // Presets can be renamed, as long as the new name is not already taken
+        task_repo.add_preset("other preset")?;
+        task_repo.rename_preset("new preset", "renamed preset")?;
+        assert_eq!(
+            task_repo.get_all_preset_names()?,
+            ["other preset", "renamed preset"]
+        );
+        assert_eq!(task_repo.get_preset("renamed preset")?.tasks.len(), 1);
+        assert!(matches!(
+            task_repo.rename_preset("renamed preset", "other preset"),
+            Err(TaskRepoError::Error { .. })
+        ));
+        assert!(
+            task_repo
+                .rename_preset("new preset", "yet another name")
+                .is_err()
+        );
 
         // That's it.
         // Note that preset injection is not implemented here.
         Ok(())
     }
+
+    #[test]
+    fn bulk_preset_tasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.add_preset("new preset")?;
+        let preset_id = task_repo.get_preset_id_from_preset_name("new preset")?;
+
+        let preset_tasks = ["first", "second", "third"]
+            .into_iter()
+            .map(|description| PresetTask::new(&LETTERS, 'B', description, preset_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(task_repo.persist_preset_tasks(preset_tasks)?, 3);
+
+        let preset = task_repo.get_preset("new preset")?;
+        let mut descriptions: Vec<&str> = preset
+            .tasks
+            .iter()
+            .map(|preset_task| preset_task.description.as_str())
+            .collect();
+        descriptions.sort();
+        assert_eq!(descriptions, ["first", "second", "third"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn backup_to() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+        task_repo.init_db()?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Important task", None)?)?;
+
+        let backup_dir = tempfile::tempdir()?;
+        let backup_path = backup_dir.path().join("backup.db");
+        task_repo.backup_to(&backup_path)?;
+
+        // The copy can be opened on its own and contains the tasks
+        let backup_conn = Connection::open(&backup_path)?;
+        let description: String =
+            backup_conn.query_row("SELECT description FROM tasks", [], |row| row.get(0))?;
+        assert_eq!(description, "Important task");
+
+        Ok(())
+    }
+
+    #[test]
+    fn restore() -> Result<(), TaskRepoError> {
+        // Prepare a backup containing a preset and an injected task
+        let backup_dir = tempfile::tempdir()?;
+        let backup_path = backup_dir.path().join("backup.db");
+        {
+            let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+            let mut task_repo = TaskRepo::new(connection_factory);
+            task_repo.init_db()?;
+            task_repo.add_preset("new preset")?;
+            let preset_id = task_repo.get_preset_id_from_preset_name("new preset")?;
+            task_repo.persist_preset_task(PresetTask::new(
+                &LETTERS,
+                'A',
+                "some description",
+                preset_id,
+            )?)?;
+            let mut injected_task =
+                Task::new(&LETTERS, Some('A'), "some description", Some("new preset"))?;
+            injected_task.source_preset_id = Some(preset_id);
+            task_repo.persist_task(&injected_task)?;
+            task_repo.conn()?.execute(
+                "VACUUM INTO ?",
+                [backup_path.to_str().expect("Path should be valid")],
+            )?;
+        }
+
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+        task_repo.init_db()?;
+        task_repo.persist_task(&Task::new(&LETTERS, Some('B'), "Medium task", None)?)?;
+
+        task_repo.restore_from(&backup_path)?;
+
+        // Everything comes from the backup, including links between tasks and presets
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                hidden: Some(false),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "some description");
+        assert_eq!(
+            task_repo.get_source_preset_name(tasks[0].id)?,
+            Some("new preset".into())
+        );
+        assert_eq!(task_repo.get_preset("new preset")?.tasks.len(), 1);
+        let changes = task_repo.get_changes(0, i64::MAX)?;
+        assert_eq!(changes.created, [1]);
+        assert!(changes.deleted.is_empty());
+
+        // Restoring again works as well, with the same connection
+        task_repo.restore_from(&backup_path)?;
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )?
+                .len(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn saved_views() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Global task", None)?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('B'),
+            "Project task",
+            Some("project"),
+        )?)?;
+        // Out of the priority range of the view
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            Some('D'),
+            "Minor project task",
+            Some("project"),
+        )?)?;
+        task_repo.persist_task(&Task::new(
+            &LETTERS,
+            None,
+            "Unprioritized project task",
+            Some("project"),
+        )?)?;
+
+        let filters = TaskListFilters {
+            project: Some("project".into()),
+            highest_priority: Some("A".into()),
+            lowest_priority: Some("C".into()),
+            ..Default::default()
+        };
+        task_repo.save_view("my view", &filters)?;
+        assert_eq!(task_repo.get_all_view_names()?, ["my view"]);
+
+        // Loading the view reproduces the filtered list
+        let view = task_repo.get_view("my view")?;
+        assert_eq!(view, filters);
+        let tasks = task_repo.get_all_tasks(
+            &TaskFilter {
+                project: view.project.as_deref(),
+                hidden: Some(false),
+                highest_priority: view.highest_priority.as_deref(),
+                lowest_priority: view.lowest_priority.as_deref(),
+                ..Default::default()
+            },
+            SortBy::Priority,
+            None,
+        )?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "Project task");
+
+        // Saving under the same name replaces the view
+        task_repo.save_view("my view", &TaskListFilters::default())?;
+        assert_eq!(task_repo.get_view("my view")?, TaskListFilters::default());
+
+        task_repo.delete_view("my view")?;
+        assert!(matches!(
+            task_repo.get_view("my view"),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+        assert!(matches!(
+            task_repo.delete_view("my view"),
+            Err(TaskRepoError::NotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn orphaned_preset_tasks() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory.clone());
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.add_preset("new preset")?;
+        let preset_id = task_repo.get_preset_id_from_preset_name("new preset")?;
+        task_repo.persist_preset_task(PresetTask::new(
+            &LETTERS,
+            'A',
+            "some description",
+            preset_id,
+        )?)?;
+
+        // No orphan yet
+        assert_eq!(task_repo.find_orphaned_preset_tasks()?.len(), 0);
+
+        // Insert an orphan behind the repo's back, as if foreign keys were not enforced
+        let conn = connection_factory.open()?;
+        conn.execute("PRAGMA foreign_keys = OFF", [])?;
+        conn.execute(
+            "INSERT INTO preset_tasks (preset_id, priority, description) VALUES (42, 'B', 'orphan')",
+            [],
+        )?;
+
+        let orphans = task_repo.find_orphaned_preset_tasks()?;
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].description, "orphan");
+        assert_eq!(orphans[0].preset_id, 42);
+
+        // Only the orphan is purged
+        assert_eq!(task_repo.purge_orphaned_preset_tasks()?, 1);
+        assert_eq!(task_repo.find_orphaned_preset_tasks()?.len(), 0);
+        assert_eq!(task_repo.get_preset("new preset")?.tasks.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_all_priorities() -> Result<(), TaskRepoError> {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new()?);
+        let mut task_repo = TaskRepo::new(connection_factory);
+
+        // Has to be called always to initialize schema
+        task_repo.init_db()?;
+
+        task_repo.persist_task(&Task::new(&LETTERS, Some('A'), "Uppercase task", None)?)?;
+        task_repo.persist_task(&Task::new(&LETTERS, None, "Unprioritized task", None)?)?;
+        task_repo.add_preset("new preset")?;
+        let preset_id = task_repo.get_preset_id_from_preset_name("new preset")?;
+        task_repo.persist_preset_task(PresetTask::new(&LETTERS, 'A', "Preset task", preset_id)?)?;
+
+        // Lowercase priorities, as stored by older versions
+        task_repo.conn()?.execute_batch(
+            "
+            INSERT INTO tasks (priority, description, completed, project) VALUES ('b', 'Lowercase task', FALSE, '');
+            UPDATE preset_tasks SET priority = 'c';
+            ",
+        )?;
+
+        assert_eq!(task_repo.normalize_all_priorities()?, 2);
+        assert_eq!(task_repo.get_task(3)?.priority, Some('B'));
+        assert_eq!(task_repo.get_task(1)?.priority, Some('A'));
+        assert_eq!(task_repo.get_task(2)?.priority, None);
+        assert_eq!(task_repo.get_preset("new preset")?.tasks[0].priority, 'C');
+
+        // Nothing left to fix
+        assert_eq!(task_repo.normalize_all_priorities()?, 0);
+
+        Ok(())
+    }
 }
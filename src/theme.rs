@@ -0,0 +1,90 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::task::PriorityBucket;
+
+// Color theme of all pages, chosen at startup for the whole server
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    #[default]
+    Dark,
+}
+
+impl Theme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+        }
+    }
+
+    // CSS variables coloring the high, medium and low priorities. Bootstrap adapts them to the
+    // theme, but their plain versions lack contrast on light backgrounds.
+    fn priority_variables(&self) -> [&'static str; 3] {
+        match self {
+            Self::Light => [
+                "--bs-danger-text-emphasis",
+                "--bs-warning-text-emphasis",
+                "--bs-info-text-emphasis",
+            ],
+            Self::Dark => ["--bs-danger", "--bs-warning", "--bs-info"],
+        }
+    }
+
+    // Inline style coloring a priority of the given bucket. Unprioritized tasks, and priorities
+    // outside of the scheme, are left unstyled.
+    pub fn priority_style(&self, bucket: Option<PriorityBucket>) -> String {
+        let [high, medium, low] = self.priority_variables();
+        let variable = match bucket {
+            Some(PriorityBucket::High) => high,
+            Some(PriorityBucket::Medium) => medium,
+            Some(PriorityBucket::Low) => low,
+            None => return String::new(),
+        };
+        format!("color: var({variable})")
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "light" => Ok(Self::Light),
+            "dark" => Ok(Self::Dark),
+            _ => Err(format!("Unknown theme {s}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_style() {
+        assert_eq!(
+            Theme::Light.priority_style(Some(PriorityBucket::High)),
+            "color: var(--bs-danger-text-emphasis)"
+        );
+        assert_eq!(
+            Theme::Light.priority_style(Some(PriorityBucket::Low)),
+            "color: var(--bs-info-text-emphasis)"
+        );
+        assert_eq!(
+            Theme::Dark.priority_style(Some(PriorityBucket::Medium)),
+            "color: var(--bs-warning)"
+        );
+        assert_eq!(Theme::Dark.priority_style(None), "");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("light".parse(), Ok(Theme::Light));
+        assert_eq!(" dark".parse(), Ok(Theme::Dark));
+        assert!("blue".parse::<Theme>().is_err());
+    }
+}
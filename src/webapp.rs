@@ -1,50 +1,141 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-use crate::presets::PresetTask;
+use crate::backup::{BackupConfig, backup_database};
+use crate::note::Note;
+use crate::note::NoteId;
+use crate::presets::{Preset, PresetId, PresetTask};
+use crate::rate_limit::RateLimiter;
+use crate::saved_view::TaskListFilters;
 use crate::sql_connection_factory::SqlConnectionFactory;
+use crate::stats::MonthlySummary;
+use crate::task::PriorityScheme;
+use crate::task::Recurrence;
+use crate::task::Subtask;
+use crate::task::SubtaskId;
 use crate::task::Task;
 use crate::task::TaskError;
 use crate::task::TaskId;
+use crate::task::normalize_project;
+use crate::task::validate_description;
 
-use crate::task_repo::{TaskRepo, TaskRepoError};
-use axum::body::Body;
+use crate::task_repo::{
+    CleanedUpTasks, ExportBundle, ExportedTask, Page, ProjectMatch, SortBy, SqlErrorKind,
+    TaskFilter, TaskRepo, TaskRepoError,
+};
+use crate::theme::Theme;
+use axum::body::{Body, Bytes};
+use axum::extract::ConnectInfo;
+use axum::extract::DefaultBodyLimit;
 use axum::extract::Query;
+use axum::extract::Request;
 use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::Method;
 use axum::http::Response;
 use axum::http::StatusCode;
+use axum::http::header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::middleware::{self, Next};
 use axum::{
-    Form, Router,
+    Form, Json, Router,
     extract::Path,
-    response::{Html, IntoResponse, Redirect, Result},
+    response::{
+        Html, IntoResponse, Redirect, Result,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{get, post},
 };
-use minijinja::value::ViaDeserialize;
-use minijinja::{Environment, context, path_loader};
-use serde::{Deserialize, Serialize};
+use axum_extra::extract::Form as RepeatedFieldsForm; // Unlike axum's Form, supports repeated fields
+use chrono::{DateTime, NaiveDate, Weekday};
+use futures_util::stream::{self, Stream};
+use minijinja::value::{Value, ViaDeserialize};
+use minijinja::{AutoEscape, Environment, context, path_loader};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use pulldown_cmark::{Event as MarkdownEvent, Parser, Tag, TagEnd};
+use serde::{Deserialize, Deserializer, Serialize, de};
+use tempfile::NamedTempFile;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 use tower_http::trace::TraceLayer;
 
 impl IntoResponse for TaskRepoError {
     fn into_response(self) -> Response<Body> {
-        let body = match self {
-            Self::Error { error } => error,
-            Self::SqlError { original_error } => original_error.to_string(),
-            Self::IoError { original_error } => original_error.to_string(),
-            Self::JinjaError { original_error } => original_error.to_string(),
-            Self::TaskError { original_error } => original_error.to_string(),
-            Self::PresetTaskError { original_error } => original_error.to_string(),
+        let sql_error_kind = self.sql_error_kind();
+        // Logged in full, whatever is shown to the client
+        let details = self.to_string();
+        let (status, body) = match self {
+            Self::Error { error } => (StatusCode::INTERNAL_SERVER_ERROR, error),
+            Self::NotFound { what } => (
+                StatusCode::NOT_FOUND,
+                format!("{} not found in storage", what),
+            ),
+            Self::Duplicate { what } => (StatusCode::CONFLICT, format!("{} already exists", what)),
+            Self::Invalid { reason } => (StatusCode::BAD_REQUEST, reason),
+            // Tasks and preset tasks are validated on input, so their errors come from the request
+            Self::TaskError { original_error } => {
+                (StatusCode::BAD_REQUEST, original_error.to_string())
+            }
+            Self::PresetTaskError { original_error } => {
+                (StatusCode::BAD_REQUEST, original_error.to_string())
+            }
+            // These may leak internals, so they are only detailed in logs
+            Self::SqlError { .. } => match sql_error_kind {
+                Some(SqlErrorKind::ConstraintViolation) => (
+                    StatusCode::CONFLICT,
+                    "Conflicting with existing data".to_string(),
+                ),
+                Some(SqlErrorKind::Busy) => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Storage is busy, please retry later".to_string(),
+                ),
+                Some(SqlErrorKind::NotFound) => {
+                    (StatusCode::NOT_FOUND, "Not found in storage".to_string())
+                }
+                None => internal_error(),
+            },
+            Self::IoError { .. } | Self::JinjaError { .. } | Self::JsonError { .. } => {
+                internal_error()
+            }
         };
 
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        // Client errors are part of normal operation, unlike server ones
+        if status.is_server_error() {
+            tracing::error!("Request failed with status {}: {}", status, details);
+        } else {
+            tracing::warn!("Request failed with status {}: {}", status, details);
+        }
+
+        (status, body).into_response()
     }
 }
 
+fn internal_error() -> (StatusCode, String) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Internal server error".to_string(),
+    )
+}
+
 impl IntoResponse for TaskError {
     fn into_response(self) -> Response<Body> {
         let body = match self {
             Self::PriorityNotInRangeError(c) => format!("Priority {} not in expected range", c),
+            Self::TodoTxtParseError(line) => format!("Line \"{}\" is not valid todo.txt", line),
+            Self::InvalidPriorityScheme(scheme) => {
+                format!("Priority scheme \"{}\" is invalid", scheme)
+            }
+            Self::InvalidRecurrence(recurrence) => {
+                format!("Recurrence \"{}\" is invalid", recurrence)
+            }
+            Self::PriorityNotAssignable(c) => format!("Priority {} cannot be assigned", c),
+            Self::EmptyDescription | Self::DescriptionTooLong(_) => self.to_string(),
         };
 
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        (StatusCode::BAD_REQUEST, body).into_response()
     }
 }
 
@@ -59,34 +150,316 @@ impl From<minijinja::Error> for TaskRepoError {
 #[derive(Clone)]
 pub struct AppState {
     pub connection_factory: Arc<dyn SqlConnectionFactory>,
+    pub admin_token: Option<String>, // Admin endpoints are disabled if None
+    pub cleanup_keep_min: Option<usize>, // Completed tasks spared by each cleanup
+    pub default_due_offset_days: Option<u32>, // New tasks are due that many days later by default
+    pub priority_scheme: PriorityScheme,
+    pub presets_enabled: bool,
+    pub backup_config: Option<BackupConfig>, // Manual backups are disabled if None
+    pub rate_limiter: Option<Arc<RateLimiter>>, // Mutating requests are unlimited if None
+    pub last_cleanup: Arc<Mutex<Option<CleanedUpTasks>>>, // Rows deleted by the last cleanup, if any
+    pub task_list_updates: broadcast::Sender<String>, // Paths of the requests that changed tasks
+    pub theme: Theme,
+}
+
+impl AppState {
+    pub fn new(connection_factory: Arc<dyn SqlConnectionFactory>) -> AppState {
+        AppState {
+            connection_factory,
+            admin_token: None,
+            cleanup_keep_min: None,
+            default_due_offset_days: None,
+            priority_scheme: PriorityScheme::default(),
+            presets_enabled: true,
+            backup_config: None,
+            rate_limiter: None,
+            last_cleanup: Arc::new(Mutex::new(None)),
+            task_list_updates: broadcast::channel(LIVE_UPDATES_CAPACITY).0,
+            theme: Theme::default(),
+        }
+    }
 }
 
+// Live updates only tell clients to refetch, so that slow ones may safely miss some of them
+const LIVE_UPDATES_CAPACITY: usize = 16;
+
+// Uploaded backups may be much larger than regular forms
+const MAX_BACKUP_SIZE: usize = 100 * 1024 * 1024;
+
+// Project filter selecting the tasks outside of any project
+const NO_PROJECT_FILTER: &str = "__none__";
+
 pub fn build_app(state: AppState) -> Router {
+    let mut admin_routes = Router::new()
+        .route(
+            "/admin/restore",
+            post(restore).layer(DefaultBodyLimit::max(MAX_BACKUP_SIZE)),
+        )
+        .route("/admin/orphans", get(get_orphans))
+        .route("/admin/purge-orphans", post(purge_orphans))
+        .route("/admin/summarize-month", post(summarize_month))
+        .route("/admin/normalize-priorities", post(normalize_priorities))
+        .route("/admin/schema", get(get_schema));
+    // Backups are written to the configured directory only
+    if state.backup_config.is_some() {
+        admin_routes = admin_routes.route("/admin/backup", post(backup));
+    }
+    let admin_routes = admin_routes.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        require_admin_token,
+    ));
+
+    // Preset routes do not exist at all if disabled
+    let preset_routes = match state.presets_enabled {
+        true => Router::new()
+            .route("/preset", post(add_new_preset))
+            .route("/preset/{preset_name}", get(get_preset))
+            .route(
+                "/preset/{preset_name}/add-new-preset-task",
+                post(add_new_preset_task),
+            )
+            .route(
+                "/preset/{preset_name}/add-many",
+                post(add_many_preset_tasks),
+            )
+            .route("/preset/{preset_name}/inject", post(inject_preset))
+            .route("/preset/{preset_name}/rename", post(rename_preset))
+            .route("/task/{task_id}/source-preset", get(get_source_preset)),
+        false => Router::new(),
+    };
+
     Router::new()
         // Home page
         .route("/", get(root))
+        .route("/report", get(get_report))
+        // Health check
+        .route("/healthz", get(healthz))
+        // Live updates of the task list
+        .route("/events", get(live_updates))
+        .route("/ws", get(live_updates_ws))
         // Basic task handling
         .route("/add-new-task", post(add_new_task))
         .route("/flag-pending/{task_id}", post(flag_pending))
         .route("/flag-completed/{task_id}", post(flag_completed))
+        .route("/set-completed/{task_id}", post(set_completed))
         .route("/increase-priority/{task_id}", post(increase_priority))
         .route("/lower-priority/{task_id}", post(lower_priority))
+        .route("/duplicate-task/{task_id}", post(duplicate_task))
+        .route("/set-priority/{task_id}", post(set_priority))
         .route("/update-description/{task_id}", post(update_description))
+        .route("/set-project/{task_id}", post(set_project))
+        .route("/move-up/{task_id}", post(move_up))
+        .route("/move-down/{task_id}", post(move_down))
+        .route("/reorder/{task_id}", post(reorder))
+        .route("/task/{task_id}", get(get_task_details))
+        .route("/task/{task_id}/add-note", post(add_note))
+        .route("/task/{task_id}/delete-note/{note_id}", post(delete_note))
+        .route("/task/{task_id}/add-subtask", post(add_subtask))
+        .route("/task/{task_id}/add-tag", post(add_tag))
+        .route("/task/{task_id}/set-recurrence", post(set_recurrence))
+        .route("/task/{task_id}/pause-recurrence", post(pause_recurrence))
+        .route("/task/{task_id}/resume-recurrence", post(resume_recurrence))
+        .route("/task/{task_id}/remove-tag/{tag}", post(remove_tag))
+        .route("/task/{task_id}/add-dependency", post(add_dependency))
+        .route(
+            "/task/{task_id}/remove-dependency/{depends_on_id}",
+            post(remove_dependency),
+        )
+        .route("/subtask/{subtask_id}/toggle", post(toggle_subtask))
+        .route("/undated", get(get_undated_tasks))
+        .route("/neglected", get(get_neglected_tasks))
+        .route("/priority/{priority}", get(get_tasks_by_priority))
+        .route("/stats", get(get_stats))
+        .route("/api/changes", get(get_changes))
+        .route("/api/cycle-time", get(get_cycle_time))
+        .route("/api/completed", get(get_completed))
+        .route("/api/project-progress", get(get_project_progress))
+        .route("/api/board-counts", get(get_board_counts))
+        .route("/api/project-task-counts", get(get_project_task_counts))
+        .route("/api/suggest", get(get_description_suggestions))
+        // Saved views
+        .route("/view", post(save_view))
+        .route("/view/{view_name}", get(get_view))
+        .route("/view/{view_name}/delete", post(delete_view))
         // Advanced manipulation
         .route("/task-cleanup", post(task_cleanup))
+        .route("/undo-cleanup", post(undo_cleanup))
+        .route("/reset-overdue-to-today", post(reset_overdue_to_today))
+        .route("/archive-completed", post(archive_completed))
+        .route("/archive", get(get_archive))
+        .route("/missed", get(get_missed))
+        .route("/archive/{archived_task_id}/restore", post(restore_task))
+        .route("/hide/{task_id}", post(hide_task))
+        .route("/unhide/{task_id}", post(unhide_task))
+        .route("/bulk-complete", post(bulk_complete))
+        .route("/bulk-delete", post(bulk_delete))
         .route("/rename-project", post(rename_project))
-        // Presets
-        .route("/preset", post(add_new_preset))
-        .route("/preset/{preset_name}", get(get_preset))
+        .route("/merge-projects", post(merge_projects))
+        .route("/set-project-color", post(set_project_color))
+        .route("/delete-project", post(delete_project))
         .route(
-            "/preset/{preset_name}/add-new-preset-task",
-            post(add_new_preset_task),
+            "/project/{project}/complete-all",
+            post(complete_all_in_project),
         )
-        .route("/preset/{preset_name}/inject", post(inject_preset))
+        .route("/complete-project", post(complete_project)) // Same, for forms
+        .route("/reopen-all", post(reopen_all))
+        .route("/project/{project}/delete-all", post(delete_all_in_project))
+        .route("/project/{project}/clear", post(clear_project))
+        // Import/export
+        .route("/export/todotxt", get(export_todotxt))
+        .route("/import/todotxt", post(import_todotxt))
+        .route("/export/todo.txt", get(export_todotxt)) // Named after the usual file name
+        .route("/import/todo.txt", post(import_todotxt))
+        .route("/api/import", post(import_json))
+        .route("/export.json", get(export_all))
+        .route("/import.json", post(import_all))
+        .route("/export/csv", get(export_csv))
+        .route("/export/ical", get(export_ical))
+        // Presets
+        .merge(preset_routes)
+        // Administration
+        .merge(admin_routes)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            publish_task_list_updates,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            limit_mutating_requests,
+        ))
         .with_state(state)
         .layer(TraceLayer::new_for_http())
 }
 
+// Expects the admin token as an "Authorization: Bearer <token>" header
+async fn require_admin_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response<Body> {
+    let Some(admin_token) = &state.admin_token else {
+        return (StatusCode::FORBIDDEN, "Admin endpoints are disabled").into_response();
+    };
+
+    let provided_token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !provided_token
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), admin_token.as_bytes()))
+    {
+        return (StatusCode::UNAUTHORIZED, "Invalid admin token").into_response();
+    }
+
+    next.run(request).await
+}
+
+// Takes as long whatever the first differing byte, so that timing does not leak secrets. Only their
+// length may leak.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// Like for live updates, any request but GET ones counts as mutating. Clients are told apart by
+// their IP address, when known.
+async fn limit_mutating_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response<Body> {
+    if let Some(rate_limiter) = &state.rate_limiter
+        && request.method() != Method::GET
+        && request.method() != Method::HEAD
+    {
+        let client = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip());
+        if !rate_limiter.allow(client) {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many requests, please retry later",
+            )
+                .into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+// Any successful request but GET ones may have changed tasks, which is told to live update
+// subscribers
+async fn publish_task_list_updates(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response<Body> {
+    let path = (request.method() != Method::GET && request.method() != Method::HEAD)
+        .then(|| request.uri().path().to_string());
+
+    let response = next.run(request).await;
+    if let Some(path) = path
+        && (response.status().is_success() || response.status().is_redirection())
+    {
+        // Only fails if no one is subscribed, which is fine
+        let _ = state.task_list_updates.send(path);
+    }
+
+    response
+}
+
+// Server-sent "tasks-changed" events, with the path of the request behind as data
+async fn live_updates(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.task_list_updates.subscribe();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let path = match receiver.recv().await {
+            Ok(path) => path,
+            Err(RecvError::Lagged(_)) => "".into(), // Missed updates are as good as a single one
+            Err(RecvError::Closed) => return None,
+        };
+        let event = Event::default().event("tasks-changed").data(path);
+        Some((Ok(event), receiver))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+// Same updates as `live_updates`, for WebSocket clients: one text message per change, holding the
+// path of the request behind
+async fn live_updates_ws(
+    State(state): State<AppState>,
+    upgrade: WebSocketUpgrade,
+) -> Response<Body> {
+    let receiver = state.task_list_updates.subscribe();
+    upgrade.on_upgrade(|socket| forward_live_updates(socket, receiver))
+}
+
+async fn forward_live_updates(mut socket: WebSocket, mut receiver: broadcast::Receiver<String>) {
+    loop {
+        tokio::select! {
+            update = receiver.recv() => {
+                let path = match update {
+                    Ok(path) => path,
+                    Err(RecvError::Lagged(_)) => "".into(), // As for server-sent events
+                    Err(RecvError::Closed) => return,
+                };
+                if socket.send(Message::text(path)).await.is_err() {
+                    return;
+                }
+            }
+            // Clients are not expected to send anything, so this only tells when they leave
+            message = socket.recv() => {
+                if let None | Some(Err(_)) | Some(Ok(Message::Close(_))) = message {
+                    return;
+                }
+            }
+        }
+    }
+}
+
 // Fixes printing of projects in the UI.
 fn projectify(project: ViaDeserialize<Option<String>>) -> String {
     match project.as_deref() {
@@ -95,39 +468,352 @@ fn projectify(project: ViaDeserialize<Option<String>>) -> String {
     }
 }
 
-fn render<S: Serialize>(template: &str, context: S) -> Result<Html<String>, TaskRepoError> {
+// Renders Unix timestamps in a human-readable way.
+fn datetime(timestamp: i64) -> String {
+    match DateTime::from_timestamp(timestamp, 0) {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M").to_string(),
+        None => "".into(),
+    }
+}
+
+// Only these links are kept, so that descriptions cannot smuggle scripts through e.g. "javascript:"
+fn is_safe_url(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            ["http", "https", "mailto"].contains(&scheme.to_ascii_lowercase().as_str())
+        }
+        _ => true, // Relative URL
+    }
+}
+
+// Renders Markdown descriptions inline, i.e. without wrapping paragraphs. Descriptions being user
+// input, raw HTML is escaped rather than rendered, and unsafe links lead nowhere.
+fn markdownify(description: &str) -> Value {
+    let events = Parser::new(description).filter_map(|event| match event {
+        MarkdownEvent::Start(Tag::Paragraph) | MarkdownEvent::End(TagEnd::Paragraph) => None,
+        MarkdownEvent::Html(html) | MarkdownEvent::InlineHtml(html) => {
+            Some(MarkdownEvent::Text(html))
+        }
+        MarkdownEvent::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Some(MarkdownEvent::Start(Tag::Link {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        })),
+        MarkdownEvent::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Some(MarkdownEvent::Start(Tag::Image {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        })),
+        event => Some(event),
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    Value::from_safe_string(html)
+}
+
+// Whether the description uses any Markdown formatting, that plain text would not show
+fn is_markdown(description: &str) -> bool {
+    Parser::new(description).any(|event| match event {
+        MarkdownEvent::Start(tag) => tag != Tag::Paragraph,
+        MarkdownEvent::Code(_) => true,
+        _ => false,
+    })
+}
+
+// Pages are styled after the theme and the priority scheme of the server
+fn render<S: Serialize>(
+    state: &AppState,
+    template: &str,
+    context: S,
+) -> Result<Html<String>, TaskRepoError> {
     let mut env = Environment::new();
     env.set_loader(path_loader("assets"));
+    // All templates produce HTML, whatever their file name
+    env.set_auto_escape_callback(|_| AutoEscape::Html);
     env.add_filter("projectify", projectify);
+    env.add_filter("datetime", datetime);
+    env.add_filter("markdownify", markdownify);
+    env.add_test("markdown", is_markdown);
+    env.add_global("theme", state.theme.as_str());
+    let priority_scheme = state.priority_scheme;
+    env.add_filter("priority_class", move |priority: Option<char>| {
+        priority
+            .and_then(|priority| priority_scheme.bucket(priority))
+            .map_or("", |bucket| bucket.class())
+    });
+    let theme = state.theme;
+    env.add_filter("priority_style", move |priority: Option<char>| {
+        theme.priority_style(priority.and_then(|priority| priority_scheme.bucket(priority)))
+    });
     let template = env.get_template(template)?;
     Ok(Html(template.render(context)?))
 }
 
-#[derive(Deserialize)]
-struct ProjectSelect {
-    project: Option<String>,
+// Exercises the real connection path, without relying on any table
+async fn healthz(State(state): State<AppState>) -> Response<Body> {
+    let check = || -> Result<(), rusqlite::Error> {
+        let conn = state.connection_factory.open()?;
+        conn.query_row("SELECT 1", [], |_| Ok(()))
+    };
+
+    match check() {
+        Ok(()) => (StatusCode::OK, "ok".to_string()).into_response(),
+        Err(error) => (StatusCode::SERVICE_UNAVAILABLE, error.to_string()).into_response(),
+    }
+}
+
+// Tasks shown by page of the main page, unless requested otherwise
+const DEFAULT_TASKS_PER_PAGE: usize = 50;
+// Larger pages are cut down to this size
+const MAX_TASKS_PER_PAGE: usize = 1000;
+
+// Unlike filters, not saved along with views
+#[derive(Deserialize, Default)]
+struct Pagination {
+    page: Option<usize>, // Starting from 1
+    per_page: Option<usize>,
 }
 
 async fn root(
     State(state): State<AppState>,
-    Query(project): Query<ProjectSelect>,
+    Query(filters): Query<TaskListFilters>,
+    Query(pagination): Query<Pagination>,
 ) -> Result<Html<String>, TaskRepoError> {
-    let mut task_repo = TaskRepo::new(state.connection_factory);
-    let all_tasks = task_repo.get_all_tasks(project.project.as_deref())?;
-    let all_projects = task_repo.get_all_projects()?;
-    let all_preset_names = task_repo.get_all_preset_names()?;
+    render_task_list(state, filters, pagination)
+}
+
+// How the task list filters are understood, by the main page as well as by reports
+struct TaskListSelection<'a> {
+    current_show: &'static str,
+    completed_filter: Option<bool>,
+    current_sort: &'static str,
+    sort_by: SortBy,
+    hidden_view: bool,
+    project_filter: Option<&'a str>,
+    highest_priority: Option<&'a str>,
+    lowest_priority: Option<&'a str>,
+}
+
+impl<'a> TaskListSelection<'a> {
+    fn new(filters: &'a TaskListFilters) -> Self {
+        // Only pending tasks are shown by default, unknown values falling back to it
+        let (current_show, completed_filter) = match filters.show.as_deref() {
+            Some("all") => ("all", None),
+            Some("completed") => ("completed", Some(true)),
+            _ => ("pending", Some(false)),
+        };
+
+        // Tasks are sorted by priority by default, unknown values falling back to it
+        let (current_sort, sort_by) = match filters.sort.as_deref() {
+            Some("description") => ("description", SortBy::Description),
+            Some("created") => ("created", SortBy::Created),
+            Some("due_date") => ("due_date", SortBy::DueDate),
+            _ => ("priority", SortBy::Priority),
+        };
+
+        // Hidden tasks are left out, unless only them are asked for. Views saved while they were
+        // said to be archived still show them.
+        let hidden_view = matches!(filters.view.as_deref(), Some("hidden" | "archived"));
+
+        // Tasks outside of any project are stored with an empty project
+        let project_filter = match filters.project.as_deref() {
+            Some(NO_PROJECT_FILTER) => Some(""),
+            project_filter => project_filter,
+        };
+
+        TaskListSelection {
+            current_show,
+            completed_filter,
+            current_sort,
+            sort_by,
+            hidden_view,
+            project_filter,
+            highest_priority: filters.highest_priority.as_deref(),
+            lowest_priority: filters.lowest_priority.as_deref(),
+        }
+    }
+
+    // Filter selecting the tasks of the list, project names being matched loosely
+    fn task_filter(&self, tag: Option<&'a str>) -> TaskFilter<'a> {
+        TaskFilter {
+            project: self.project_filter,
+            project_match: ProjectMatch::Normalized,
+            completed: self.completed_filter,
+            tag,
+            hidden: Some(self.hidden_view),
+            highest_priority: self.highest_priority,
+            lowest_priority: self.lowest_priority,
+        }
+    }
+
+    // Human-readable summary, e.g. `Pending tasks in project "work", sorted by priority`
+    fn describe(&self, tag: Option<&str>) -> String {
+        let show = match self.current_show {
+            "all" => "All",
+            "completed" => "Completed",
+            _ => "Pending",
+        };
+        let hidden = if self.hidden_view { "hidden " } else { "" };
+        let mut description = format!("{show} {hidden}tasks");
+
+        match self.project_filter {
+            Some("") => description.push_str(" outside of any project"),
+            Some(project) => description.push_str(&format!(" in project \"{project}\"")),
+            None => {}
+        }
+        if let Some(tag) = tag {
+            description.push_str(&format!(" tagged \"{tag}\""));
+        }
+        match (self.highest_priority, self.lowest_priority) {
+            (Some(highest), Some(lowest)) => {
+                description.push_str(&format!(" of priority {highest} to {lowest}"))
+            }
+            (Some(highest), None) => {
+                description.push_str(&format!(" of priority {highest} or above"))
+            }
+            (None, Some(lowest)) => {
+                description.push_str(&format!(" of priority {lowest} or below"))
+            }
+            (None, None) => {}
+        }
+
+        let sort = match self.sort_by {
+            SortBy::Priority => "priority",
+            SortBy::Description => "description",
+            SortBy::Created => "creation date",
+            SortBy::DueDate => "due date",
+        };
+        description.push_str(&format!(", sorted by {sort}"));
+        description
+    }
+}
+
+// Main page, showing the task list as selected by the filters
+fn render_task_list(
+    state: AppState,
+    filters: TaskListFilters,
+    pagination: Pagination,
+) -> Result<Html<String>, TaskRepoError> {
+    let selection = TaskListSelection::new(&filters);
+    let task_filter = selection.task_filter(filters.tag.as_deref());
+    let TaskListSelection {
+        current_show,
+        current_sort,
+        sort_by,
+        hidden_view,
+        ..
+    } = selection;
+
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+    let task_count = task_repo.count_tasks(&task_filter)?;
+
+    // Pages out of range fall back to the closest one
+    let per_page = pagination
+        .per_page
+        .filter(|per_page| *per_page > 0)
+        .unwrap_or(DEFAULT_TASKS_PER_PAGE)
+        .min(MAX_TASKS_PER_PAGE);
+    let page_count = task_count.div_ceil(per_page).max(1);
+    let current_page = pagination.page.unwrap_or(1).clamp(1, page_count);
+
+    let all_tasks = task_repo.get_all_tasks(
+        &task_filter,
+        sort_by,
+        Some(Page {
+            limit: per_page,
+            offset: (current_page - 1) * per_page,
+        }),
+    )?;
+    let all_projects = task_repo.get_all_projects(ProjectMatch::Normalized)?;
+    let project_stats = task_repo.get_project_stats()?;
+    let project_colors = task_repo.get_project_colors()?;
+    let pending_counts: HashMap<_, _> = project_stats
+        .iter()
+        .map(|(project, pending, _)| (project, pending))
+        .collect();
+    let total_pending = task_repo.get_stats()?.pending;
+    let default_due_date = default_due_date(state.default_due_offset_days);
+    let all_preset_names = match state.presets_enabled {
+        true => task_repo.get_all_preset_names()?,
+        false => vec![],
+    };
+    let all_view_names = task_repo.get_all_view_names()?;
 
     render(
+        &state,
         "index.html.j2",
-        context! { tasks => all_tasks, projects => all_projects, current_project => project.project, preset_names => all_preset_names },
+        context! { tasks => all_tasks, projects => all_projects, project_stats => project_stats, pending_counts => pending_counts, project_colors => project_colors, total_pending => total_pending, current_project => filters.project, no_project => NO_PROJECT_FILTER, current_show => current_show, current_tag => filters.tag, current_highest_priority => filters.highest_priority, current_lowest_priority => filters.lowest_priority, current_sort => current_sort, hidden_view => hidden_view, current_page => current_page, page_count => page_count, per_page => per_page, task_count => task_count, preset_names => all_preset_names, presets_enabled => state.presets_enabled, view_names => all_view_names, priority_scheme => state.priority_scheme, default_due_date => default_due_date },
+    )
+}
+
+// Printable version of the task list as selected by the filters, every task on a single page
+async fn get_report(
+    State(state): State<AppState>,
+    Query(filters): Query<TaskListFilters>,
+) -> Result<Html<String>, TaskRepoError> {
+    let selection = TaskListSelection::new(&filters);
+
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+    let tasks = task_repo.get_all_tasks(
+        &selection.task_filter(filters.tag.as_deref()),
+        selection.sort_by,
+        None,
+    )?;
+    let generated_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+
+    render(
+        &state,
+        "report.html.j2",
+        context! { tasks => tasks, filter_description => selection.describe(filters.tag.as_deref()), generated_at => generated_at },
     )
 }
 
+// Allows optional form fields to be submitted empty.
+fn empty_string_as_none<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let opt = Option::<String>::deserialize(de)?;
+    match opt.as_deref() {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse::<T>().map_err(de::Error::custom).map(Some),
+    }
+}
+
+// Tells apart missing form fields (outer None) from fields submitted empty (inner None).
+fn present_empty_string_as_none<'de, D, T>(de: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    empty_string_as_none(de).map(Some)
+}
+
 #[derive(Deserialize)]
 struct AddNewTaskInput {
-    priority: char,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    priority: Option<char>,
     description: String,
     project: Option<String>,
+    #[serde(default, deserialize_with = "present_empty_string_as_none")]
+    due_date: Option<Option<NaiveDate>>, // Submitting it empty opts out of the default due date
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    recurrence: Option<Recurrence>,
 }
 
 async fn add_new_task(
@@ -136,62 +822,140 @@ async fn add_new_task(
 ) -> Result<Redirect> {
     let mut task_repo = TaskRepo::new(state.connection_factory);
 
-    let task = Task::new(task.priority, &task.description, task.project.as_deref())?;
-    task_repo.persist_task(&task)?;
+    let mut new_task = Task::new(
+        &state.priority_scheme,
+        task.priority,
+        &task.description,
+        task.project.as_deref(),
+    )?;
+    new_task.due_date = match task.due_date {
+        Some(due_date) => due_date,
+        None => default_due_date(state.default_due_offset_days),
+    };
+    new_task.recurrence = task.recurrence;
+    task_repo.add_task_unique(&new_task)?;
 
     Ok(Redirect::to("/"))
 }
 
+fn default_due_date(default_due_offset_days: Option<u32>) -> Option<NaiveDate> {
+    default_due_offset_days
+        .map(|offset| chrono::Local::now().date_naive() + chrono::Days::new(offset.into()))
+}
+
+// Leaves the task untouched, and returns no content, if it already has the requested state.
+// Completing a recurring task also returns the row of its next occurrence.
+fn set_task_completed(
+    state: AppState,
+    task_id: TaskId,
+    completed: bool,
+) -> Result<Response<Body>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+
+    let mut task = task_repo.get_task(task_id)?;
+    if task.completed == completed {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    }
+    if !completed {
+        task.completed = completed;
+        task_repo.persist_task(&task)?;
+        return Ok(render(&state, "task_row.html.j2", context! { task => task })?.into_response());
+    }
+
+    let recurring_task_id = task_repo.complete_and_recur(task_id)?;
+    let Html(mut rows) = render(
+        &state,
+        "task_row.html.j2",
+        context! { task => task_repo.get_task(task_id)? },
+    )?;
+    if let Some(recurring_task_id) = recurring_task_id {
+        let Html(recurring_row) = render(
+            &state,
+            "task_row.html.j2",
+            context! { task => task_repo.get_task(recurring_task_id)? },
+        )?;
+        rows.push_str(&recurring_row);
+    }
+
+    Ok(Html(rows).into_response())
+}
+
+#[derive(Deserialize)]
+struct SetCompletedInput {
+    completed: bool,
+}
+
+async fn set_completed(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<SetCompletedInput>,
+) -> Result<Response<Body>, TaskRepoError> {
+    set_task_completed(state, task_id, input.completed)
+}
+
 async fn flag_completed(
     State(state): State<AppState>,
     Path(task_id): Path<TaskId>,
+) -> Result<Response<Body>, TaskRepoError> {
+    set_task_completed(state, task_id, true)
+}
+
+async fn flag_pending(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Response<Body>, TaskRepoError> {
+    set_task_completed(state, task_id, false)
+}
+
+async fn increase_priority(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
 ) -> Result<Html<String>, TaskRepoError> {
-    let mut task_repo = TaskRepo::new(state.connection_factory);
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
 
-    let mut task = task_repo.get_task(task_id)?;
-    task.completed = true;
-    task_repo.persist_task(&task)?;
+    let task = task_repo.shift_priority(task_id, &state.priority_scheme, -1)?;
 
-    render("task_row.html.j2", context! { task => task })
+    render(&state, "task_row.html.j2", context! { task => task })
 }
 
-async fn flag_pending(
+async fn lower_priority(
     State(state): State<AppState>,
     Path(task_id): Path<TaskId>,
 ) -> Result<Html<String>, TaskRepoError> {
-    let mut task_repo = TaskRepo::new(state.connection_factory);
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
 
-    let mut task = task_repo.get_task(task_id)?;
-    task.completed = false;
-    task_repo.persist_task(&task)?;
+    let task = task_repo.shift_priority(task_id, &state.priority_scheme, 1)?;
 
-    render("task_row.html.j2", context! { task => task })
+    render(&state, "task_row.html.j2", context! { task => task })
 }
 
-async fn increase_priority(
+async fn duplicate_task(
     State(state): State<AppState>,
     Path(task_id): Path<TaskId>,
 ) -> Result<Html<String>, TaskRepoError> {
-    let mut task_repo = TaskRepo::new(state.connection_factory);
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
 
-    let mut task = task_repo.get_task(task_id)?;
-    task.increase_priority();
-    task_repo.persist_task(&task)?;
+    let copy_id = task_repo.duplicate_task(task_id)?;
+    let copy = task_repo.get_task(copy_id)?;
 
-    render("task_row.html.j2", context! { task => task })
+    render(&state, "task_row.html.j2", context! { task => copy })
 }
 
-async fn lower_priority(
+#[derive(Deserialize)]
+struct SetPriorityInput {
+    priority: char,
+}
+
+async fn set_priority(
     State(state): State<AppState>,
     Path(task_id): Path<TaskId>,
+    Form(input): Form<SetPriorityInput>,
 ) -> Result<Html<String>, TaskRepoError> {
-    let mut task_repo = TaskRepo::new(state.connection_factory);
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
 
-    let mut task = task_repo.get_task(task_id)?;
-    task.lower_priority();
-    task_repo.persist_task(&task)?;
+    let task = task_repo.set_priority(task_id, &state.priority_scheme, input.priority)?;
 
-    render("task_row.html.j2", context! { task => task })
+    render(&state, "task_row.html.j2", context! { task => task })
 }
 
 #[derive(Deserialize)]
@@ -207,116 +971,1200 @@ async fn update_description(
     let mut task_repo = TaskRepo::new(state.connection_factory);
 
     let mut task = task_repo.get_task(task_id)?;
-    task.description = String::from(task_description.task_description.trim());
+    task.description = validate_description(&task_description.task_description)?.into();
     task_repo.persist_task(&task)?;
 
     Ok(Response::new(Body::empty()))
 }
 
-async fn task_cleanup(State(state): State<AppState>) -> Result<Redirect> {
+async fn move_up(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.move_task_up(task_id)?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn move_down(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Redirect, TaskRepoError> {
     let mut task_repo = TaskRepo::new(state.connection_factory);
 
-    task_repo.cleanup()?;
+    task_repo.move_task_down(task_id)?;
 
     Ok(Redirect::to("/"))
 }
 
 #[derive(Deserialize)]
-struct RenameProjectInput {
-    current_project_name: String,
-    new_project_name: String,
+struct ReorderInput {
+    position: usize,
 }
 
-async fn rename_project(
+async fn reorder(
     State(state): State<AppState>,
-    Form(input): Form<RenameProjectInput>,
-) -> Result<Redirect> {
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<ReorderInput>,
+) -> Result<Redirect, TaskRepoError> {
     let mut task_repo = TaskRepo::new(state.connection_factory);
 
-    task_repo.rename_project(&input.current_project_name, &input.new_project_name)?;
+    task_repo.reorder_task(task_id, input.position)?;
 
     Ok(Redirect::to("/"))
 }
 
 #[derive(Deserialize)]
-struct AddNewPresetInput {
-    preset_name: String,
+struct SetProjectInput {
+    project: String,
 }
 
-async fn add_new_preset(
+async fn set_project(
     State(state): State<AppState>,
-    Form(preset): Form<AddNewPresetInput>,
-) -> Result<Redirect, TaskRepoError> {
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<SetProjectInput>,
+) -> Result<Html<String>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+
+    // An empty project name means the task no longer pertains to any project
+    task_repo.set_task_project(task_id, normalize_project(Some(&input.project)))?;
+    let task = task_repo.get_task(task_id)?;
+
+    render(&state, "task_row.html.j2", context! { task => task })
+}
+
+async fn get_source_preset(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Json<Option<String>>, TaskRepoError> {
     let mut task_repo = TaskRepo::new(state.connection_factory);
-    task_repo.add_preset(&preset.preset_name)?;
 
-    let redirection_url = format!("/preset/{}", preset.preset_name);
-    Ok(Redirect::to(&redirection_url))
+    Ok(Json(task_repo.get_source_preset_name(task_id)?))
 }
 
-async fn get_preset(
+async fn get_task_details(
     State(state): State<AppState>,
-    Path(preset_name): Path<String>,
+    Path(task_id): Path<TaskId>,
 ) -> Result<Html<String>, TaskRepoError> {
-    let mut task_repo = TaskRepo::new(state.connection_factory);
-    let preset = task_repo.get_preset(&preset_name)?;
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+    let task = task_repo.get_task(task_id)?;
+    let notes = task_repo.get_notes(task_id)?;
+    let blockers = task_repo.get_blockers(task_id)?;
 
-    render("preset.html.j2", context! { preset => preset})
+    render(
+        &state,
+        "task.html.j2",
+        context! { task => task, notes => notes, blockers => blockers },
+    )
 }
 
 #[derive(Deserialize)]
-struct AddNewPresetTaskInput {
-    task_priority: char,
-    task_description: String,
+struct AddNoteInput {
+    body: String,
 }
 
-async fn add_new_preset_task(
+async fn add_note(
     State(state): State<AppState>,
-    Path(preset_name): Path<String>,
-    Form(preset_task): Form<AddNewPresetTaskInput>,
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<AddNoteInput>,
 ) -> Result<Redirect, TaskRepoError> {
     let mut task_repo = TaskRepo::new(state.connection_factory);
 
-    let preset_id = task_repo.get_preset_id_from_preset_name(&preset_name)?;
-
-    let preset_task = PresetTask::new(
-        preset_task.task_priority,
-        &preset_task.task_description,
-        preset_id,
-    )?;
-    task_repo.persist_preset_task(preset_task)?;
+    task_repo.add_note(task_id, &input.body)?;
 
-    let redirection_url = format!("/preset/{}", preset_name);
-    Ok(Redirect::to(&redirection_url))
+    Ok(Redirect::to(&format!("/task/{task_id}")))
 }
 
-async fn inject_preset(
+async fn delete_note(
     State(state): State<AppState>,
-    Path(preset_name): Path<String>,
+    Path((task_id, note_id)): Path<(TaskId, NoteId)>,
 ) -> Result<Redirect, TaskRepoError> {
     let mut task_repo = TaskRepo::new(state.connection_factory);
 
-    let preset = task_repo.get_preset(&preset_name)?;
-    for preset_task in preset.tasks {
-        let task = Task::new(
-            preset_task.priority,
-            &preset_task.description,
-            Some(&preset_name),
-        )?;
-        task_repo.persist_task(&task)?
-    }
+    task_repo.delete_note(task_id, note_id)?;
 
-    Ok(Redirect::to("/"))
+    Ok(Redirect::to(&format!("/task/{task_id}")))
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::sql_connection_factory::tests::TempDirSqliteConnectionFactory;
-
-    use super::*;
-    use axum::http::{self, Request, header::LOCATION};
-    use http_body_util::BodyExt;
-    use tower::Service;
+#[derive(Deserialize)]
+struct AddDependencyInput {
+    depends_on_id: TaskId,
+}
+
+async fn add_dependency(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<AddDependencyInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.add_dependency(task_id, input.depends_on_id)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+async fn remove_dependency(
+    State(state): State<AppState>,
+    Path((task_id, depends_on_id)): Path<(TaskId, TaskId)>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.remove_dependency(task_id, depends_on_id)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+#[derive(Deserialize)]
+struct AddTagInput {
+    tag: String,
+}
+
+async fn add_tag(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<AddTagInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.add_tag_to_task(task_id, &input.tag)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+async fn remove_tag(
+    State(state): State<AppState>,
+    Path((task_id, tag)): Path<(TaskId, String)>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.remove_tag_from_task(task_id, &tag)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+#[derive(Deserialize)]
+struct SetRecurrenceInput {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    recurrence: Option<Recurrence>, // Empty for tasks that no longer recur
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    weekday: Option<Weekday>, // For weekly recurrences, e.g. "mon" or "monday"
+}
+
+async fn set_recurrence(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<SetRecurrenceInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.set_recurrence(task_id, input.recurrence, input.weekday)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+async fn pause_recurrence(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.set_recurrence_paused(task_id, true)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+async fn resume_recurrence(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.set_recurrence_paused(task_id, false)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+async fn hide_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.hide_task(task_id)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+async fn unhide_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.unhide_task(task_id)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+#[derive(Deserialize)]
+struct AddSubtaskInput {
+    description: String,
+}
+
+async fn add_subtask(
+    State(state): State<AppState>,
+    Path(task_id): Path<TaskId>,
+    Form(input): Form<AddSubtaskInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.add_subtask(task_id, &input.description)?;
+
+    Ok(Redirect::to(&format!("/task/{task_id}")))
+}
+
+async fn toggle_subtask(
+    State(state): State<AppState>,
+    Path(subtask_id): Path<SubtaskId>,
+) -> Result<Html<String>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+
+    let subtask = task_repo.toggle_subtask(subtask_id)?;
+    let task = task_repo.get_task(subtask.task_id)?;
+
+    render(&state, "task_row.html.j2", context! { task => task })
+}
+
+#[derive(Deserialize)]
+struct ProjectSelect {
+    project: Option<String>,
+}
+
+async fn get_undated_tasks(
+    State(state): State<AppState>,
+    Query(select): Query<ProjectSelect>,
+) -> Result<Json<Vec<Task>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(
+        task_repo.get_undated_tasks(select.project.as_deref())?,
+    ))
+}
+
+async fn get_tasks_by_priority(
+    State(state): State<AppState>,
+    Path(priority): Path<char>,
+    Query(select): Query<ProjectSelect>,
+) -> Result<Json<Vec<Task>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.get_by_priority(
+        &state.priority_scheme,
+        priority,
+        select.project.as_deref(),
+    )?))
+}
+
+async fn get_neglected_tasks(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<(String, Task)>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.oldest_pending_per_project()?))
+}
+
+async fn get_stats(State(state): State<AppState>) -> Result<Html<String>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+    let stats = task_repo.get_stats()?;
+
+    render(&state, "stats.html.j2", context! { stats => stats })
+}
+
+#[derive(Deserialize)]
+struct TimeWindow {
+    from: i64, // Unix timestamps, both included
+    to: i64,
+}
+
+async fn get_changes(
+    State(state): State<AppState>,
+    Query(window): Query<TimeWindow>,
+) -> Result<Response<Body>, TaskRepoError> {
+    if window.from > window.to {
+        return Ok((StatusCode::BAD_REQUEST, "from should not be after to").into_response());
+    }
+
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.get_changes(window.from, window.to)?).into_response())
+}
+
+async fn get_completed(
+    State(state): State<AppState>,
+    Query(window): Query<TimeWindow>,
+) -> Result<Response<Body>, TaskRepoError> {
+    if window.from > window.to {
+        return Ok((StatusCode::BAD_REQUEST, "from should not be after to").into_response());
+    }
+
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.get_completed_between(window.from, window.to)?).into_response())
+}
+
+async fn get_cycle_time(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<(char, f64)>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.avg_completion_time_by_priority()?))
+}
+
+async fn get_project_progress(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<(String, f64)>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.project_completion_ratios()?))
+}
+
+// Tasks outside of any project are counted under a null project
+async fn get_board_counts(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<(Option<String>, u32, u32)>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.board_counts()?))
+}
+
+async fn get_project_task_counts(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<(String, usize)>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.get_project_task_counts()?))
+}
+
+const DEFAULT_SUGGESTION_COUNT: u32 = 10;
+
+#[derive(Deserialize)]
+struct SuggestionsInput {
+    prefix: String,
+    limit: Option<u32>,
+}
+
+async fn get_description_suggestions(
+    State(state): State<AppState>,
+    Query(input): Query<SuggestionsInput>,
+) -> Result<Json<Vec<String>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.description_suggestions(
+        &input.prefix,
+        input.limit.unwrap_or(DEFAULT_SUGGESTION_COUNT),
+    )?))
+}
+
+#[derive(Deserialize)]
+struct BulkTasksInput {
+    #[serde(default)]
+    task_id: Vec<TaskId>,
+}
+
+async fn bulk_complete(
+    State(state): State<AppState>,
+    RepeatedFieldsForm(input): RepeatedFieldsForm<BulkTasksInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.complete_tasks(&input.task_id)?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn reset_overdue_to_today(State(state): State<AppState>) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.reset_overdue(chrono::Local::now().date_naive())?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn bulk_delete(
+    State(state): State<AppState>,
+    RepeatedFieldsForm(input): RepeatedFieldsForm<BulkTasksInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.delete_tasks(&input.task_id)?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn task_cleanup(State(state): State<AppState>) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let cleaned_up_tasks = task_repo.cleanup(state.cleanup_keep_min)?;
+    *state.last_cleanup.lock().unwrap() = Some(cleaned_up_tasks);
+
+    Ok(Redirect::to("/"))
+}
+
+// Only the last cleanup may be undone, and only once. It is forgotten only once undone, so that
+// a failed undo may be retried.
+async fn undo_cleanup(State(state): State<AppState>) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let mut last_cleanup = state.last_cleanup.lock().unwrap();
+    let cleaned_up_tasks = last_cleanup.as_ref().ok_or(TaskRepoError::NotFound {
+        what: "Cleanup to undo".into(),
+    })?;
+    task_repo.undo_cleanup(cleaned_up_tasks)?;
+    *last_cleanup = None;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn archive_completed(State(state): State<AppState>) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.archive_completed()?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn get_archive(State(state): State<AppState>) -> Result<Html<String>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+    let archived_tasks = task_repo.get_archived_tasks()?;
+
+    render(
+        &state,
+        "archive.html.j2",
+        context! { tasks => archived_tasks },
+    )
+}
+
+// Overdue pending tasks, with the number of days they are late by
+async fn get_missed(State(state): State<AppState>) -> Result<Html<String>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+    let today = chrono::Local::now().date_naive();
+    let missed_tasks: Vec<_> = task_repo
+        .get_missed_tasks(today)?
+        .into_iter()
+        .map(|task| {
+            let days_late = task.due_date.map(|due_date| (today - due_date).num_days());
+            (task, days_late)
+        })
+        .collect();
+
+    render(
+        &state,
+        "missed.html.j2",
+        context! { missed_tasks => missed_tasks, today => today },
+    )
+}
+
+async fn restore_task(
+    State(state): State<AppState>,
+    Path(archived_task_id): Path<TaskId>,
+) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.restore_task(archived_task_id)?;
+
+    Ok(Redirect::to("/archive"))
+}
+
+#[derive(Deserialize)]
+struct RenameProjectInput {
+    current_project_name: String,
+    new_project_name: String,
+}
+
+async fn rename_project(
+    State(state): State<AppState>,
+    Form(input): Form<RenameProjectInput>,
+) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.rename_project(&input.current_project_name, &input.new_project_name)?;
+
+    Ok(Redirect::to("/"))
+}
+
+#[derive(Deserialize)]
+struct SaveViewInput {
+    view_name: String,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    project: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    show: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    tag: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    highest_priority: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    lowest_priority: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    sort: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    view: Option<String>,
+}
+
+async fn save_view(
+    State(state): State<AppState>,
+    Form(input): Form<SaveViewInput>,
+) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let filters = TaskListFilters {
+        project: input.project,
+        show: input.show,
+        tag: input.tag,
+        highest_priority: input.highest_priority,
+        lowest_priority: input.lowest_priority,
+        sort: input.sort,
+        view: input.view,
+    };
+    task_repo.save_view(&input.view_name, &filters)?;
+
+    // View names are free text, which has to be escaped as a path segment
+    let view_name = utf8_percent_encode(&input.view_name, NON_ALPHANUMERIC);
+    Ok(Redirect::to(&format!("/view/{view_name}")))
+}
+
+async fn get_view(
+    State(state): State<AppState>,
+    Path(view_name): Path<String>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Html<String>, TaskRepoError> {
+    let filters = TaskRepo::new(state.connection_factory.clone()).get_view(&view_name)?;
+
+    render_task_list(state, filters, pagination)
+}
+
+async fn delete_view(
+    State(state): State<AppState>,
+    Path(view_name): Path<String>,
+) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.delete_view(&view_name)?;
+
+    Ok(Redirect::to("/"))
+}
+
+#[derive(Deserialize)]
+struct MergeProjectsInput {
+    from_project_name: String,
+    into_project_name: String,
+}
+
+async fn merge_projects(
+    State(state): State<AppState>,
+    Form(input): Form<MergeProjectsInput>,
+) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.merge_projects(&input.from_project_name, &input.into_project_name)?;
+
+    Ok(Redirect::to("/"))
+}
+
+#[derive(Deserialize)]
+struct SetProjectColorInput {
+    project_name: String,
+    color: String,
+}
+
+async fn set_project_color(
+    State(state): State<AppState>,
+    Form(input): Form<SetProjectColorInput>,
+) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.set_project_color(&input.project_name, &input.color)?;
+
+    Ok(Redirect::to("/"))
+}
+
+#[derive(Deserialize)]
+struct DeleteProjectInput {
+    project_name: String,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    reassign_to: Option<String>, // Tasks are deleted if None
+}
+
+async fn delete_project(
+    State(state): State<AppState>,
+    Form(input): Form<DeleteProjectInput>,
+) -> Result<Redirect> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    // Tasks outside of any project are stored with an empty project
+    let reassign_to = match input.reassign_to.as_deref() {
+        Some(NO_PROJECT_FILTER) => Some(""),
+        reassign_to => reassign_to,
+    };
+    task_repo.delete_project(&input.project_name, reassign_to)?;
+
+    Ok(Redirect::to("/"))
+}
+
+// Main page, filtered on the project
+fn project_page_redirect(project: &str) -> Result<Redirect, TaskRepoError> {
+    let query = serde_urlencoded::to_string([("project", project)]).map_err(|error| {
+        TaskRepoError::Error {
+            error: error.to_string(),
+        }
+    })?;
+
+    Ok(Redirect::to(&format!("/?{query}")))
+}
+
+async fn complete_all_in_project(
+    State(state): State<AppState>,
+    Path(project): Path<String>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.complete_all_in_project(&project)?;
+
+    project_page_redirect(&project)
+}
+
+#[derive(Deserialize)]
+struct CompleteProjectInput {
+    project: String,
+}
+
+async fn complete_project(
+    State(state): State<AppState>,
+    Form(input): Form<CompleteProjectInput>,
+) -> Result<Redirect, TaskRepoError> {
+    complete_all_in_project(State(state), Path(input.project)).await
+}
+
+#[derive(Deserialize)]
+struct ReopenAllInput {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    project: Option<String>, // All projects if None
+}
+
+async fn reopen_all(
+    State(state): State<AppState>,
+    Form(input): Form<ReopenAllInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    // Tasks outside of any project are stored with an empty project
+    let project_filter = match input.project.as_deref() {
+        Some(NO_PROJECT_FILTER) => Some(""),
+        project_filter => project_filter,
+    };
+    task_repo.reopen_all(project_filter)?;
+
+    match input.project {
+        Some(project) => project_page_redirect(&project),
+        None => Ok(Redirect::to("/")),
+    }
+}
+
+async fn delete_all_in_project(
+    State(state): State<AppState>,
+    Path(project): Path<String>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.delete_all_in_project(&project)?;
+
+    project_page_redirect(&project)
+}
+
+// Tasks are kept, out of any project
+async fn clear_project(
+    State(state): State<AppState>,
+    Path(project): Path<String>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.clear_project(&project)?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn export_todotxt(State(state): State<AppState>) -> Result<impl IntoResponse, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let todotxt = task_repo.export_todotxt()?;
+
+    Ok(([(CONTENT_TYPE, "text/plain; charset=utf-8")], todotxt))
+}
+
+async fn export_ical(State(state): State<AppState>) -> Result<impl IntoResponse, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let ical = task_repo.export_ical(&state.priority_scheme)?;
+
+    Ok(([(CONTENT_TYPE, "text/calendar; charset=utf-8")], ical))
+}
+
+async fn import_todotxt(
+    State(state): State<AppState>,
+    todotxt: String,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    task_repo.import_todotxt(&state.priority_scheme, &todotxt)?;
+
+    Ok(Redirect::to("/"))
+}
+
+#[derive(Deserialize)]
+struct ImportTaskInput {
+    priority: Option<char>,
+    description: String,
+    project: Option<String>,
+    #[serde(default)]
+    completed: bool,
+}
+
+#[derive(Serialize)]
+struct SkippedTask {
+    index: usize, // Position in the imported array
+    description: String,
+    error: String,
+}
+
+#[derive(Serialize)]
+struct ImportSummary {
+    imported: usize,
+    skipped: Vec<SkippedTask>,
+}
+
+// Unlike todo.txt imports, invalid tasks are reported instead of failing the whole import
+async fn import_json(
+    State(state): State<AppState>,
+    Json(inputs): Json<Vec<ImportTaskInput>>,
+) -> Result<Json<ImportSummary>, TaskRepoError> {
+    let mut tasks = vec![];
+    let mut skipped = vec![];
+    for (index, input) in inputs.into_iter().enumerate() {
+        match Task::new(
+            &state.priority_scheme,
+            input.priority,
+            &input.description,
+            input.project.as_deref(),
+        ) {
+            Ok(mut task) => {
+                task.completed = input.completed;
+                tasks.push(task);
+            }
+            Err(error) => skipped.push(SkippedTask {
+                index,
+                description: input.description,
+                error: error.to_string(),
+            }),
+        }
+    }
+
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+    let imported = task_repo.import_tasks(&tasks)?;
+
+    Ok(Json(ImportSummary { imported, skipped }))
+}
+
+// Unlike the other exports, also covers presets. See `ExportBundle` for what is left out.
+async fn export_all(State(state): State<AppState>) -> Result<Json<ExportBundle>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.export_all()?))
+}
+
+// Accepts what `/export.json` returns, fields computed on export being ignored
+#[derive(Deserialize)]
+struct ImportBundleInput {
+    tasks: Vec<ImportBundleTaskInput>,
+    #[serde(default)]
+    presets: Vec<ImportPresetInput>,
+    #[serde(default)]
+    project_colors: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ImportBundleTaskInput {
+    id: Option<TaskId>, // Only used to link tasks to the tasks they depend on
+    priority: Option<char>,
+    description: String,
+    project: Option<String>,
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    hidden: bool,
+    source_preset_id: Option<PresetId>,
+    due_date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    recurrence_paused: bool,
+    created_at: Option<i64>,
+    completed_at: Option<i64>,
+    #[serde(default)]
+    subtasks: Vec<ImportSubtaskInput>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    notes: Vec<ImportNoteInput>,
+    #[serde(default)]
+    depends_on: Vec<TaskId>,
+}
+
+#[derive(Deserialize)]
+struct ImportSubtaskInput {
+    description: String,
+    #[serde(default)]
+    completed: bool,
+}
+
+#[derive(Deserialize)]
+struct ImportNoteInput {
+    body: String,
+    created_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ImportPresetInput {
+    id: PresetId, // Only used to link tasks to the presets they were injected from
+    name: String,
+    tasks: Vec<ImportPresetTaskInput>,
+}
+
+#[derive(Deserialize)]
+struct ImportPresetTaskInput {
+    priority: char,
+    description: String,
+}
+
+// Either everything is imported, or nothing, e.g. if a single task is invalid
+async fn import_all(
+    State(state): State<AppState>,
+    Json(input): Json<ImportBundleInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let tasks = input
+        .tasks
+        .into_iter()
+        .map(|input| {
+            let mut task = Task::new(
+                &state.priority_scheme,
+                input.priority,
+                &input.description,
+                input.project.as_deref(),
+            )?;
+            task.id = input.id.unwrap_or(-1);
+            task.completed = input.completed;
+            task.hidden = input.hidden;
+            task.source_preset_id = input.source_preset_id;
+            task.due_date = input.due_date;
+            task.recurrence = input.recurrence;
+            task.recurrence_paused = input.recurrence_paused;
+            task.created_at = input.created_at.unwrap_or(0);
+            task.subtasks = input
+                .subtasks
+                .into_iter()
+                .map(|subtask| Subtask {
+                    id: -1,
+                    task_id: task.id,
+                    description: subtask.description,
+                    completed: subtask.completed,
+                })
+                .collect();
+            task.tags = input.tags;
+            let notes = input
+                .notes
+                .into_iter()
+                .map(|note| Note {
+                    id: -1,
+                    task_id: task.id,
+                    body: note.body,
+                    created_at: note.created_at.unwrap_or(0),
+                })
+                .collect();
+            Ok(ExportedTask {
+                task,
+                completed_at: input.completed_at,
+                notes,
+                depends_on: input.depends_on,
+            })
+        })
+        .collect::<Result<_, TaskRepoError>>()?;
+    let presets = input
+        .presets
+        .into_iter()
+        .map(|input| {
+            let tasks = input
+                .tasks
+                .iter()
+                .map(|task| {
+                    PresetTask::new(
+                        &state.priority_scheme,
+                        task.priority,
+                        &task.description,
+                        input.id,
+                    )
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Preset {
+                id: input.id,
+                name: input.name,
+                tasks,
+            })
+        })
+        .collect::<Result<_, TaskRepoError>>()?;
+
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+    task_repo.import_all(ExportBundle {
+        tasks,
+        presets,
+        project_colors: input.project_colors,
+    })?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn export_csv(State(state): State<AppState>) -> Result<impl IntoResponse, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let csv = task_repo.export_csv()?;
+
+    Ok((
+        [
+            (CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (CONTENT_DISPOSITION, "attachment; filename=\"tasks.csv\""),
+        ],
+        csv,
+    ))
+}
+
+// Expects the raw content of a database backup as body
+async fn restore(State(state): State<AppState>, backup: Bytes) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let mut backup_file = NamedTempFile::new()?;
+    backup_file.write_all(&backup)?;
+    backup_file.flush()?;
+    task_repo.restore_from(backup_file.path())?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn get_orphans(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PresetTask>>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.find_orphaned_preset_tasks()?))
+}
+
+async fn get_schema(State(state): State<AppState>) -> Result<impl IntoResponse, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let schema = task_repo.get_schema()?;
+
+    Ok(([(CONTENT_TYPE, "text/plain; charset=utf-8")], schema))
+}
+
+// Returns the number of purged preset tasks
+async fn purge_orphans(State(state): State<AppState>) -> Result<Json<usize>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.purge_orphaned_preset_tasks()?))
+}
+
+// Returns the file name of the new backup
+async fn backup(State(state): State<AppState>) -> Result<Json<String>, TaskRepoError> {
+    let backup_config = state
+        .backup_config
+        .expect("Backup route should only exist if configured");
+
+    let backup_path = backup_database(
+        state.connection_factory,
+        &backup_config.directory,
+        backup_config.retention,
+    )?;
+    let backup_name = backup_path
+        .file_name()
+        .expect("Backup path should have a file name")
+        .to_string_lossy()
+        .into_owned();
+
+    Ok(Json(backup_name))
+}
+
+// Returns the number of fixed tasks and preset tasks
+async fn normalize_priorities(State(state): State<AppState>) -> Result<Json<usize>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.normalize_all_priorities()?))
+}
+
+#[derive(Deserialize)]
+struct SummarizeMonthInput {
+    year: i32,
+    month: u32,
+}
+
+async fn summarize_month(
+    State(state): State<AppState>,
+    Form(input): Form<SummarizeMonthInput>,
+) -> Result<Json<MonthlySummary>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    Ok(Json(task_repo.summarize_month(input.year, input.month)?))
+}
+
+#[derive(Deserialize)]
+struct AddNewPresetInput {
+    preset_name: String,
+}
+
+async fn add_new_preset(
+    State(state): State<AppState>,
+    Form(preset): Form<AddNewPresetInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+    task_repo.add_preset(&preset.preset_name)?;
+
+    let redirection_url = format!("/preset/{}", preset.preset_name);
+    Ok(Redirect::to(&redirection_url))
+}
+
+#[derive(Deserialize)]
+struct RenamePresetInput {
+    new_name: String,
+}
+
+async fn rename_preset(
+    State(state): State<AppState>,
+    Path(preset_name): Path<String>,
+    Form(input): Form<RenamePresetInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+    task_repo.rename_preset(&preset_name, &input.new_name)?;
+
+    let redirection_url = format!("/preset/{}", input.new_name);
+    Ok(Redirect::to(&redirection_url))
+}
+
+async fn get_preset(
+    State(state): State<AppState>,
+    Path(preset_name): Path<String>,
+) -> Result<Html<String>, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory.clone());
+    let preset = task_repo.get_preset(&preset_name)?;
+
+    render(
+        &state,
+        "preset.html.j2",
+        context! { preset => preset, priority_scheme => state.priority_scheme },
+    )
+}
+
+#[derive(Deserialize)]
+struct AddNewPresetTaskInput {
+    task_priority: char,
+    task_description: String,
+}
+
+async fn add_new_preset_task(
+    State(state): State<AppState>,
+    Path(preset_name): Path<String>,
+    Form(preset_task): Form<AddNewPresetTaskInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let preset_id = task_repo.get_preset_id_from_preset_name(&preset_name)?;
+
+    let preset_task = PresetTask::new(
+        &state.priority_scheme,
+        preset_task.task_priority,
+        &preset_task.task_description,
+        preset_id,
+    )?;
+    task_repo.persist_preset_task(preset_task)?;
+
+    let redirection_url = format!("/preset/{}", preset_name);
+    Ok(Redirect::to(&redirection_url))
+}
+
+#[derive(Deserialize)]
+struct AddManyPresetTasksInput {
+    task_priority: char,
+    task_descriptions: String, // One per line, blank lines being ignored
+}
+
+async fn add_many_preset_tasks(
+    State(state): State<AppState>,
+    Path(preset_name): Path<String>,
+    Form(input): Form<AddManyPresetTasksInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+
+    let preset_id = task_repo.get_preset_id_from_preset_name(&preset_name)?;
+
+    let preset_tasks = input
+        .task_descriptions
+        .lines()
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+        .map(|description| {
+            PresetTask::new(
+                &state.priority_scheme,
+                input.task_priority,
+                description,
+                preset_id,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    task_repo.persist_preset_tasks(preset_tasks)?;
+
+    let redirection_url = format!("/preset/{}", preset_name);
+    Ok(Redirect::to(&redirection_url))
+}
+
+#[derive(Deserialize)]
+struct InjectPresetInput {
+    #[serde(default)]
+    force: bool, // Also inject tasks already pending
+}
+
+async fn inject_preset(
+    State(state): State<AppState>,
+    Path(preset_name): Path<String>,
+    Query(input): Query<InjectPresetInput>,
+) -> Result<Redirect, TaskRepoError> {
+    let mut task_repo = TaskRepo::new(state.connection_factory);
+    task_repo.inject_preset(&state.priority_scheme, &preset_name, input.force)?;
+
+    Ok(Redirect::to("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::sql_connection_factory::SqlConnection;
+    use crate::sql_connection_factory::tests::{
+        InMemorySqliteConnectionFactory, TempDirSqliteConnectionFactory,
+    };
+
+    use super::*;
+    use crate::task::LETTERS;
+    use axum::http::{self, header::LOCATION};
+    use futures_util::StreamExt;
+    use http_body_util::BodyExt;
+    use tower::Service;
 
     async fn add_new_task(
         app: &mut Router,
@@ -338,52 +2186,2214 @@ mod tests {
                         http::header::CONTENT_TYPE,
                         mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
                     )
-                    .body(Body::from(form_text))
+                    .body(Body::from(form_text))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+    }
+
+    async fn parse_body(response: Response<Body>) -> String {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    async fn get_page_body(app: &mut Router, uri: &str) -> String {
+        let response = app
+            .call(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        parse_body(response).await
+    }
+
+    async fn get_main_page_body(app: &mut Router) -> String {
+        get_page_body(app, "/").await
+    }
+
+    struct FailingConnectionFactory;
+
+    impl SqlConnectionFactory for FailingConnectionFactory {
+        fn open(&self) -> Result<SqlConnection, rusqlite::Error> {
+            Err(rusqlite::Error::InvalidPath("unreachable.db".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn priority_styling() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+        let mut app_state = AppState::new(connection_factory);
+        app_state.priority_scheme = "1-5".parse().unwrap();
+        app_state.theme = Theme::Light;
+        let mut app = build_app(app_state);
+
+        add_new_task(&mut app, '1', "UrgentTask", None).await;
+        add_new_task(&mut app, '5', "MinorTask", None).await;
+
+        // Priorities are styled after the configured scheme and theme
+        let body = get_main_page_body(&mut app).await;
+        assert!(body.contains(r#"data-bs-theme="light""#));
+        assert!(body.contains(
+            r#"<span class="priority-high" style="color: var(--bs-danger-text-emphasis)">(1)</span>"#
+        ));
+        assert!(body.contains(
+            r#"<span class="priority-low" style="color: var(--bs-info-text-emphasis)">(5)</span>"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn health_check() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(parse_body(response).await, "ok");
+
+        // Unreachable database
+        let mut app = build_app(AppState::new(Arc::new(FailingConnectionFactory)));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn health_check_without_schema() {
+        // The database is reachable even though no table was created yet
+        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new().unwrap());
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn sql_errors_are_not_leaked() {
+        // Without any schema, listing tasks fails on the missing table
+        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new().unwrap());
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let response = app
+            .call(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let parsed_body = parse_body(response).await;
+        assert!(!parsed_body.contains("no such table"));
+        assert!(!parsed_body.contains("tasks"));
+    }
+
+    #[tokio::test]
+    async fn sql_error_statuses() {
+        let sqlite_failure = |code, message: &str| TaskRepoError::SqlError {
+            original_error: rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(code),
+                Some(message.into()),
+            ),
+        };
+
+        for (code, expected_status) in [
+            (
+                rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE,
+                StatusCode::CONFLICT,
+            ),
+            (rusqlite::ffi::SQLITE_BUSY, StatusCode::SERVICE_UNAVAILABLE),
+            (
+                rusqlite::ffi::SQLITE_LOCKED,
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                rusqlite::ffi::SQLITE_CORRUPT,
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ] {
+            let response = sqlite_failure(code, "SomeInternalDetail on tasks").into_response();
+            assert_eq!(response.status(), expected_status);
+
+            // Details are still kept out of the response
+            let parsed_body = parse_body(response).await;
+            assert!(!parsed_body.contains("SomeInternalDetail"));
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_input_is_a_bad_request() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        for (uri, form_text) in [
+            ("/add-new-task", "priority=A&description=%20"), // Empty description
+            ("/add-new-task", "priority=1&description=SomeOtherTask"), // Not in the scheme
+            ("/task/1/add-note", "body=%20"),                // Blank note
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri(uri)
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(form_text))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn full_basic_flow() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        // Add new task
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+
+        // Ensure it appears in the output
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("(B)"));
+        assert!(parsed_body.contains("SomeTask"));
+
+        // Increase priority
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/increase-priority/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+
+        // Ensure priority was increased
+        assert!(!parsed_body.contains("(B)"));
+        assert!(parsed_body.contains("(A)"));
+
+        // Lower priority
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/lower-priority/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+
+        // Ensure priority was increased
+        assert!(!parsed_body.contains("(A)"));
+        assert!(parsed_body.contains("(B)"));
+
+        // Set priority directly
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/set-priority/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("priority=A"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(parse_body(response).await.contains("(A)"));
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(!parsed_body.contains("(B)"));
+        assert!(parsed_body.contains("(A)"));
+
+        // Flag as completed
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/flag-completed/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+
+        // Ensure task is flagged as completed
+        assert!(!parsed_body.contains("✓"));
+        assert!(parsed_body.contains("✗"));
+
+        // Flag as pending
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/flag-pending/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+
+        // Ensure task is flagged as pending
+        assert!(!parsed_body.contains("✗"));
+        assert!(parsed_body.contains("✓"));
+
+        // Update description
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/update-description/1")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("task_description=SomeNewTask"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+
+        // Body empty for this request as there is no need for replacement
+        assert_eq!(parsed_body.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn task_duplication() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/duplicate-task/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The new row is rendered, for the copy, which is suffixed as the task is still pending
+        let parsed_body = parse_body(response).await;
+        assert!(parsed_body.contains("/flag-completed/2"));
+        let main_page_body = get_main_page_body(&mut app).await;
+        assert!(main_page_body.contains("value=\"SomeTask\""));
+        assert!(main_page_body.contains("value=\"SomeTask (copy)\""));
+    }
+
+    #[tokio::test]
+    async fn unprioritized_tasks() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        // Add new task with an empty priority
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/add-new-task")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("priority=&description=SomeTask"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        // Ensure it appears in the output, without priority
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(!parsed_body.contains(">()</span>"));
+
+        // Increasing priority gives it a first priority
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/increase-priority/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+        assert!(parsed_body.contains("(Z)"));
+    }
+
+    #[tokio::test]
+    async fn task_cleanup() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        // Add new task
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+        add_new_task(&mut app, 'A', "SomeImportantTask", None).await;
+        add_new_task(&mut app, 'C', "SomeNotImportantTask", None).await;
+
+        // Flag some of them as completed
+        for i in 1..=2 {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri(format!("/flag-completed/{i}"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // Ensure they are still there, but hidden by default
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(!parsed_body.contains("SomeTask"));
+        assert!(!parsed_body.contains("SomeImportantTask"));
+        assert!(parsed_body.contains("SomeNotImportantTask"));
+
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("SomeImportantTask"));
+        assert!(!parsed_body.contains("SomeNotImportantTask"));
+
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("SomeImportantTask"));
+        assert!(parsed_body.contains("SomeNotImportantTask"));
+
+        // Trigger cleanup
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/task-cleanup")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+
+        // Ensure they have been deleted
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(!parsed_body.contains("SomeTask")); // Completed => removed
+        assert!(!parsed_body.contains("SomeImportantTask")); // Completed => removed
+        assert!(parsed_body.contains("SomeNotImportantTask")); // Pending => kept
+
+        // Undo cleanup, which may only be done once
+        for expected_status in [StatusCode::SEE_OTHER, StatusCode::NOT_FOUND] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/undo-cleanup")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected_status);
+        }
+
+        // Ensure they are back, still completed
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("SomeImportantTask"));
+        assert!(!parsed_body.contains("SomeNotImportantTask"));
+    }
+
+    #[tokio::test]
+    async fn bulk_operations() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        add_new_task(&mut app, 'B', "SomeOtherTask", None).await;
+        add_new_task(&mut app, 'C', "SomeDeletedTask", None).await;
+
+        for (uri, form_text) in [
+            ("/bulk-complete", "task_id=1&task_id=2&task_id=42"),
+            ("/bulk-delete", "task_id=3&task_id=42"),
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri(uri)
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(form_text))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        }
+
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("SomeOtherTask"));
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(!parsed_body.contains("SomeDeletedTask"));
+    }
+
+    #[tokio::test]
+    async fn saved_views() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", Some("project1")).await;
+        add_new_task(&mut app, 'B', "SomeOtherTask", Some("project2")).await;
+        add_new_task(&mut app, 'C', "SomeMinorTask", Some("project1")).await;
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/view")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(
+                        "view_name=my+view&project=project1&show=all&lowest_priority=B",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/view/my%20view");
+
+        // The saved view applies its filters, and is listed on the main page
+        let parsed_body = get_page_body(&mut app, "/view/my%20view").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(!parsed_body.contains("SomeOtherTask"));
+        assert!(!parsed_body.contains("SomeMinorTask"));
+        assert!(
+            get_main_page_body(&mut app)
+                .await
+                .contains("/view/my%20view")
+        );
+
+        // Unknown views are not found
+        for (method, uri) in [
+            (http::Method::GET, "/view/unknown"),
+            (http::Method::POST, "/view/unknown/delete"),
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn bulk_operations_by_project() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", Some("my project")).await;
+        add_new_task(&mut app, 'B', "SomeOtherTask", Some("other")).await;
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/project/my%20project/complete-all")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get(LOCATION).unwrap(),
+            "/?project=my+project"
+        );
+
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(!parsed_body.contains("SomeOtherTask"));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/project/my%20project/delete-all")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(!parsed_body.contains(r#"value="SomeTask""#));
+        assert!(parsed_body.contains("SomeOtherTask"));
+
+        // Projects may also be completed from a form
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/complete-project")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("project=other"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/?project=other");
+
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(parsed_body.contains("SomeOtherTask"));
+
+        // Then reopened
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/reopen-all")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("project=other"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/?project=other");
+
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(!parsed_body.contains("SomeOtherTask"));
+    }
+
+    #[tokio::test]
+    async fn clear_project() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", Some("my project")).await;
+        add_new_task(&mut app, 'B', "SomeOtherTask", Some("other project")).await;
+
+        let parsed_body = get_page_body(&mut app, "/api/project-task-counts").await;
+        assert_eq!(parsed_body, r#"[["my project",1],["other project",1]]"#);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/project/my%20project/clear")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let parsed_body = get_page_body(&mut app, "/api/project-task-counts").await;
+        assert_eq!(parsed_body, r#"[["other project",1]]"#);
+        let parsed_body = get_page_body(&mut app, "/?project=__none__").await;
+        assert!(parsed_body.contains("SomeTask"));
+    }
+
+    #[tokio::test]
+    async fn no_project_filter() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", Some("project1")).await;
+        add_new_task(&mut app, 'B', "SomeUncategorizedTask", None).await;
+
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("No project"));
+
+        let parsed_body = get_page_body(&mut app, "/?project=__none__").await;
+        assert!(!parsed_body.contains(r#"value="SomeTask""#));
+        assert!(parsed_body.contains("SomeUncategorizedTask"));
+    }
+
+    #[tokio::test]
+    async fn missing_task() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/increase-priority/99999")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(parse_body(response).await.contains("Task 99999"));
+    }
+
+    #[tokio::test]
+    async fn sorting() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeZebraTask", None).await;
+        add_new_task(&mut app, 'B', "SomeAntTask", None).await;
+
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.find("SomeZebraTask") < parsed_body.find("SomeAntTask"));
+
+        // The sort order is kept when changing other filters
+        let parsed_body = get_page_body(&mut app, "/?sort=description").await;
+        assert!(parsed_body.find("SomeAntTask") < parsed_body.find("SomeZebraTask"));
+        assert!(parsed_body.contains(r#"<input type="hidden" name="sort" value="description" />"#));
+
+        // Unknown sort orders fall back to the default one
+        let parsed_body = get_page_body(&mut app, "/?sort=unknown").await;
+        assert!(parsed_body.find("SomeZebraTask") < parsed_body.find("SomeAntTask"));
+    }
+
+    #[tokio::test]
+    async fn pagination() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeFirstTask", None).await;
+        add_new_task(&mut app, 'B', "SomeSecondTask", None).await;
+        add_new_task(&mut app, 'C', "SomeThirdTask", None).await;
+
+        // Everything fits in a single page by default
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("SomeThirdTask"));
+        assert!(!parsed_body.contains("Next page"));
+
+        let parsed_body = get_page_body(&mut app, "/?page=2&per_page=2").await;
+        assert!(!parsed_body.contains("SomeFirstTask"));
+        assert!(parsed_body.contains("SomeThirdTask"));
+        assert!(parsed_body.contains("Page 2 of 2 (3 tasks)"));
+        assert!(parsed_body.contains("Previous page"));
+        assert!(!parsed_body.contains("Next page"));
+
+        // Pages out of range are brought back in range
+        let parsed_body = get_page_body(&mut app, "/?page=42&per_page=2").await;
+        assert!(parsed_body.contains("Page 2 of 2 (3 tasks)"));
+
+        // So are overly large pages
+        let parsed_body = get_page_body(&mut app, "/?per_page=18446744073709551615").await;
+        assert!(parsed_body.contains("SomeFirstTask"));
+        assert!(parsed_body.contains("SomeThirdTask"));
+    }
+
+    #[tokio::test]
+    async fn pending_duplicate_is_rejected() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/add-new-task")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("priority=A&description=SomeTask"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn archive() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        add_new_task(&mut app, 'B', "SomeArchivedTask", None).await;
+        for uri in ["/flag-completed/2", "/archive-completed"] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert!(response.status().is_success() || response.status().is_redirection());
+        }
+
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(!parsed_body.contains("SomeArchivedTask"));
+        let parsed_body = get_page_body(&mut app, "/archive").await;
+        assert!(parsed_body.contains("SomeArchivedTask"));
+        assert!(!parsed_body.contains("SomeTask<"));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/archive/1/restore")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(parsed_body.contains("SomeArchivedTask"));
+
+        // It is no longer in the archive
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/archive/1/restore")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn presets_disabled() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app_state = AppState::new(connection_factory);
+        app_state.presets_enabled = false;
+        let mut app = build_app(app_state);
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/preset")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("preset_name=SomePreset"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Nor the presets tasks were injected from
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/task/1/source-preset")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(!parsed_body.contains("Preset management"));
+        assert!(!parsed_body.contains("action=\"/preset\""));
+    }
+
+    #[tokio::test]
+    async fn live_updates() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/events")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        let mut events = response.into_body();
+
+        // Reading pages changes nothing
+        get_main_page_body(&mut app).await;
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), events.frame())
+            .await
+            .expect("An event should be sent")
+            .unwrap()
+            .unwrap();
+        let event = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+        assert_eq!(event, "event: tasks-changed\ndata: /add-new-task\n\n");
+    }
+
+    #[tokio::test]
+    async fn live_updates_over_websocket() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        // Upgrades need an actual connection
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, app.clone()).into_future());
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{address}/ws"))
+            .await
+            .unwrap();
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+            .await
+            .expect("A message should be sent")
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.into_text().unwrap().as_str(), "/add-new-task");
+    }
+
+    #[tokio::test]
+    async fn hide_tasks() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        add_new_task(&mut app, 'B', "SomeHiddenTask", None).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/hide/2")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/task/2");
+
+        // Hidden tasks are left out by default, whatever their completion state
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(parsed_body.contains("value=\"SomeTask\""));
+        assert!(!parsed_body.contains("SomeHiddenTask"));
+
+        // The hidden view only shows them, as did the former archived view
+        for uri in ["/?view=hidden", "/?view=archived"] {
+            let parsed_body = get_page_body(&mut app, uri).await;
+            assert!(!parsed_body.contains("value=\"SomeTask\""));
+            assert!(parsed_body.contains("SomeHiddenTask"));
+        }
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/unhide/2")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("SomeHiddenTask"));
+    }
+
+    #[tokio::test]
+    async fn tasks_and_projects() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        // Add new task with or without projects
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+        add_new_task(&mut app, 'B', "SomeOtherTask", Some("project1")).await;
+
+        // Ensure it appears in the output
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("project1"));
+
+        // Pending tasks are counted by project, and overall
+        assert!(parsed_body.contains("project1 (1)"));
+        assert!(parsed_body.contains("2 pending tasks overall"));
+
+        // Rename project
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/rename-project")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(
+                        "current_project_name=project1&new_project_name=project2",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+
+        // Ensure new name appears in the output
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("project2"));
+
+        // Projects may be tinted
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/set-project-color")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("project_name=project2&color=%23ff8800"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("border-left: 0.5em solid #ff8800"));
+
+        // Merge project into another one
+        add_new_task(&mut app, 'B', "SomeThirdTask", Some("project3")).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/merge-projects")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(
+                        "from_project_name=project3&into_project_name=project2",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let parsed_body = get_page_body(&mut app, "/?project=project2").await;
+        assert!(parsed_body.contains("SomeOtherTask"));
+        assert!(parsed_body.contains("SomeThirdTask"));
+        assert!(!parsed_body.contains("project3"));
+
+        // Delete project, moving its tasks out of any project
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/delete-project")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("project_name=project2&reassign_to=__none__"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let parsed_body = get_page_body(&mut app, "/?project=__none__").await;
+        assert!(parsed_body.contains("SomeOtherTask"));
+        assert!(parsed_body.contains("SomeThirdTask"));
+        assert!(!parsed_body.contains("project2"));
+    }
+
+    #[tokio::test]
+    async fn project_filter_ignores_case() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeWorkTask", Some("Work")).await;
+        add_new_task(&mut app, 'A', "SomeOtherWorkTask", Some("work")).await;
+        add_new_task(&mut app, 'B', "SomeHomeTask", Some("home")).await;
+
+        let parsed_body = get_page_body(&mut app, "/?project=work").await;
+        assert!(parsed_body.contains("SomeWorkTask"));
+        assert!(parsed_body.contains("SomeOtherWorkTask"));
+        assert!(!parsed_body.contains("SomeHomeTask"));
+
+        // Projects are stored as first spelled, so the dropdown lists a single variant
+        assert_eq!(
+            parsed_body
+                .matches(r#"<option value="Work"></option>"#)
+                .count(),
+            1
+        );
+        assert!(!parsed_body.contains(r#"<option value="work">"#));
+    }
+
+    #[tokio::test]
+    async fn todotxt_import_export() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        // Import some tasks
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/import/todotxt")
+                    .header(http::header::CONTENT_TYPE, mime::TEXT_PLAIN.as_ref())
+                    .body(Body::from(
+                        "(B) SomeTask +project1\nx (A) SomeCompletedTask",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+
+        // Ensure they appear in the output
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("SomeCompletedTask"));
+
+        // Export them back
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/export/todotxt")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "text/plain; charset=utf-8"
+        );
+        let parsed_body = parse_body(response).await;
+        assert_eq!(
+            parsed_body,
+            "(B) SomeTask +project1\nx (A) SomeCompletedTask"
+        );
+
+        // Also available under the usual file name
+        let parsed_body = get_page_body(&mut app, "/export/todo.txt").await;
+        assert_eq!(
+            parsed_body,
+            "(B) SomeTask +project1\nx (A) SomeCompletedTask"
+        );
+    }
+
+    #[tokio::test]
+    async fn json_export_import() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        let mut task_repo = TaskRepo::new(connection_factory.clone());
+        task_repo.init_db().unwrap();
+        task_repo.add_preset("preset1").unwrap();
+        let preset_id = task_repo.get_preset_id_from_preset_name("preset1").unwrap();
+        task_repo
+            .persist_preset_task(
+                PresetTask::new(&LETTERS, 'A', "SomePresetTask", preset_id).unwrap(),
+            )
+            .unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+        add_new_task(&mut app, 'B', "SomeTask", Some("project1")).await;
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/export.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            mime::APPLICATION_JSON.as_ref()
+        );
+        let exported = parse_body(response).await;
+
+        // Import everything into another instance
+        let other_connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(other_connection_factory.clone())
+            .init_db()
+            .unwrap();
+        let mut other_app = build_app(AppState::new(other_connection_factory));
+
+        let mut import = async |exported: String| {
+            other_app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/import.json")
+                        .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                        .body(Body::from(exported))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+        };
+        assert_eq!(import(exported.clone()).await, StatusCode::SEE_OTHER);
+
+        // Presets whose name is taken are renamed rather than failing the import
+        assert_eq!(import(exported).await, StatusCode::SEE_OTHER);
+
+        let bundle: serde_json::Value =
+            serde_json::from_str(&get_page_body(&mut other_app, "/export.json").await).unwrap();
+        assert_eq!(bundle["tasks"][0]["description"], "SomeTask");
+        assert_eq!(bundle["tasks"][0]["project"], "project1");
+        assert_eq!(bundle["presets"][0]["name"], "preset1");
+        assert_eq!(
+            bundle["presets"][0]["tasks"][0]["description"],
+            "SomePresetTask"
+        );
+        assert_eq!(bundle["presets"][1]["name"], "preset1 (2)");
+    }
+
+    #[tokio::test]
+    async fn rate_limiting() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app_state = AppState::new(connection_factory);
+        app_state.rate_limiter = Some(Arc::new(RateLimiter::new(2)));
+        let mut app = build_app(app_state);
+
+        let mut import_nothing = async || {
+            app.call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/import/todotxt")
+                    .header(http::header::CONTENT_TYPE, mime::TEXT_PLAIN.as_ref())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+        };
+        assert_eq!(import_nothing().await, StatusCode::SEE_OTHER);
+        assert_eq!(import_nothing().await, StatusCode::SEE_OTHER);
+        assert_eq!(import_nothing().await, StatusCode::TOO_MANY_REQUESTS);
+
+        // Read-only requests are not limited
+        let response = app
+            .call(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn json_import() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/api/import")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(
+                        r#"[
+                            {"priority": "B", "description": "SomeTask", "project": "project1"},
+                            {"priority": "+", "description": "InvalidPriorityTask"},
+                            {"description": "SomeCompletedTask", "completed": true},
+                            {"priority": "A", "description": " "}
+                        ]"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let summary: serde_json::Value = serde_json::from_str(&parse_body(response).await).unwrap();
+        assert_eq!(summary["imported"], 2);
+        let skipped = summary["skipped"].as_array().unwrap();
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0]["index"], 1);
+        assert_eq!(skipped[0]["description"], "InvalidPriorityTask");
+        assert!(skipped[0]["error"].as_str().unwrap().contains('+'));
+        assert_eq!(skipped[1]["index"], 3);
+        assert_eq!(skipped[1]["error"], TaskError::EmptyDescription.to_string());
+
+        // Only valid tasks were imported, as given
+        let parsed_body = get_page_body(&mut app, "/?show=all").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(!parsed_body.contains("InvalidPriorityTask"));
+        let parsed_body = get_page_body(&mut app, "/export/todotxt").await;
+        assert_eq!(parsed_body, "(B) SomeTask +project1\nx SomeCompletedTask");
+    }
+
+    #[tokio::test]
+    async fn csv_export() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'B', "SomeTask", Some("project1")).await;
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/export/csv")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "text/csv; charset=utf-8"
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get(http::header::CONTENT_DISPOSITION)
+                .unwrap(),
+            "attachment; filename=\"tasks.csv\""
+        );
+        let parsed_body = parse_body(response).await;
+        assert!(parsed_body.contains("1,B,SomeTask,false,project1"));
+    }
+
+    #[tokio::test]
+    async fn reordering() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        add_new_task(&mut app, 'A', "SomeOtherTask", None).await;
+
+        // Alphabetical order by default
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.find("SomeOtherTask") < parsed_body.find("SomeTask"));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/move-up/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.find("SomeTask") < parsed_body.find("SomeOtherTask"));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/move-down/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.find("SomeOtherTask") < parsed_body.find("SomeTask"));
+    }
+
+    #[tokio::test]
+    async fn reorder() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        add_new_task(&mut app, 'A', "SomeOtherTask", None).await;
+        add_new_task(&mut app, 'A', "YetAnotherTask", None).await;
+
+        // Moving past the end puts the task last
+        for (form_text, expected_order) in [
+            (
+                "position=99",
+                ["SomeOtherTask", "YetAnotherTask", "SomeTask"],
+            ),
+            (
+                "position=0",
+                ["SomeTask", "SomeOtherTask", "YetAnotherTask"],
+            ),
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/reorder/1")
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(form_text))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+            assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+
+            let parsed_body = get_main_page_body(&mut app).await;
+            let positions: Vec<_> = expected_order
+                .iter()
+                .map(|description| parsed_body.find(description).unwrap())
+                .collect();
+            assert!(positions.is_sorted(), "{form_text}: {positions:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn set_project() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+
+        // Move the task into a project, then out of it
+        for (form_text, in_project) in [("project=project1", true), ("project=", false)] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/set-project/1")
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(form_text))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let parsed_body = parse_body(response).await;
+            assert!(parsed_body.contains("SomeTask"));
+            assert_eq!(parsed_body.contains("project1"), in_project);
+        }
+
+        // Unknown tasks cannot be moved
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/set-project/42")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("project=project1"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn set_completed() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+
+        // Only the first request actually changes the task
+        for expected_status in [StatusCode::OK, StatusCode::NO_CONTENT] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/set-completed/1")
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from("completed=true"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected_status);
+        }
+
+        let parsed_body = get_page_body(&mut app, "/?show=completed").await;
+        assert!(parsed_body.contains("SomeTask"));
+    }
+
+    #[tokio::test]
+    async fn stats() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'B', "SomeTask", Some("project1")).await;
+
+        let parsed_body = get_page_body(&mut app, "/stats").await;
+        assert!(parsed_body.contains("project1"));
+        assert!(parsed_body.contains("(B)"));
+    }
+
+    #[tokio::test]
+    async fn dependencies() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeBlocker", None).await;
+        add_new_task(&mut app, 'A', "SomeBlockedTask", None).await;
+        for (task_id, depends_on_id, expected_status) in [
+            (2, 1, StatusCode::SEE_OTHER),
+            (1, 2, StatusCode::BAD_REQUEST), // Would be a cycle
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri(format!("/task/{task_id}/add-dependency"))
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(format!("depends_on_id={depends_on_id}")))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected_status);
+        }
+
+        let parsed_body = get_page_body(&mut app, "/task/2").await;
+        assert!(parsed_body.contains("This task is blocked"));
+        assert!(parsed_body.contains("SomeBlocker"));
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert_eq!(parsed_body.matches("Blocked by pending tasks").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn tags() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", Some("project1")).await;
+        add_new_task(&mut app, 'B', "SomeOtherTask", Some("project1")).await;
+        add_new_task(&mut app, 'C', "SomeUnrelatedTask", Some("project2")).await;
+
+        for task_id in [1, 3] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri(format!("/task/{task_id}/add-tag"))
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from("tag=%40home"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        }
+
+        let parsed_body = get_page_body(&mut app, "/?tag=%40home").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(!parsed_body.contains("SomeOtherTask"));
+        assert!(parsed_body.contains("SomeUnrelatedTask"));
+
+        // Tag and project filters are combined
+        let parsed_body = get_page_body(&mut app, "/?tag=%40home&project=project1").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(!parsed_body.contains("SomeUnrelatedTask"));
+
+        // Only tags the task has may be removed
+        for expected_status in [StatusCode::SEE_OTHER, StatusCode::NOT_FOUND] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/task/1/remove-tag/%40home")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected_status);
+        }
+        let parsed_body = get_page_body(&mut app, "/?tag=%40home").await;
+        assert!(!parsed_body.contains(r#"value="SomeTask""#));
+        assert!(parsed_body.contains("SomeUnrelatedTask"));
+    }
+
+    #[tokio::test]
+    async fn recurring_task() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/add-new-task")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(
+                        "priority=A&description=SomeChore&due_date=2024-01-01&recurrence=daily",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        // Completing the task also returns the row of its next occurrence
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/flag-completed/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+        assert_eq!(parsed_body.matches("<tr").count(), 2);
+        assert!(parsed_body.contains("2024-01-02"));
+
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("SomeChore"));
+        assert!(parsed_body.contains("2024-01-02"));
+        assert!(!parsed_body.contains("2024-01-01"));
+
+        // The next occurrence can be moved to the following Monday, recurring weekly from there
+        for (form_text, status) in [
+            ("recurrence=weekly&weekday=monday", StatusCode::SEE_OTHER),
+            ("recurrence=yearly", StatusCode::UNPROCESSABLE_ENTITY),
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/task/2/set-recurrence")
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(form_text))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status);
+        }
+        let parsed_body = get_page_body(&mut app, "/task/2").await;
+        assert!(parsed_body.contains("This task recurs weekly."));
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("2024-01-08"));
+    }
+
+    #[tokio::test]
+    async fn missed_tasks() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let today = chrono::Local::now().date_naive();
+        for (description, due_date) in [
+            ("SomeLateTask", today - chrono::Days::new(3)),
+            ("SomeFutureTask", today + chrono::Days::new(1)),
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri("/add-new-task")
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(format!(
+                            "priority=A&description={description}&due_date={due_date}"
+                        )))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        }
+
+        let parsed_body = get_page_body(&mut app, "/missed").await;
+        assert!(parsed_body.contains("SomeLateTask"));
+        assert!(!parsed_body.contains("SomeFutureTask"));
+        assert!(parsed_body.contains("<td>3</td>"));
+    }
+
+    #[tokio::test]
+    async fn completed_between() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        add_new_task(&mut app, 'A', "SomeCompletedTask", None).await;
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/flag-completed/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let window = format!("from={}&to={}", now - 60, now + 60);
+        let parsed_body = get_page_body(&mut app, &format!("/api/completed?{window}")).await;
+        assert!(parsed_body.contains("SomeCompletedTask"));
+        assert!(!parsed_body.contains(r#""SomeTask""#));
+
+        // Windows have to be in order
+        let response = app
+            .call(
+                Request::builder()
+                    .uri(format!("/api/completed?from={}&to={}", now + 60, now - 60))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn cycle_time() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeCompletedTask", None).await;
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/flag-completed/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Only completed tasks are accounted for
+        let parsed_body = get_page_body(&mut app, "/api/cycle-time").await;
+        assert!(parsed_body.starts_with(r#"[["A","#));
+        assert!(!parsed_body.contains(r#""B""#));
+    }
+
+    #[tokio::test]
+    async fn project_progress() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeCompletedTask", Some("project1")).await;
+        add_new_task(&mut app, 'B', "SomeTask", Some("project1")).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/flag-completed/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let parsed_body = get_page_body(&mut app, "/api/project-progress").await;
+        assert_eq!(parsed_body, r#"[["project1",0.5]]"#);
+
+        let parsed_body = get_page_body(&mut app, "/api/board-counts").await;
+        assert_eq!(parsed_body, r#"[["project1",1,1]]"#);
+    }
+
+    #[tokio::test]
+    async fn report() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeWorkTask", Some("work")).await;
+        add_new_task(&mut app, 'B', "SomeHomeTask", Some("home")).await;
+
+        // Same filters as the main page
+        let parsed_body = get_page_body(&mut app, "/report?project=work").await;
+        assert!(parsed_body.contains("SomeWorkTask"));
+        assert!(!parsed_body.contains("SomeHomeTask"));
+        assert!(parsed_body.contains("Pending tasks in project"));
+        assert!(parsed_body.contains("sorted by priority"));
+        assert!(parsed_body.contains("Generated at "));
+
+        // Without anything interactive
+        assert!(!parsed_body.contains("<script"));
+        assert!(!parsed_body.contains("hx-"));
+
+        let parsed_body = get_page_body(&mut app, "/report?show=completed").await;
+        assert!(!parsed_body.contains("SomeWorkTask"));
+        assert!(parsed_body.contains("Completed tasks, sorted by priority"));
+    }
+
+    #[tokio::test]
+    async fn description_suggestions() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "BuyMilk", None).await;
+        add_new_task(&mut app, 'A', "BuyBread", None).await;
+        add_new_task(&mut app, 'A', "CallMom", None).await;
+
+        let parsed_body = get_page_body(&mut app, "/api/suggest?prefix=Buy").await;
+        assert_eq!(parsed_body, r#"["BuyBread","BuyMilk"]"#);
+        let parsed_body = get_page_body(&mut app, "/api/suggest?prefix=Buy&limit=1").await;
+        assert_eq!(parsed_body, r#"["BuyBread"]"#);
+    }
+
+    #[tokio::test]
+    async fn changes() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+        add_new_task(&mut app, 'A', "SomeCompletedTask", None).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/flag-completed/2")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let window = format!("from={}&to={}", now - 60, now + 60);
+        let parsed_body = get_page_body(&mut app, &format!("/api/changes?{window}")).await;
+        assert_eq!(
+            parsed_body,
+            r#"{"created":[1,2],"completed":[2],"deleted":[],"edited":[]}"#
+        );
+
+        // Windows have to be in order
+        let response = app
+            .call(
+                Request::builder()
+                    .uri(format!("/api/changes?from={}&to={}", now + 60, now - 60))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn default_due_date() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app_state = AppState::new(connection_factory.clone());
+        app_state.default_due_offset_days = Some(7);
+        let mut app = build_app(app_state);
+
+        // Tasks created without any due date get the default one
+        add_new_task(&mut app, 'B', "SomeDefaultDatedTask", None).await;
+        let expected_due_date = chrono::Local::now().date_naive() + chrono::Days::new(7);
+        let task = TaskRepo::new(connection_factory.clone())
+            .get_task(1)
+            .unwrap();
+        assert_eq!(task.due_date, Some(expected_due_date));
+
+        // Unless the due date is explicitly left empty
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/add-new-task")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(
+                        "priority=A&description=SomeUndatedTask&due_date=",
+                    ))
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let parsed_body = get_page_body(&mut app, "/undated").await;
+        assert!(parsed_body.contains("SomeUndatedTask"));
+        assert!(!parsed_body.contains("SomeDefaultDatedTask"));
+
+        // The default due date is suggested on the main page
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains(&format!("value=\"{expected_due_date}\"")));
+    }
+
+    #[tokio::test]
+    async fn undated_tasks() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
 
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'B', "SomeUndatedTask", None).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/add-new-task")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(
+                        "priority=A&description=SomeDatedTask&due_date=2026-01-01",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
         assert_eq!(response.status(), StatusCode::SEE_OTHER);
-        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+
+        // The due date is shown in the main page
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("2026-01-01"));
+
+        let parsed_body = get_page_body(&mut app, "/undated").await;
+        assert!(parsed_body.contains("SomeUndatedTask"));
+        assert!(!parsed_body.contains("SomeDatedTask"));
     }
 
-    async fn parse_body(response: Response<Body>) -> String {
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        String::from_utf8(body.to_vec()).unwrap()
+    #[tokio::test]
+    async fn tasks_by_priority() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
+        add_new_task(&mut app, 'A', "SomeOtherTask", None).await;
+        add_new_task(&mut app, 'C', "SomeMinorTask", None).await;
+
+        let parsed_body = get_page_body(&mut app, "/priority/A").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("SomeOtherTask"));
+        assert!(!parsed_body.contains("SomeMinorTask"));
     }
 
-    async fn get_main_page_body(app: &mut Router) -> String {
+    #[tokio::test]
+    async fn neglected_tasks() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'B', "SomeOldTask", Some("project1")).await;
+        add_new_task(&mut app, 'A', "SomeNewTask", Some("project1")).await;
+
+        let parsed_body = get_page_body(&mut app, "/neglected").await;
+        assert!(parsed_body.contains("project1"));
+        assert!(parsed_body.contains("SomeOldTask"));
+        assert!(!parsed_body.contains("SomeNewTask"));
+    }
+
+    #[tokio::test]
+    async fn task_notes() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+
+        // Add a note, shown in the task details
         let response = app
-            .call(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/task/1/add-note")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("body=SomeNote"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/task/1");
+
+        let parsed_body = get_page_body(&mut app, "/task/1").await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("SomeNote"));
+
+        // Then delete it, which only its own task may do
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/task/2/delete-note/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/task/1/delete-note/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let parsed_body = get_page_body(&mut app, "/task/1").await;
+        assert!(!parsed_body.contains("SomeNote"));
+
+        // Deleting it again finds nothing
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/task/1/delete-note/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-
-        parse_body(response).await
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn full_basic_flow() {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new().unwrap());
+    async fn subtasks() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
         TaskRepo::new(connection_factory.clone()).init_db().unwrap();
 
-        let mut app = build_app(AppState { connection_factory });
+        let mut app = build_app(AppState::new(connection_factory));
 
-        // Add new task
         add_new_task(&mut app, 'B', "SomeTask", None).await;
+        for (task_id, description, expected_status) in [
+            (1, "SomeSubtask", StatusCode::SEE_OTHER),
+            (1, "SomeOtherSubtask", StatusCode::SEE_OTHER),
+            (1, "+", StatusCode::BAD_REQUEST), // Blank
+            (42, "SomeSubtask", StatusCode::NOT_FOUND),
+        ] {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method(http::Method::POST)
+                        .uri(format!("/task/{task_id}/add-subtask"))
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                        )
+                        .body(Body::from(format!("description={description}")))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected_status);
+        }
 
-        // Ensure it appears in the output
-        let parsed_body = get_main_page_body(&mut app).await;
-        assert!(parsed_body.contains("(B)"));
-        assert!(parsed_body.contains("SomeTask"));
-
-        // Increase priority
+        // Completing a subtask returns the updated row of its task
         let response = app
             .call(
                 Request::builder()
                     .method(http::Method::POST)
-                    .uri("/increase-priority/1")
+                    .uri("/subtask/1/toggle")
                     .body(Body::empty())
                     .unwrap(),
             )
@@ -391,187 +4401,247 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
         let parsed_body = parse_body(response).await;
+        assert!(parsed_body.contains("SomeTask"));
+        assert!(parsed_body.contains("1/2"));
 
-        // Ensure priority was increased
-        assert!(!parsed_body.contains("(B)"));
-        assert!(parsed_body.contains("(A)"));
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("SomeOtherSubtask"));
+        assert!(parsed_body.contains("1/2"));
 
-        // Lower priority
         let response = app
             .call(
                 Request::builder()
                     .method(http::Method::POST)
-                    .uri("/lower-priority/1")
+                    .uri("/subtask/42/toggle")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let parsed_body = parse_body(response).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 
-        // Ensure priority was increased
-        assert!(!parsed_body.contains("(A)"));
-        assert!(parsed_body.contains("(B)"));
+    async fn restore(app: &mut Router, admin_token: Option<&str>, backup: Vec<u8>) -> StatusCode {
+        let mut request = Request::builder()
+            .method(http::Method::POST)
+            .uri("/admin/restore");
+        if let Some(admin_token) = admin_token {
+            request = request.header(AUTHORIZATION, format!("Bearer {admin_token}"));
+        }
 
-        // Flag as completed
         let response = app
-            .call(
-                Request::builder()
-                    .method(http::Method::POST)
-                    .uri("/flag-completed/1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .call(request.body(Body::from(backup)).unwrap())
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let parsed_body = parse_body(response).await;
+        response.status()
+    }
 
-        // Ensure task is flagged as completed
-        assert!(!parsed_body.contains("✓"));
-        assert!(parsed_body.contains("✗"));
+    #[tokio::test]
+    async fn restore_backup() {
+        // Prepare a backup from another instance
+        let other_connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        let mut other_task_repo = TaskRepo::new(other_connection_factory.clone());
+        other_task_repo.init_db().unwrap();
+        other_task_repo
+            .persist_task(&Task::new(&LETTERS, Some('A'), "SomeRestoredTask", None).unwrap())
+            .unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup_path =
+            crate::backup::backup_database(other_connection_factory.clone(), backup_dir.path(), 1)
+                .unwrap();
+        let backup = std::fs::read(backup_path).unwrap();
+
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+        let mut app_state = AppState::new(connection_factory);
+        app_state.admin_token = Some("secret".into());
+        let mut app = build_app(app_state);
+
+        add_new_task(&mut app, 'B', "SomeTask", None).await;
+
+        // Admin token is required
+        assert_eq!(
+            restore(&mut app, None, backup.clone()).await,
+            StatusCode::UNAUTHORIZED
+        );
+        for wrong_token in ["wrong", "secreT", "secret2"] {
+            assert_eq!(
+                restore(&mut app, Some(wrong_token), backup.clone()).await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+
+        // Corrupt uploads are rejected, and existing tasks are kept
+        assert_eq!(
+            restore(&mut app, Some("secret"), b"not a database".to_vec()).await,
+            StatusCode::BAD_REQUEST
+        );
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("SomeTask"));
+
+        // Valid backups replace all tasks
+        assert_eq!(
+            restore(&mut app, Some("secret"), backup).await,
+            StatusCode::SEE_OTHER
+        );
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("SomeRestoredTask"));
+        assert!(!parsed_body.contains("SomeTask"));
+    }
+
+    #[tokio::test]
+    async fn manual_backup() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        let mut app_state = AppState::new(connection_factory);
+        app_state.admin_token = Some("secret".into());
+        app_state.backup_config = Some(BackupConfig {
+            directory: backup_dir.path().into(),
+            interval: std::time::Duration::from_secs(3600),
+            retention: 1,
+        });
+        let mut app = build_app(app_state);
+
+        add_new_task(&mut app, 'A', "SomeTask", None).await;
 
-        // Flag as pending
         let response = app
             .call(
                 Request::builder()
                     .method(http::Method::POST)
-                    .uri("/flag-pending/1")
+                    .uri("/admin/backup")
+                    .header(AUTHORIZATION, "Bearer secret")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
-        let parsed_body = parse_body(response).await;
+        let backup_name: String = serde_json::from_str(&parse_body(response).await).unwrap();
 
-        // Ensure task is flagged as pending
-        assert!(!parsed_body.contains("✗"));
-        assert!(parsed_body.contains("✓"));
+        // The backup is a copy of the database
+        let backup_conn = rusqlite::Connection::open(backup_dir.path().join(backup_name)).unwrap();
+        let description: String = backup_conn
+            .query_row("SELECT description FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(description, "SomeTask");
+    }
+
+    #[tokio::test]
+    async fn manual_backup_disabled_by_default() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+        let mut app_state = AppState::new(connection_factory);
+        app_state.admin_token = Some("secret".into());
+        let mut app = build_app(app_state);
 
-        // Update description
         let response = app
             .call(
                 Request::builder()
                     .method(http::Method::POST)
-                    .uri("/update-description/1")
-                    .header(
-                        http::header::CONTENT_TYPE,
-                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
-                    )
-                    .body(Body::from("task_description=SomeNewTask"))
+                    .uri("/admin/backup")
+                    .header(AUTHORIZATION, "Bearer secret")
+                    .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let parsed_body = parse_body(response).await;
-
-        // Body empty for this request as there is no need for replacement
-        assert_eq!(parsed_body.len(), 0);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn task_cleanup() {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new().unwrap());
+    async fn summarize_invalid_month() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
         TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+        let mut app_state = AppState::new(connection_factory);
+        app_state.admin_token = Some("secret".into());
+        let mut app = build_app(app_state);
 
-        let mut app = build_app(AppState { connection_factory });
-
-        // Add new task
-        add_new_task(&mut app, 'B', "SomeTask", None).await;
-        add_new_task(&mut app, 'A', "SomeImportantTask", None).await;
-        add_new_task(&mut app, 'C', "SomeNotImportantTask", None).await;
-
-        // Flag some of them as completed
-        for i in 1..=2 {
+        for form in ["year=2024&month=13", "year=262143&month=12"] {
             let response = app
                 .call(
                     Request::builder()
                         .method(http::Method::POST)
-                        .uri(format!("/flag-completed/{i}"))
-                        .body(Body::empty())
+                        .uri("/admin/summarize-month")
+                        .header(AUTHORIZATION, "Bearer secret")
+                        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                        .body(Body::from(form))
                         .unwrap(),
                 )
                 .await
                 .unwrap();
-            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         }
+    }
 
-        // Ensure they are still in the main page
-        let parsed_body = get_main_page_body(&mut app).await;
-        assert!(parsed_body.contains("SomeTask"));
-        assert!(parsed_body.contains("SomeImportantTask"));
-        assert!(parsed_body.contains("SomeNotImportantTask"));
+    #[tokio::test]
+    async fn schema() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut state = AppState::new(connection_factory);
+        state.admin_token = Some("secret".into());
+        let mut app = build_app(state);
 
-        // Trigger cleanup
         let response = app
             .call(
                 Request::builder()
-                    .method(http::Method::POST)
-                    .uri("/task-cleanup")
+                    .uri("/admin/schema")
+                    .header(AUTHORIZATION, "Bearer secret")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::SEE_OTHER);
-        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
-
-        // Ensure they have been deleted
-        let parsed_body = get_main_page_body(&mut app).await;
-        assert!(!parsed_body.contains("SomeTask")); // Completed => removed
-        assert!(!parsed_body.contains("SomeImportantTask")); // Completed => removed
-        assert!(parsed_body.contains("SomeNotImportantTask")); // Pending => kept
-    }
-
-    #[tokio::test]
-    async fn tasks_and_projects() {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new().unwrap());
-        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
-
-        let mut app = build_app(AppState { connection_factory });
-
-        // Add new task with or without projects
-        add_new_task(&mut app, 'B', "SomeTask", None).await;
-        add_new_task(&mut app, 'B', "SomeOtherTask", Some("project1")).await;
-
-        // Ensure it appears in the output
-        let parsed_body = get_main_page_body(&mut app).await;
-        assert!(parsed_body.contains("project1"));
+        assert_eq!(response.status(), StatusCode::OK);
+        let parsed_body = parse_body(response).await;
+        assert!(parsed_body.contains("CREATE TABLE tasks"));
+        for column in [
+            "priority",
+            "description",
+            "completed",
+            "project",
+            "due_date",
+        ] {
+            assert!(
+                parsed_body.contains(column),
+                "Column {column} should be listed"
+            );
+        }
 
-        // Rename project
+        // Like other admin endpoints, the token is required
         let response = app
             .call(
                 Request::builder()
-                    .method(http::Method::POST)
-                    .uri("/rename-project")
-                    .header(
-                        http::header::CONTENT_TYPE,
-                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
-                    )
-                    .body(Body::from(
-                        "current_project_name=project1&new_project_name=project2",
-                    ))
+                    .uri("/admin/schema")
+                    .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::SEE_OTHER);
-        assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
 
-        // Ensure new name appears in the output
-        let parsed_body = get_main_page_body(&mut app).await;
-        assert!(parsed_body.contains("project2"));
+    #[tokio::test]
+    async fn admin_endpoints_disabled_by_default() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+
+        assert_eq!(
+            restore(&mut app, Some(""), vec![]).await,
+            StatusCode::FORBIDDEN
+        );
     }
 
     #[tokio::test]
     async fn presets() {
-        let connection_factory = Arc::new(TempDirSqliteConnectionFactory::new().unwrap());
-        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        let mut task_repo = TaskRepo::new(connection_factory.clone());
+        task_repo.init_db().unwrap();
 
-        let mut app = build_app(AppState { connection_factory });
+        let mut app = build_app(AppState::new(connection_factory));
 
         // Add new preset
         let form_text: String = "preset_name=preset1".to_string();
@@ -660,5 +4730,181 @@ mod tests {
         // And now the task should be injected
         let parsed_body = get_main_page_body(&mut app).await;
         assert!(parsed_body.contains("my_new_description"));
+
+        // With its source preset remembered
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/task/1/source-preset")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(parse_body(response).await, "\"preset1\"");
+
+        // Injecting the preset again does not duplicate pending tasks
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/preset/preset1/inject")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Unless forced
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/preset/preset1/inject?force=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            task_repo
+                .get_all_tasks(
+                    &TaskFilter {
+                        hidden: Some(false),
+                        ..Default::default()
+                    },
+                    SortBy::Priority,
+                    None
+                )
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Rename the preset
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/preset/preset1/rename")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from("new_name=preset2"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get(LOCATION).unwrap(), "/preset/preset2");
+        let parsed_body = get_page_body(&mut app, "/preset/preset2").await;
+        assert!(parsed_body.contains("my_new_description"));
+
+        // Add several preset tasks at once
+        let response = app
+            .call(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/preset/preset2/add-many")
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                    )
+                    .body(Body::from(
+                        "task_priority=C&task_descriptions=first_bulk_task%0D%0A%0D%0Asecond_bulk_task",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let preset = task_repo.get_preset("preset2").unwrap();
+        assert_eq!(preset.tasks.len(), 3);
+        let parsed_body = get_page_body(&mut app, "/preset/preset2").await;
+        assert!(parsed_body.contains("first_bulk_task"));
+        assert!(parsed_body.contains("second_bulk_task"));
+    }
+
+    #[test]
+    fn markdown_descriptions() {
+        assert_eq!(
+            markdownify("Read **bold** and _emphasized_ [text](https://example.com)").as_str(),
+            Some(
+                "Read <strong>bold</strong> and <em>emphasized</em> \
+                 <a href=\"https://example.com\">text</a>"
+            )
+        );
+
+        // Descriptions cannot inject anything
+        assert_eq!(
+            markdownify("<script>alert(1)</script>").as_str(),
+            Some("&lt;script&gt;alert(1)&lt;/script&gt;")
+        );
+        assert_eq!(
+            markdownify("[Click](JavaScript:alert(1))").as_str(),
+            Some("<a href=\"\">Click</a>")
+        );
+
+        assert!(is_markdown("Read **bold**"));
+        assert!(!is_markdown("Buy 2 * 3 eggs"));
+    }
+
+    #[tokio::test]
+    async fn markdown_task_description() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+        add_new_task(&mut app, 'A', "Read **bold** book", None).await;
+
+        // Rendered in pages, next to the source to edit
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("Read <strong>bold</strong> book"));
+        assert!(parsed_body.contains("value=\"Read **bold** book\""));
+
+        // But kept as is by the API
+        let parsed_body = get_page_body(&mut app, "/api/suggest?prefix=Read").await;
+        assert_eq!(parsed_body, r#"["Read **bold** book"]"#);
+    }
+
+    #[tokio::test]
+    async fn user_input_is_escaped() {
+        let connection_factory = Arc::new(InMemorySqliteConnectionFactory::new().unwrap());
+        TaskRepo::new(connection_factory.clone()).init_db().unwrap();
+
+        let mut app = build_app(AppState::new(connection_factory));
+        add_new_task(
+            &mut app,
+            'A',
+            "<script>alert(1)</script>",
+            Some("<script>alert(2)</script>"),
+        )
+        .await;
+
+        for uri in ["/", "/task/1", "/report"] {
+            let parsed_body = get_page_body(&mut app, uri).await;
+            assert!(parsed_body.contains("&lt;script&gt;alert(1)&lt;"), "{uri}");
+            assert!(!parsed_body.contains("<script>alert"), "{uri}");
+        }
+        let parsed_body = get_main_page_body(&mut app).await;
+        assert!(parsed_body.contains("&lt;script&gt;alert(2)&lt;"));
     }
 }
@@ -7,8 +7,8 @@ const taskDescription = "Some test task " + crypto.randomBytes(5).toString('hex'
 test.describe('basic features', () => {
 
   test.beforeEach(async ({ page }) => {
-    //Always go to the home page first
-    await page.goto(rootUrl);
+    //Always go to the home page first, completed tasks included
+    await page.goto(rootUrl + "?show=all");
   });
 
 